@@ -0,0 +1,145 @@
+use std::thread::JoinHandle;
+
+use windows::{
+    Win32::{
+        Foundation::{CloseHandle, ERROR_SUCCESS, HANDLE, WAIT_OBJECT_0},
+        System::{
+            Registry::{
+                HKEY, HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE, KEY_NOTIFY, KEY_QUERY_VALUE,
+                REG_NOTIFY_CHANGE_LAST_SET, RRF_RT_REG_DWORD, RRF_RT_REG_SZ, RegCloseKey,
+                RegGetValueW, RegNotifyChangeKeyValue, RegOpenKeyExW,
+            },
+            Threading::{CreateEventW, INFINITE, WaitForSingleObject},
+        },
+    },
+    core::{PCWSTR, w},
+};
+
+use crate::komo::BarTargets;
+use crate::msgs::TaskbarAlignment;
+
+/// Explorer settings, among them the taskbar alignment
+const ADVANCED_KEY: PCWSTR = w!(r"Software\Microsoft\Windows\CurrentVersion\Explorer\Advanced");
+/// 0 with the Start button at the left, 1 with it centered along with the app
+/// buttons; missing until the user changes it, and on Windows 10
+const ALIGNMENT_VALUE: PCWSTR = w!("TaskbarAl");
+
+/// Holds the build number of the running Windows, which unlike
+/// `GetVersionEx` doesn't depend on the application manifest
+const VERSION_KEY: PCWSTR = w!(r"SOFTWARE\Microsoft\Windows NT\CurrentVersion");
+const BUILD_VALUE: PCWSTR = w!("CurrentBuildNumber");
+/// First release of Windows 11, whose taskbar centers its buttons by default
+const WINDOWS_11_BUILD: u32 = 22000;
+
+/// Build number of the running Windows, e.g. 22631.
+fn windows_build() -> Option<u32> {
+    let mut value = [0u16; 16];
+    let mut size = size_of_val(&value) as u32;
+    let result = unsafe {
+        RegGetValueW(
+            HKEY_LOCAL_MACHINE,
+            VERSION_KEY,
+            BUILD_VALUE,
+            RRF_RT_REG_SZ,
+            None,
+            Some(value.as_mut_ptr() as _),
+            Some(&mut size),
+        )
+    };
+    if result != ERROR_SUCCESS {
+        return None;
+    }
+    let len = value.iter().position(|c| *c == 0).unwrap_or(value.len());
+    String::from_utf16_lossy(&value[..len]).parse().ok()
+}
+
+/// Whether the taskbar centers its buttons. Without a setting that is
+/// `centered_by_default`, true only on Windows 11.
+fn is_centered(key: HKEY, centered_by_default: bool) -> bool {
+    let mut value = 0u32;
+    let mut size = size_of::<u32>() as u32;
+    let result = unsafe {
+        RegGetValueW(
+            key,
+            PCWSTR::null(),
+            ALIGNMENT_VALUE,
+            RRF_RT_REG_DWORD,
+            None,
+            Some(&mut value as *mut u32 as _),
+            Some(&mut size),
+        )
+    };
+    if result != ERROR_SUCCESS {
+        return centered_by_default;
+    }
+    value != 0
+}
+
+/// Waits for the next change to `key`, returning false if it can't.
+fn wait_for_change(key: HKEY, event: HANDLE) -> bool {
+    let result = unsafe {
+        RegNotifyChangeKeyValue(key, false, REG_NOTIFY_CHANGE_LAST_SET, Some(event), true)
+    };
+    if let Err(err) = result.ok() {
+        log::error!("Failed to watch the taskbar alignment: {err}");
+        return false;
+    }
+    unsafe { WaitForSingleObject(event, INFINITE) == WAIT_OBJECT_0 }
+}
+
+/// Tells the windows whenever the taskbar's buttons move between the left and
+/// the center, which changes the free space next to the Start button.
+pub fn start_watching_taskbar_alignment(bars: BarTargets) -> JoinHandle<()> {
+    std::thread::spawn(move || {
+        let mut key = HKEY::default();
+        let result = unsafe {
+            RegOpenKeyExW(
+                HKEY_CURRENT_USER,
+                ADVANCED_KEY,
+                None,
+                KEY_NOTIFY | KEY_QUERY_VALUE,
+                &mut key,
+            )
+        };
+        if let Err(err) = result.ok() {
+            log::warn!("Not watching the taskbar alignment: {err}");
+            return;
+        }
+        let event = match unsafe { CreateEventW(None, false, false, None) } {
+            Ok(event) => event,
+            Err(err) => {
+                log::error!("Failed to watch the taskbar alignment: {err}");
+                let _ = unsafe { RegCloseKey(key) };
+                return;
+            }
+        };
+
+        let centered_by_default = windows_build().is_some_and(|build| build >= WINDOWS_11_BUILD);
+        let mut centered = is_centered(key, centered_by_default);
+        // any other Explorer setting changes the key as well
+        while wait_for_change(key, event) {
+            let now_centered = is_centered(key, centered_by_default);
+            if now_centered == centered {
+                continue;
+            }
+            centered = now_centered;
+            log::info!(
+                "Taskbar {}",
+                if centered { "centered" } else { "aligned left" }
+            );
+            for bar in bars.get() {
+                unsafe {
+                    bar.hwnd
+                        .get()
+                        .PostMessage(TaskbarAlignment::to_wndmsg(centered))
+                        .ok();
+                }
+            }
+        }
+
+        unsafe {
+            CloseHandle(event).ok();
+            let _ = RegCloseKey(key);
+        }
+    })
+}
@@ -0,0 +1,881 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Bumped whenever the config layout changes, together with a new entry in
+/// `MIGRATIONS`.
+pub const CONFIG_VERSION: u32 = 3;
+
+type Migration = fn(&mut Map<String, Value>);
+
+/// `MIGRATIONS[n]` upgrades a version `n + 1` config to version `n + 2`.
+const MIGRATIONS: &[Migration] = &[
+    // 1 -> 2: configs written before versioning only lack the `version` field
+    |_| {},
+    // 2 -> 3: `ctrl_click_follows` became `send_follows`, which covers every
+    // way the bar sends a window
+    |document| {
+        fold_ctrl_click_follows(document);
+        if let Some(Value::Object(profiles)) = document.get_mut("profiles") {
+            for profile in profiles.values_mut().filter_map(Value::as_object_mut) {
+                fold_ctrl_click_follows(profile);
+            }
+        }
+    },
+];
+
+fn fold_ctrl_click_follows(options: &mut Map<String, Value>) {
+    if options.remove("ctrl_click_follows") == Some(Value::Bool(true)) {
+        options.insert("send_follows".to_string(), true.into());
+    }
+}
+
+const _: () = assert!(MIGRATIONS.len() == CONFIG_VERSION as usize - 1);
+
+/// How workspace buttons are labelled in the bar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DisplayMode {
+    /// Workspace names, falling back to their number
+    #[default]
+    Full,
+    /// Workspace numbers only
+    Compact,
+    /// State indicators only, without any label
+    Icon,
+}
+
+/// How the bar appears when komoswitch starts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StartupVisibility {
+    #[default]
+    Visible,
+    /// Only the tray icon is shown until the bar is toggled
+    Hidden,
+    /// Only the focused workspace is shown until the bar is toggled or clicked
+    Minimized,
+}
+
+/// What the container boxes of the scrolling layout indicator show
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContainerLabels {
+    /// Position of the container in the workspace
+    #[default]
+    Index,
+    /// First letter of the title of the container's focused window, or its
+    /// glyph from `container_glyphs`
+    Initial,
+}
+
+/// When resting the cursor on a workspace button switches to it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HoverSwitch {
+    #[default]
+    Off,
+    /// Only while a mouse button is held, e.g. dragging a file between apps
+    Drag,
+    Always,
+}
+
+/// When the bar cuts down on animations and repaints to save power
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerSaving {
+    /// While Windows' battery saver is on
+    #[default]
+    BatterySaver,
+    /// Whenever the device runs on battery
+    OnBattery,
+    Never,
+}
+
+/// How the container pager is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PagerStyle {
+    /// Labelled boxes for the focused container and its neighbors
+    #[default]
+    Boxes,
+    /// A small dot per container
+    Dots,
+}
+
+/// A global hotkey such as `ctrl+alt+w`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hotkey {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    pub win: bool,
+    /// Windows virtual-key code
+    pub key: u16,
+}
+
+const NAMED_KEYS: &[(&str, u16)] = &[
+    ("space", 0x20),
+    ("tab", 0x09),
+    ("enter", 0x0D),
+    ("escape", 0x1B),
+    ("left", 0x25),
+    ("up", 0x26),
+    ("right", 0x27),
+    ("down", 0x28),
+];
+
+impl FromStr for Hotkey {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let mut hotkey = Self {
+            ctrl: false,
+            alt: false,
+            shift: false,
+            win: false,
+            key: 0,
+        };
+
+        for part in s.split('+').map(|part| part.trim().to_ascii_lowercase()) {
+            match part.as_str() {
+                "ctrl" | "control" => hotkey.ctrl = true,
+                "alt" => hotkey.alt = true,
+                "shift" => hotkey.shift = true,
+                "win" | "super" => hotkey.win = true,
+                key if hotkey.key != 0 => {
+                    anyhow::bail!("Hotkey {s:?} has more than one key: {key}")
+                }
+                key if key.len() == 1 && key.chars().all(|c| c.is_ascii_alphanumeric()) => {
+                    hotkey.key = key.to_ascii_uppercase().as_bytes()[0] as u16;
+                }
+                key => {
+                    let function_key = key.strip_prefix('f').and_then(|n| n.parse::<u16>().ok());
+                    hotkey.key = match function_key {
+                        Some(n @ 1..=24) => 0x70 + n - 1,
+                        _ => NAMED_KEYS
+                            .iter()
+                            .find(|(name, _)| *name == key)
+                            .map(|(_, vk)| *vk)
+                            .with_context(|| format!("Unknown key {key:?} in hotkey {s:?}"))?,
+                    };
+                }
+            }
+        }
+
+        if hotkey.key == 0 {
+            anyhow::bail!("Hotkey {s:?} has no key");
+        }
+        Ok(hotkey)
+    }
+}
+
+impl std::fmt::Display for Hotkey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (enabled, name) in [
+            (self.ctrl, "ctrl+"),
+            (self.alt, "alt+"),
+            (self.shift, "shift+"),
+            (self.win, "win+"),
+        ] {
+            if enabled {
+                f.write_str(name)?;
+            }
+        }
+
+        match self.key {
+            0x70..=0x87 => write!(f, "f{}", self.key - 0x70 + 1),
+            key => match NAMED_KEYS.iter().find(|(_, vk)| *vk == key) {
+                Some((name, _)) => f.write_str(name),
+                None => write!(f, "{}", (key as u8 as char).to_ascii_lowercase()),
+            },
+        }
+    }
+}
+
+impl Serialize for Hotkey {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Hotkey {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// A `#rrggbb` color
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HexColor {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl FromStr for HexColor {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let hex = s
+            .strip_prefix('#')
+            .filter(|hex| hex.len() == 6 && hex.is_ascii())
+            .with_context(|| format!("Expected a #rrggbb color, got {s:?}"))?;
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16);
+
+        Ok(Self {
+            r: channel(0)?,
+            g: channel(2)?,
+            b: channel(4)?,
+        })
+    }
+}
+
+impl std::fmt::Display for HexColor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
+
+impl Serialize for HexColor {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for HexColor {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Weight of a font
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FontWeight {
+    Light,
+    Normal,
+    Medium,
+    Semibold,
+    Bold,
+}
+
+/// Font of the focused workspace's label where it differs from the others
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FontStyle {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub weight: Option<FontWeight>,
+    /// Height in pixels at 96 DPI
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<i32>,
+}
+
+/// What is drawn behind text to keep it readable over a busy or light taskbar
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextEffect {
+    #[default]
+    None,
+    /// 1px outline all around the text
+    Outline,
+    /// 1px shadow below and to the right of the text
+    Shadow,
+}
+
+/// Shape of a workspace state indicator
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IndicatorStyle {
+    /// Bar below the label
+    #[default]
+    Underline,
+    /// Rounded background behind the whole button
+    Pill,
+    /// Small dot below the label
+    Dot,
+    /// Bar along the left edge of the button
+    EdgeBar,
+}
+
+/// Which end of the taskbar the bar is placed from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Anchor {
+    #[default]
+    Left,
+    Center,
+    /// Just past the clock, or at the far end of a taskbar without one
+    RightOfClock,
+    /// Just before the Start button and app icons Windows 11 centers, moving
+    /// as they come and go; like `Left` where there are none
+    Auto,
+}
+
+/// Where the bar sits within the taskbar, or on the screen while standalone
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Placement {
+    pub anchor: Anchor,
+    /// Offset along the taskbar from the anchor, in pixels at 96 DPI
+    pub x: i32,
+    /// Offset across the taskbar from its top, in pixels at 96 DPI
+    pub y: i32,
+    /// Keep clear of the Windows 11 Start and widgets buttons too, which
+    /// takes UI Automation; `Auto` uses it regardless to find the app icons
+    pub check_overlap: bool,
+}
+
+impl Default for Placement {
+    fn default() -> Self {
+        Self {
+            anchor: Anchor::Left,
+            x: 15,
+            y: 0,
+            check_overlap: false,
+        }
+    }
+}
+
+/// How the mouse wheel switches workspaces, or containers over the pager
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScrollOptions {
+    /// Wheel notches per switch
+    pub notches: u32,
+    /// Scrolling down goes to the previous workspace instead of the next
+    pub reverse: bool,
+    /// Scrolling past the last workspace continues at the first and back
+    pub wrap: bool,
+}
+
+impl Default for ScrollOptions {
+    fn default() -> Self {
+        Self {
+            notches: 1,
+            reverse: false,
+            wrap: true,
+        }
+    }
+}
+
+/// What clicking a workspace button does
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClickAction {
+    None,
+    Focus,
+    /// Focuses the workspace that was focused before the current one
+    PreviousWorkspace,
+    /// Sends the focused window to the workspace
+    SendWindow,
+    /// Sends the focused window to the workspace and follows it
+    MoveWindow,
+    /// Focuses the workspace and toggles monocle on it
+    ToggleMonocle,
+    /// Runs the `CustomCommand` of that name
+    Command(String),
+}
+
+/// Single and double click actions of a kind of button. A single click waits
+/// out the double-click time only when there is a double-click action.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClickBinding {
+    pub single: ClickAction,
+    pub double: ClickAction,
+}
+
+/// Click actions of the focused workspace's button and of all others
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClickActions {
+    pub workspace: ClickBinding,
+    pub focused: ClickBinding,
+}
+
+impl Default for ClickActions {
+    fn default() -> Self {
+        Self {
+            workspace: ClickBinding {
+                single: ClickAction::Focus,
+                double: ClickAction::None,
+            },
+            focused: ClickBinding {
+                single: ClickAction::PreviousWorkspace,
+                double: ClickAction::ToggleMonocle,
+            },
+        }
+    }
+}
+
+/// A komorebi command of the user's own, for what the bar has no option of
+/// its own
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CustomCommand {
+    /// Names the command in click actions, the context menu and the palette
+    pub name: String,
+    /// The `SocketMessage` to send, as komorebi reads it from its socket,
+    /// e.g. `{"type": "CycleFocusMonitor", "content": "Next"}`
+    pub message: Value,
+    #[serde(default)]
+    pub hotkey: Option<Hotkey>,
+    /// Lists the command in the context menu
+    #[serde(default)]
+    pub menu: bool,
+}
+
+/// Indicator style of each workspace state
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IndicatorStyles {
+    pub empty: IndicatorStyle,
+    pub nonempty: IndicatorStyle,
+    pub focused: IndicatorStyle,
+}
+
+/// Text drawn as the workspace state indicator instead of the colored bars,
+/// e.g. `○`, `◉` and `●`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateGlyphs {
+    pub empty: String,
+    pub nonempty: String,
+    pub focused: String,
+}
+
+/// Rounded, usually translucent background behind the whole bar
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Capsule {
+    /// Defaults to a color contrasting with the text
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<HexColor>,
+    /// From 0 (invisible) to 1 (opaque)
+    pub opacity: f32,
+    /// In pixels at 96 DPI
+    pub corner_radius: i32,
+}
+
+impl Default for Capsule {
+    fn default() -> Self {
+        Self {
+            color: None,
+            opacity: 0.5,
+            corner_radius: 8,
+        }
+    }
+}
+
+/// Colors replacing the ones derived from the Windows theme
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ColorOverrides {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonempty: Option<HexColor>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub focused: Option<HexColor>,
+    /// Second color of the focused indicator when `focused_gradient` is on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub focused_end: Option<HexColor>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub empty: Option<HexColor>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub monocle: Option<HexColor>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub maximized: Option<HexColor>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning: Option<HexColor>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hover: Option<HexColor>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub foreground: Option<HexColor>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text_effect: Option<HexColor>,
+}
+
+/// Options in effect, i.e. the top-level options of the config file with the
+/// active profile applied on top.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub version: u32,
+    pub display_mode: DisplayMode,
+    /// Shows a bar on every monitor's taskbar with that monitor's workspaces,
+    /// instead of one bar following the focused monitor; read at startup
+    pub per_monitor_bars: bool,
+    /// Shows the workspaces of every monitor in the one bar, grouped by
+    /// monitor; read at startup
+    pub all_monitors: bool,
+    /// Executables (e.g. `obs64.exe`) that put the bar into quiet mode while running
+    pub presentation_processes: Vec<String>,
+    pub colors: ColorOverrides,
+    /// Space around workspace labels and state text, in pixels at 96 DPI
+    pub padding: i32,
+    pub text_effect: TextEffect,
+    pub capsule: Option<Capsule>,
+    pub placement: Placement,
+    pub focused_font: FontStyle,
+    pub startup: StartupVisibility,
+    /// Shows a notification area icon; always on when starting hidden
+    pub tray_icon: bool,
+    /// Pops up a notification when komorebi goes away and when it is back
+    pub connection_notifications: bool,
+    /// Leaves the bar out of screenshots, recordings and streams. Only works
+    /// for the standalone bar, Windows can't exclude it inside the taskbar
+    pub exclude_from_capture: bool,
+    /// Cuts down on animations and repaints to make the battery last longer
+    pub power_saving: PowerSaving,
+    /// Language of the menus, chips and tooltips, e.g. `de`; the Windows
+    /// display language when unset
+    pub locale: Option<String>,
+    /// Shows or hides the bar from anywhere
+    pub toggle_hotkey: Option<Hotkey>,
+    /// Opens the command palette from anywhere
+    pub palette_hotkey: Option<Hotkey>,
+    /// Opens a search over the windows on every workspace from anywhere
+    pub window_search_hotkey: Option<Hotkey>,
+    /// Moves the keyboard focus to the bar to pick a workspace with the arrows
+    pub navigate_hotkey: Option<Hotkey>,
+    /// Draws the icons of each workspace's windows inside its button
+    pub app_icons: bool,
+    /// Icons per workspace button before the rest are summarized as "+N"
+    pub max_app_icons: usize,
+    /// Adds a trailing "+" button that creates and focuses a new workspace
+    pub new_workspace_button: bool,
+    pub indicator_styles: IndicatorStyles,
+    /// Fills the focused indicator with a gradient from the `focused` to the
+    /// `focused_end` color
+    pub focused_gradient: bool,
+    pub state_glyphs: Option<StateGlyphs>,
+    /// Sends windows dragged by their title bar and dropped on a workspace
+    /// button to that workspace
+    pub drag_to_workspace: bool,
+    pub hover_switch: HoverSwitch,
+    /// How long the cursor rests on a button before `hover_switch` kicks in
+    pub hover_switch_delay_ms: u32,
+    pub click_actions: ClickActions,
+    pub custom_commands: Vec<CustomCommand>,
+    pub scroll: ScrollOptions,
+    /// Follows the focused window whenever the bar sends it to a workspace,
+    /// by Ctrl+click, a `send_window` click action or dropping it on a button
+    pub send_follows: bool,
+    /// Lists the titles of a workspace's windows while hovering its button
+    pub window_tooltips: bool,
+    /// Shows whether the focused workspace's layer is tiling or floating
+    pub layer_indicator: bool,
+    /// Shows the name of the focused workspace's layout
+    pub layout_indicator: bool,
+    /// Shows the title of the focused window after the workspace buttons
+    pub window_title: bool,
+    /// Longest window title shown before it is truncated
+    pub max_title_chars: usize,
+    /// Shows how many windows each non-empty workspace contains
+    pub window_count_badges: bool,
+    /// Counts the focused workspace's floating and minimized windows
+    pub hidden_window_counts: bool,
+    /// Mirrors the bar while the Windows locale reads right to left
+    pub mirror_rtl: bool,
+    pub pager_style: PagerStyle,
+    /// Draws the container pager for every layout, not only scrolling ones
+    pub pager_all_layouts: bool,
+    /// Containers shown on each side of the focused one in the boxes pager
+    pub pager_neighbors: usize,
+    pub container_labels: ContainerLabels,
+    /// Glyphs by executable (e.g. `firefox.exe`) used instead of title initials
+    pub container_glyphs: HashMap<String, String>,
+    /// Notifications from komorebi larger than this are logged as anomalies
+    pub warn_notification_bytes: usize,
+    /// More notifications from komorebi per second than this are logged as anomalies
+    pub warn_notifications_per_second: u32,
+    /// Seconds between full reads of komorebi's state catching up on missed
+    /// notifications, 0 to only follow notifications
+    pub resync_interval_secs: u64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            version: CONFIG_VERSION,
+            display_mode: DisplayMode::default(),
+            per_monitor_bars: false,
+            all_monitors: false,
+            presentation_processes: vec![
+                "obs64.exe".to_string(),
+                "POWERPNT.EXE".to_string(),
+                "PowerToys.Awake.exe".to_string(),
+            ],
+            colors: ColorOverrides::default(),
+            padding: 20,
+            text_effect: TextEffect::default(),
+            capsule: None,
+            placement: Placement::default(),
+            focused_font: FontStyle::default(),
+            startup: StartupVisibility::default(),
+            tray_icon: false,
+            connection_notifications: false,
+            exclude_from_capture: false,
+            power_saving: PowerSaving::default(),
+            locale: None,
+            toggle_hotkey: None,
+            palette_hotkey: None,
+            window_search_hotkey: None,
+            navigate_hotkey: None,
+            app_icons: false,
+            max_app_icons: 3,
+            drag_to_workspace: false,
+            hover_switch: HoverSwitch::default(),
+            hover_switch_delay_ms: 400,
+            click_actions: ClickActions::default(),
+            custom_commands: Vec::new(),
+            scroll: ScrollOptions::default(),
+            send_follows: false,
+            window_tooltips: false,
+            layer_indicator: false,
+            layout_indicator: false,
+            window_title: false,
+            max_title_chars: 40,
+            new_workspace_button: false,
+            indicator_styles: IndicatorStyles::default(),
+            focused_gradient: false,
+            state_glyphs: None,
+            window_count_badges: false,
+            hidden_window_counts: false,
+            mirror_rtl: false,
+            pager_style: PagerStyle::default(),
+            pager_all_layouts: false,
+            pager_neighbors: 1,
+            container_labels: ContainerLabels::default(),
+            container_glyphs: HashMap::new(),
+            warn_notification_bytes: 1024 * 1024,
+            warn_notifications_per_second: 50,
+            resync_interval_secs: 0,
+        }
+    }
+}
+
+/// Recursively replaces the values in `base` with the ones in `overlay`.
+fn merge(base: &mut Value, overlay: &Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay.clone(),
+    }
+}
+
+/// The config file as written by the user: top-level options plus named
+/// `profiles`, each a partial set of options layered over the top-level ones
+/// while it is the `active_profile`.
+pub struct ConfigFile {
+    document: Map<String, Value>,
+}
+
+impl Default for ConfigFile {
+    fn default() -> Self {
+        let mut document = Map::new();
+        document.insert("version".to_string(), CONFIG_VERSION.into());
+        Self { document }
+    }
+}
+
+impl ConfigFile {
+    /// `%KOMOSWITCH_CONFIG%`, or `%USERPROFILE%\.config\komoswitch\config.json`
+    pub fn path() -> Option<PathBuf> {
+        if let Some(path) = std::env::var_os("KOMOSWITCH_CONFIG") {
+            return Some(PathBuf::from(path));
+        }
+
+        let home = std::env::var_os("USERPROFILE")?;
+        Some(
+            PathBuf::from(home)
+                .join(".config")
+                .join("komoswitch")
+                .join("config.json"),
+        )
+    }
+
+    pub fn load() -> Self {
+        Self::try_load().unwrap_or_else(|err| {
+            log::error!("Failed to load config, using defaults: {err}");
+            Self::default()
+        })
+    }
+
+    fn try_load() -> anyhow::Result<Self> {
+        let path = Self::path().context("Could not determine config path")?;
+        if !path.exists() {
+            log::info!("No config found at {}, using defaults", path.display());
+            return Ok(Self::default());
+        }
+
+        let contents = std::fs::read_to_string(&path)?;
+        let value: Value = serde_json::from_str(&contents)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        let Value::Object(mut document) = value else {
+            anyhow::bail!("Config must be a JSON object");
+        };
+
+        if let Some(old_version) = Self::migrate(&mut document)? {
+            let backup = path.with_extension(format!("v{old_version}.json.bak"));
+            std::fs::write(&backup, &contents)?;
+            std::fs::write(&path, serde_json::to_string_pretty(&document)?)?;
+            log::info!(
+                "Migrated config from version {old_version} to {CONFIG_VERSION}, backup saved to {}",
+                backup.display()
+            );
+        }
+
+        log::info!("Loaded config from {}", path.display());
+        Ok(Self { document })
+    }
+
+    /// Upgrades `document` in place to `CONFIG_VERSION`, returning the version
+    /// it was upgraded from, or `None` if it was already current.
+    fn migrate(document: &mut Map<String, Value>) -> anyhow::Result<Option<u32>> {
+        let version = match document.get("version") {
+            Some(version) => version
+                .as_u64()
+                .and_then(|version| u32::try_from(version).ok())
+                .context("Config version must be a positive integer")?,
+            None => 1,
+        };
+
+        if version == 0 {
+            anyhow::bail!("Config version must be a positive integer");
+        }
+
+        if version > CONFIG_VERSION {
+            log::warn!(
+                "Config version {version} is newer than supported version {CONFIG_VERSION}, unknown options are ignored"
+            );
+            return Ok(None);
+        }
+
+        if version == CONFIG_VERSION {
+            return Ok(None);
+        }
+
+        for migration in &MIGRATIONS[version as usize - 1..] {
+            migration(document);
+        }
+        document.insert("version".to_string(), CONFIG_VERSION.into());
+
+        Ok(Some(version))
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let path = Self::path().context("Could not determine config path")?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        std::fs::write(&path, serde_json::to_string_pretty(&self.document)?)?;
+        log::info!("Saved config to {}", path.display());
+        Ok(())
+    }
+
+    pub fn profiles(&self) -> Vec<String> {
+        self.document
+            .get("profiles")
+            .and_then(Value::as_object)
+            .map(|profiles| profiles.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn active_profile(&self) -> Option<&str> {
+        self.document.get("active_profile").and_then(Value::as_str)
+    }
+
+    pub fn set_active_profile(&mut self, name: Option<&str>) {
+        match name {
+            Some(name) => self
+                .document
+                .insert("active_profile".to_string(), name.into()),
+            None => self.document.remove("active_profile"),
+        };
+    }
+
+    fn active_overlay(&self) -> Option<&Map<String, Value>> {
+        let name = self.active_profile()?;
+        self.document.get("profiles")?.get(name)?.as_object()
+    }
+
+    /// Sets a top-level option, or the active profile's copy of it if the
+    /// profile overrides that option.
+    pub fn set(&mut self, key: &str, value: impl Serialize) -> anyhow::Result<()> {
+        let value = serde_json::to_value(value)?;
+
+        let overridden = self
+            .active_overlay()
+            .is_some_and(|overlay| overlay.contains_key(key));
+        let target = match (overridden, self.active_profile().map(str::to_string)) {
+            (true, Some(name)) => self
+                .document
+                .get_mut("profiles")
+                .and_then(|profiles| profiles.get_mut(&name))
+                .and_then(Value::as_object_mut)
+                .context("Active profile disappeared")?,
+            _ => &mut self.document,
+        };
+
+        target.insert(key.to_string(), value);
+        Ok(())
+    }
+
+    /// The options in effect. Invalid ones are left at their default, see
+    /// `effective_checked`.
+    pub fn effective(&self) -> Config {
+        self.effective_checked().0
+    }
+
+    /// The options in effect, along with a description of each one that was
+    /// left at its default because it couldn't be read.
+    pub fn effective_checked(&self) -> (Config, Vec<String>) {
+        let mut options = self.document.clone();
+        options.remove("profiles");
+        options.remove("active_profile");
+        let mut options = Value::Object(options);
+
+        match (self.active_profile(), self.active_overlay()) {
+            (Some(_), Some(overlay)) => merge(&mut options, &Value::Object(overlay.clone())),
+            (Some(name), None) => log::warn!("Active profile {name:?} does not exist"),
+            (None, _) => {}
+        }
+
+        if let Ok(config) = serde_json::from_value(options.clone()) {
+            return (config, Vec::new());
+        }
+
+        // reading the options one at a time only loses the invalid ones
+        let mut valid = Map::new();
+        let mut invalid = Vec::new();
+        for (key, value) in options.as_object().into_iter().flatten() {
+            let single = Map::from_iter([(key.clone(), value.clone())]);
+            match serde_json::from_value::<Config>(Value::Object(single)) {
+                Ok(_) => {
+                    valid.insert(key.clone(), value.clone());
+                }
+                Err(err) => invalid.push(format!("`{key}` left at its default: {err}")),
+            }
+        }
+        let config = serde_json::from_value(Value::Object(valid)).unwrap_or_else(|err| {
+            invalid.push(format!("all options left at their defaults: {err}"));
+            Config::default()
+        });
+        (config, invalid)
+    }
+}
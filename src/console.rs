@@ -0,0 +1,20 @@
+use windows::Win32::System::Console::{
+    ATTACH_PARENT_PROCESS, AllocConsole, AttachConsole, GetConsoleWindow,
+};
+
+/// Gives release builds, which have no console of their own, somewhere to log
+/// and panic to: the console komoswitch was started from, or a new one when
+/// started from Explorer.
+pub fn attach() {
+    unsafe {
+        // debug builds already have one
+        if !GetConsoleWindow().is_invalid() {
+            return;
+        }
+        if AttachConsole(ATTACH_PARENT_PROCESS).is_ok() {
+            return;
+        }
+        // without a console there is nowhere to tell about the failure
+        let _ = AllocConsole();
+    }
+}
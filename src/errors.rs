@@ -0,0 +1,74 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use winsafe::{prelude::*, HWND};
+
+use crate::msgs::ErrorsChanged;
+
+/// Number of errors kept for the error badge popup
+const CAPACITY: usize = 20;
+
+/// How long an error keeps the badge visible
+pub const BADGE_DURATION: Duration = Duration::from_secs(5 * 60);
+
+pub struct RecentError {
+    pub at: Instant,
+    pub message: String,
+}
+
+impl RecentError {
+    pub fn describe(&self) -> String {
+        let secs = self.at.elapsed().as_secs();
+        let ago = if secs < 60 {
+            format!("{secs}s ago")
+        } else {
+            format!("{}m ago", secs / 60)
+        };
+        format!("[{ago}] {}", self.message)
+    }
+}
+
+static RECENT_ERRORS: Mutex<VecDeque<RecentError>> = Mutex::new(VecDeque::new());
+
+/// Logs a non-fatal error, remembers it for the error badge and asks the
+/// window to repaint.
+pub fn report(hwnd: &HWND, message: String) {
+    log::error!("{message}");
+
+    {
+        let mut errors = RECENT_ERRORS.lock();
+        if errors.len() == CAPACITY {
+            errors.pop_front();
+        }
+        errors.push_back(RecentError {
+            at: Instant::now(),
+            message,
+        });
+    }
+
+    unsafe {
+        hwnd.PostMessage(ErrorsChanged::to_wndmsg()).ok();
+    }
+}
+
+pub fn has_recent() -> bool {
+    RECENT_ERRORS
+        .lock()
+        .back()
+        .is_some_and(|error| error.at.elapsed() < BADGE_DURATION)
+}
+
+/// Descriptions of all remembered errors, newest first
+pub fn describe_all() -> Vec<String> {
+    RECENT_ERRORS
+        .lock()
+        .iter()
+        .rev()
+        .map(RecentError::describe)
+        .collect()
+}
+
+pub fn clear() {
+    RECENT_ERRORS.lock().clear();
+}
@@ -0,0 +1,50 @@
+use windows::{
+    Win32::System::EventLog::{
+        DeregisterEventSource, EVENTLOG_ERROR_TYPE, EVENTLOG_WARNING_TYPE, REPORT_EVENT_TYPE,
+        RegisterEventSourceW, ReportEventW,
+    },
+    core::{PCWSTR, w},
+};
+
+/// Source the events show up under in the Application log. It isn't
+/// registered, so Event Viewer says it has no description for them, but
+/// still shows the message.
+const SOURCE: PCWSTR = w!("komoswitch");
+
+/// Writes an error that stops komoswitch to the Windows Event Log, where it
+/// can be found after a failed start with nobody watching the logs.
+pub fn error(message: &str) {
+    report(EVENTLOG_ERROR_TYPE, message);
+}
+
+/// Writes a problem komoswitch keeps running through, but that likely
+/// explains a misbehaving bar, to the Windows Event Log.
+pub fn warning(message: &str) {
+    report(EVENTLOG_WARNING_TYPE, message);
+}
+
+fn report(kind: REPORT_EVENT_TYPE, message: &str) {
+    if let Err(err) = try_report(kind, message) {
+        log::error!("Failed to write to the event log: {err}");
+    }
+}
+
+fn try_report(kind: REPORT_EVENT_TYPE, message: &str) -> anyhow::Result<()> {
+    let message: Vec<u16> = message.encode_utf16().chain(std::iter::once(0)).collect();
+    unsafe {
+        let source = RegisterEventSourceW(None, SOURCE)?;
+        let result = ReportEventW(
+            source,
+            kind,
+            0,
+            0,
+            None,
+            0,
+            Some(&[PCWSTR(message.as_ptr())]),
+            None,
+        );
+        DeregisterEventSource(source).ok();
+        result?;
+    }
+    Ok(())
+}
@@ -1,25 +1,507 @@
-use std::io::{BufReader, Read};
+use std::io::{BufRead, BufReader, Read};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::thread::JoinHandle;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use anyhow::Context;
-use komorebi_client::{Notification, Ring, SocketMessage, State, SubscribeOptions, Workspace};
+use komorebi_client::{
+    DefaultLayout, Layout, Notification, Ring, SocketMessage, State, StateQuery,
+    SubscribeOptions, Window, Workspace,
+};
+use parking_lot::RwLock;
 use winsafe::HWND;
+use crate::errors;
+use crate::msgs::{BarUpdate, KomorebiOnline};
+use crate::window::BarHandle;
 
-use crate::msgs::UpdateWorkspaces;
+/// Version of komorebi the bundled `komorebi_client` comes from, keep in sync
+/// with the tag in Cargo.toml
+pub const KOMOREBI_CLIENT_VERSION: &str = "0.1.38";
 
-fn workspaces_from_state(
-    state: State,
-) -> anyhow::Result<Ring<Workspace>> {
-    let monitor = state.monitors.focused().context("No focused monintor?")?;
+/// The workspaces a bar shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BarScope {
+    /// The focused monitor's, following the focus from monitor to monitor
+    Focused,
+    /// One monitor's, see `per_monitor_bars`
+    Monitor(usize),
+    /// Every monitor's in a row, see `all_monitors`
+    All,
+}
+
+/// A bar window and the workspaces it shows
+#[derive(Clone)]
+pub struct BarTarget {
+    pub hwnd: BarHandle,
+    pub scope: BarScope,
+}
+
+/// The bars the background threads post to, which change as monitors come
+/// and go
+#[derive(Clone)]
+pub struct BarTargets(Arc<RwLock<Vec<BarTarget>>>);
+
+impl BarTargets {
+    pub fn new(bars: Vec<BarTarget>) -> Self {
+        Self(Arc::new(RwLock::new(bars)))
+    }
+
+    pub fn set(&self, bars: Vec<BarTarget>) {
+        *self.0.write() = bars;
+    }
+
+    /// The bars as of now
+    pub fn get(&self) -> Vec<BarTarget> {
+        self.0.read().clone()
+    }
+
+    /// The bar errors concerning all of them are reported to
+    fn first(&self) -> HWND {
+        self.0
+            .read()
+            .first()
+            .map_or(HWND::NULL, |bar| bar.hwnd.get())
+    }
+}
+
+/// The workspaces of one monitor within the workspaces sent to a bar
+#[derive(Debug, Clone)]
+pub struct MonitorGroup {
+    pub monitor_idx: usize,
+    /// Index of the monitor's first workspace among all the bar's workspaces
+    pub start: usize,
+    pub len: usize,
+}
+
+impl MonitorGroup {
+    pub fn contains(&self, idx: usize) -> bool {
+        idx >= self.start && idx < self.start + self.len
+    }
+}
+
+/// Workspaces of each monitor in `state`, by monitor index
+fn monitor_workspaces(state: &State) -> Vec<&Ring<Workspace>> {
+    state
+        .monitors
+        .elements()
+        .iter()
+        .map(|monitor| &monitor.workspaces)
+        .collect()
+}
+
+/// Workspaces of the monitors in `scope`, one after the other, and where each
+/// monitor's start, so the bar can address them by monitor even once komorebi
+/// focused another one. With `BarScope::All` the focused workspace is the one
+/// on the focused monitor.
+fn workspaces_in_scope(
+    monitors: &[&Ring<Workspace>],
+    focused_monitor: usize,
+    scope: BarScope,
+) -> anyhow::Result<(Ring<Workspace>, Vec<MonitorGroup>)> {
+    let monitor_idx = match scope {
+        BarScope::Monitor(idx) => idx,
+        BarScope::Focused => focused_monitor,
+        BarScope::All => return Ok(all_workspaces(monitors, focused_monitor)),
+    };
+    let workspaces = monitors
+        .get(monitor_idx)
+        .with_context(|| format!("No monitor {monitor_idx}"))?;
+
+    let group = MonitorGroup {
+        monitor_idx,
+        start: 0,
+        len: workspaces.elements().len(),
+    };
+    Ok(((*workspaces).clone(), vec![group]))
+}
+
+fn all_workspaces(
+    monitors: &[&Ring<Workspace>],
+    focused_monitor: usize,
+) -> (Ring<Workspace>, Vec<MonitorGroup>) {
+    let mut workspaces = Ring::default();
+    let mut groups = Vec::new();
+    let mut focused_idx = 0;
+    for (monitor_idx, monitor) in monitors.iter().enumerate() {
+        let start = workspaces.elements().len();
+        if monitor_idx == focused_monitor {
+            focused_idx = start + monitor.focused_idx();
+        }
+        workspaces
+            .elements_mut()
+            .extend(monitor.elements().iter().cloned());
+        groups.push(MonitorGroup {
+            monitor_idx,
+            start,
+            len: monitor.elements().len(),
+        });
+    }
+    workspaces.focus(focused_idx);
+    (workspaces, groups)
+}
+
+/// What the bar needs of a komorebi state, which may have been read in part
+pub struct StateWorkspaces {
+    /// Each monitor's workspaces, by monitor index
+    monitors: Vec<Ring<Workspace>>,
+    focused_monitor: usize,
+    pub paused: bool,
+    /// Whether the state deserialized as a whole, see `partial_state`
+    pub compatible: bool,
+}
+
+impl StateWorkspaces {
+    fn from_state(state: &State) -> Self {
+        Self {
+            monitors: monitor_workspaces(state).into_iter().cloned().collect(),
+            focused_monitor: state.monitors.focused_idx(),
+            paused: state.is_paused,
+            compatible: true,
+        }
+    }
+
+    /// The workspaces in `scope`, see `workspaces_in_scope`
+    pub fn in_scope(
+        &self,
+        scope: BarScope,
+    ) -> anyhow::Result<(Ring<Workspace>, Vec<MonitorGroup>)> {
+        let monitors: Vec<&Ring<Workspace>> = self.monitors.iter().collect();
+        workspaces_in_scope(&monitors, self.focused_monitor, scope)
+    }
+}
+
+/// Digs each monitor's workspaces out of a `state` this version of
+/// komorebi_client can't deserialize, e.g. after a komorebi upgrade added or
+/// renamed a field. Workspaces that still parse are kept whole, the others
+/// only keep their name.
+fn partial_state(state: &serde_json::Value) -> anyhow::Result<StateWorkspaces> {
+    use serde_json::Value;
+
+    let elements = state
+        .pointer("/monitors/elements")
+        .and_then(Value::as_array)
+        .context("No monitors in state")?;
+    let focused = |ring: &Value| {
+        ring.get("focused")
+            .and_then(Value::as_u64)
+            .map_or(0, |idx| idx as usize)
+    };
+
+    let mut monitors = Vec::with_capacity(elements.len());
+    for monitor in elements {
+        let ring = monitor.get("workspaces").context("No workspaces in monitor")?;
+        let mut workspaces = Ring::default();
+        for workspace in ring
+            .get("elements")
+            .and_then(Value::as_array)
+            .context("No workspaces in monitor")?
+        {
+            let workspace = serde_json::from_value(workspace.clone()).unwrap_or_else(|_| {
+                let mut placeholder = Workspace::default();
+                placeholder.name = workspace
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .map(str::to_string);
+                placeholder
+            });
+            workspaces.elements_mut().push_back(workspace);
+        }
+        if !workspaces.elements().is_empty() {
+            workspaces.focus(focused(ring).min(workspaces.elements().len() - 1));
+        }
+        monitors.push(workspaces);
+    }
+
+    Ok(StateWorkspaces {
+        compatible: false,
+        focused_monitor: focused(state.get("monitors").unwrap_or(&Value::Null)),
+        paused: state
+            .get("is_paused")
+            .and_then(Value::as_bool)
+            .unwrap_or(false),
+        monitors,
+    })
+}
+
+/// komorebi's built-in layouts with their display names, in cycling order
+pub const DEFAULT_LAYOUTS: [(DefaultLayout, &str); 9] = [
+    (DefaultLayout::BSP, "BSP"),
+    (DefaultLayout::Columns, "Columns"),
+    (DefaultLayout::Rows, "Rows"),
+    (DefaultLayout::VerticalStack, "Vertical Stack"),
+    (DefaultLayout::HorizontalStack, "Horizontal Stack"),
+    (DefaultLayout::UltrawideVerticalStack, "Ultrawide"),
+    (DefaultLayout::Grid, "Grid"),
+    (DefaultLayout::RightMainVerticalStack, "Right Main"),
+    (DefaultLayout::Scrolling, "Scrolling"),
+];
 
-    Ok(monitor.workspaces.clone())
+/// Display name of `layout`, see `DEFAULT_LAYOUTS`
+pub fn layout_name(layout: &Layout) -> &'static str {
+    match layout {
+        Layout::Default(default) => DEFAULT_LAYOUTS
+            .iter()
+            .find(|(candidate, _)| candidate == default)
+            .map_or("Layout", |(_, name)| *name),
+        Layout::Custom(_) => "Custom",
+    }
 }
 
-pub fn read_workspaces() -> anyhow::Result<Ring<Workspace>> {
+/// Title of the window focused on the focused workspace, if any.
+pub fn focused_window_title(workspaces: &Ring<Workspace>) -> Option<String> {
+    let workspace = workspaces.focused()?;
+    let window = if let Some(container) = workspace.monocle_container() {
+        container.focused_window()
+    } else if let Some(window) = workspace.maximized_window() {
+        Some(window)
+    } else {
+        workspace
+            .focused_container()
+            .and_then(|container| container.focused_window())
+    }?;
+    window.title().ok()
+}
+
+/// Every window on `workspace`: tiled, monocle, maximized and floating.
+pub fn workspace_windows(workspace: &Workspace) -> Vec<&Window> {
+    let containers = workspace
+        .containers()
+        .iter()
+        .chain(workspace.monocle_container());
+    containers
+        .flat_map(|container| container.windows().iter())
+        .chain(workspace.maximized_window())
+        .chain(workspace.floating_windows().iter())
+        .collect()
+}
+
+/// Index of the one workspace whose name changed from `old` to `new` when
+/// nothing else about the workspaces did, i.e. when `new` is a rename.
+pub fn renamed_workspace(old: &Ring<Workspace>, new: &Ring<Workspace>) -> Option<usize> {
+    if old.elements().len() != new.elements().len() || old.focused_idx() != new.focused_idx() {
+        return None;
+    }
+    let mut renamed = old
+        .elements()
+        .iter()
+        .zip(new.elements())
+        .enumerate()
+        .filter(|(_, (old, new))| old.name != new.name)
+        .map(|(idx, _)| idx);
+    let idx = renamed.next()?;
+    if renamed.next().is_some() {
+        return None;
+    }
+
+    // names aside, the workspaces have to be the same
+    let without_name = |workspace: &Workspace| {
+        let mut value = serde_json::to_value(workspace).ok()?;
+        value.as_object_mut()?.remove("name");
+        Some(value)
+    };
+    let unchanged = old.elements().iter().zip(new.elements()).all(|(old, new)| {
+        let old = without_name(old);
+        old.is_some() && old == without_name(new)
+    });
+    unchanged.then_some(idx)
+}
+
+/// Titles of every window on `workspace`, see `workspace_windows`.
+pub fn workspace_window_titles(workspace: &Workspace) -> Vec<String> {
+    workspace_windows(workspace)
+        .into_iter()
+        .filter_map(|window| window.title().ok())
+        .collect()
+}
+
+pub fn read_state() -> anyhow::Result<State> {
     let response = komorebi_client::send_query(&SocketMessage::State)?;
-    let state: State = serde_json::from_str(&response)?;
-    workspaces_from_state(state)
+    Ok(serde_json::from_str(&response)?)
+}
+
+/// Reads komorebi's state for its workspaces, in part if this version of
+/// komorebi_client can't deserialize it, e.g. after a komorebi upgrade.
+pub fn read_state_workspaces() -> anyhow::Result<StateWorkspaces> {
+    let response = komorebi_client::send_query(&SocketMessage::State)?;
+    let err = match serde_json::from_str::<State>(&response) {
+        Ok(state) => return Ok(StateWorkspaces::from_state(&state)),
+        Err(err) => err,
+    };
+    let value: serde_json::Value = serde_json::from_str(&response)?;
+    // the state not fitting is the more telling error
+    let partial = partial_state(&value).map_err(|_| err)?;
+    log::warn!("Showing the parts of an incompatible komorebi state");
+    Ok(partial)
+}
+
+/// `%KOMOREBI_CONFIG_HOME%\komorebi.json`, or `%USERPROFILE%\komorebi.json`
+pub fn static_config_path() -> Option<PathBuf> {
+    let home = std::env::var_os("KOMOREBI_CONFIG_HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))?;
+    Some(PathBuf::from(home).join("komorebi.json"))
+}
+
+/// Builds placeholder workspaces named after a monitor (the first one unless
+/// given) in the static komorebi config, so the bar has something to show
+/// before komorebi answers.
+pub fn workspaces_from_static_config(
+    monitor_idx: Option<usize>,
+) -> anyhow::Result<Ring<Workspace>> {
+    let monitor_idx = monitor_idx.unwrap_or(0);
+    let path = static_config_path().context("Could not determine komorebi config path")?;
+    let config: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+
+    let names = config
+        .pointer(&format!("/monitors/{monitor_idx}/workspaces"))
+        .and_then(serde_json::Value::as_array)
+        .with_context(|| format!("No workspaces defined for monitor {monitor_idx}"))?
+        .iter()
+        .map(|workspace| workspace.get("name").and_then(serde_json::Value::as_str));
+
+    let mut workspaces = Ring::default();
+    for name in names {
+        let mut workspace = Workspace::default();
+        workspace.name = name.map(str::to_string);
+        workspaces.elements_mut().push_back(workspace);
+    }
+
+    if workspaces.elements().is_empty() {
+        anyhow::bail!("No workspaces defined for monitor {monitor_idx}");
+    }
+
+    log::info!("Seeded workspace names from {}", path.display());
+    Ok(workspaces)
+}
+
+/// Apps the static komorebi config routes to each workspace, by monitor and
+/// workspace index, e.g. `chrome.exe` or `Slack + slack.exe` for a rule that
+/// has to match on both.
+pub fn workspace_rules_from_static_config() -> anyhow::Result<Vec<Vec<Vec<String>>>> {
+    let path = static_config_path().context("Could not determine komorebi config path")?;
+    let config: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+    let monitors = config
+        .get("monitors")
+        .and_then(serde_json::Value::as_array)
+        .context("No monitors defined")?;
+
+    Ok(monitors
+        .iter()
+        .map(|monitor| {
+            monitor
+                .get("workspaces")
+                .and_then(serde_json::Value::as_array)
+                .map_or(Vec::new(), |workspaces| {
+                    workspaces.iter().map(workspace_rules).collect()
+                })
+        })
+        .collect())
+}
+
+/// Apps matched by the `workspace_rules` and `initial_workspace_rules` of a
+/// workspace in the static komorebi config, the latter marked as only
+/// applying to newly opened windows.
+fn workspace_rules(workspace: &serde_json::Value) -> Vec<String> {
+    let rule_id = |rule: &serde_json::Value| rule.get("id").and_then(|id| id.as_str());
+
+    let mut rules = Vec::new();
+    for (key, suffix) in [("workspace_rules", ""), ("initial_workspace_rules", " (new)")] {
+        let Some(entries) = workspace.get(key).and_then(serde_json::Value::as_array) else {
+            continue;
+        };
+        for entry in entries {
+            // a list of rules only matches windows matching all of them
+            let ids: Vec<&str> = match entry.as_array() {
+                Some(composite) => composite.iter().filter_map(rule_id).collect(),
+                None => rule_id(entry).into_iter().collect(),
+            };
+            if !ids.is_empty() {
+                rules.push(format!("{}{suffix}", ids.join(" + ")));
+            }
+        }
+    }
+    rules
+}
+
+/// Minimum time between two anomaly warnings of the same kind
+const ANOMALY_WARNING_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Thresholds above which komorebi notifications are logged as anomalies
+#[derive(Debug, Clone, Copy)]
+pub struct NotificationLimits {
+    pub max_bytes: usize,
+    pub max_per_second: u32,
+}
+
+/// Tracks notification sizes and arrival rates, warning (at most once a
+/// minute per kind) when they exceed the limits.
+struct NotificationStats {
+    limits: NotificationLimits,
+    second_started: Instant,
+    in_second: u32,
+    last_size_warning: Option<Instant>,
+    last_rate_warning: Option<Instant>,
+}
+
+impl NotificationStats {
+    fn new(limits: NotificationLimits) -> Self {
+        Self {
+            limits,
+            second_started: Instant::now(),
+            in_second: 0,
+            last_size_warning: None,
+            last_rate_warning: None,
+        }
+    }
+
+    fn record(&mut self, bytes: usize) {
+        log::debug!("Notification of {bytes} bytes");
+
+        if self.second_started.elapsed() >= Duration::from_secs(1) {
+            self.second_started = Instant::now();
+            self.in_second = 0;
+        }
+        self.in_second += 1;
+
+        if bytes > self.limits.max_bytes && Self::may_warn(&mut self.last_size_warning) {
+            log::warn!(
+                "komorebi notification anomaly: kind=large_payload bytes={bytes} threshold={}",
+                self.limits.max_bytes
+            );
+        }
+        if self.in_second > self.limits.max_per_second
+            && Self::may_warn(&mut self.last_rate_warning)
+        {
+            log::warn!(
+                "komorebi notification anomaly: kind=high_rate events_this_second={} threshold={}",
+                self.in_second,
+                self.limits.max_per_second
+            );
+        }
+    }
+
+    fn may_warn(last_warning: &mut Option<Instant>) -> bool {
+        if last_warning.is_some_and(|at| at.elapsed() < ANOMALY_WARNING_INTERVAL) {
+            return false;
+        }
+        *last_warning = Some(Instant::now());
+        true
+    }
+}
+
+/// Set while nobody can see the bars, see `pause_polling`
+static POLLING_PAUSED: AtomicBool = AtomicBool::new(false);
+
+/// Holds off the periodic resync, the presentation tool polling and the
+/// attempts to reach komorebi while the session is locked or the display is
+/// off. The bars resync themselves once they can be seen again.
+pub fn pause_polling(paused: bool) {
+    POLLING_PAUSED.store(paused, Ordering::Relaxed);
+}
+
+pub fn polling_paused() -> bool {
+    POLLING_PAUSED.load(Ordering::Relaxed)
 }
 
 #[cfg(debug_assertions)]
@@ -27,35 +509,74 @@ const SOCK_NAME: &str = "komorebi-switcher-debug.sock";
 #[cfg(not(debug_assertions))]
 const SOCK_NAME: &str = "komorebi-switcher.sock";
 
-pub fn start_listen_for_workspaces(hwnd: HWND) -> anyhow::Result<JoinHandle<()>> {
-    let socket = loop {
-        match komorebi_client::subscribe_with_options(
-            SOCK_NAME,
-            SubscribeOptions {
-                filter_state_changes: true,
-            },
-        ) {
-            Ok(socket) => break socket,
-            Err(_) => std::thread::sleep(Duration::from_secs(1)),
+/// Failed attempts to subscribe, a second apart, after which the failure is
+/// written to the event log, once
+const SUBSCRIBE_ATTEMPTS_LOGGED: u32 = 5 * 60;
+
+pub fn start_listen_for_workspaces(
+    bars: BarTargets,
+    limits: NotificationLimits,
+) -> anyhow::Result<JoinHandle<()>> {
+    let handle = std::thread::spawn(move || {
+        let mut stats = NotificationStats::new(limits);
+
+        let mut offline = false;
+        let mut attempts = 0;
+        let socket = loop {
+            if polling_paused() {
+                std::thread::sleep(Duration::from_secs(1));
+                continue;
+            }
+            match komorebi_client::subscribe_with_options(
+                SOCK_NAME,
+                SubscribeOptions {
+                    filter_state_changes: true,
+                },
+            ) {
+                Ok(socket) => break socket,
+                Err(err) => {
+                    if !offline {
+                        offline = true;
+                        post_online(&bars.get(), false);
+                    }
+                    attempts += 1;
+                    if attempts == SUBSCRIBE_ATTEMPTS_LOGGED {
+                        crate::eventlog::warning(&format!(
+                            "Still unable to subscribe to komorebi after {} minutes: {err}",
+                            SUBSCRIBE_ATTEMPTS_LOGGED / 60
+                        ));
+                    }
+                    std::thread::sleep(Duration::from_secs(1));
+                }
+            };
         };
-    };
 
-    log::info!("Subscribed to komorebi events");
+        log::info!("Subscribed to komorebi events");
+        post_online(&bars.get(), true);
+        post_version_check(&bars.get());
+
+        // the window may still be showing workspaces seeded from the static config
+        post_fresh_state(&bars.get());
 
-    let handle = std::thread::spawn(move || {
         log::debug!("Listenting for messages from komorebi...");
 
         for client in socket.incoming() {
             let client = match client {
                 Ok(client) => client,
                 Err(e) => {
-                    log::error!("Failed to get komorebi event subscription: {e}");
+                    errors::report(
+                        &bars.first(),
+                        format!("Failed to get komorebi event subscription: {e}"),
+                    );
                     continue;
                 }
             };
 
             if let Err(error) = client.set_read_timeout(Some(Duration::from_secs(1))) {
-                log::error!("Error when setting read timeout: {}", error)
+                errors::report(
+                    &bars.first(),
+                    format!("Error when setting read timeout: {error}"),
+                );
             }
 
             let mut buffer = Vec::new();
@@ -64,57 +585,219 @@ pub fn start_listen_for_workspaces(hwnd: HWND) -> anyhow::Result<JoinHandle<()>>
             // this is when we know a shutdown has been sent
             if matches!(reader.read_to_end(&mut buffer), Ok(0)) {
                 log::info!("Disconnected from komorebi!");
+                post_online(&bars.get(), false);
 
-                while komorebi_client::send_message(&SocketMessage::AddSubscriberSocket(
-                    SOCK_NAME.to_string(),
-                ))
-                .is_err()
+                while polling_paused()
+                    || komorebi_client::send_message(&SocketMessage::AddSubscriberSocket(
+                        SOCK_NAME.to_string(),
+                    ))
+                    .is_err()
                 {
                     log::info!("Attempting to reconnect to komorebi...");
                     std::thread::sleep(Duration::from_secs(3));
                 }
 
                 log::info!("Reconnected to komorebi!");
+                post_online(&bars.get(), true);
+                post_version_check(&bars.get());
+                // whatever changed while disconnected came without a notification
+                post_fresh_state(&bars.get());
                 continue;
             }
 
+            stats.record(buffer.len());
+
             let notification_str = match String::from_utf8(buffer) {
                 Ok(notification_str) => notification_str,
                 Err(e) => {
-                    log::error!("Failed to parse komorebi notification string as utf8: {e}");
+                    errors::report(
+                        &bars.first(),
+                        format!("Failed to parse komorebi notification string as utf8: {e}"),
+                    );
                     continue;
                 }
             };
 
-            let notification = match serde_json::from_str::<Notification>(&notification_str) {
-                Ok(notification) => notification,
-                Err(e) => {
-                    log::error!("Failed to parse komorebi notification string as json: {e}");
-                    continue;
+            post_notification(&bars.get(), &notification_str);
+        }
+    });
+
+    Ok(handle)
+}
+
+/// Reads komorebi's full state every `interval` and sends it to the bars, so
+/// notifications dropped e.g. under heavy load don't leave them out of date.
+pub fn start_periodic_resync(bars: BarTargets, interval: Duration) -> JoinHandle<()> {
+    std::thread::spawn(move || {
+        loop {
+            std::thread::sleep(interval);
+            if polling_paused() {
+                continue;
+            }
+            match read_state_workspaces() {
+                Ok(state) => post_state_workspaces(&bars.get(), &state),
+                // the listener thread tells the bars when komorebi is gone
+                Err(e) => log::debug!("Skipping periodic resync: {e}"),
+            }
+        }
+    })
+}
+
+/// Parses a komorebi notification and sends its workspaces to the windows.
+fn post_notification(bars: &[BarTarget], notification_str: &str) {
+    let hwnd = &bars[0].hwnd;
+    let notification = match serde_json::from_str::<Notification>(notification_str) {
+        Ok(notification) => notification,
+        Err(e) => {
+            let partial = serde_json::from_str::<serde_json::Value>(notification_str)
+                .map_err(anyhow::Error::from)
+                .and_then(|value| partial_state(value.get("state").context("No state")?));
+            match partial {
+                Ok(partial) => {
+                    log::warn!("Showing the parts of an incompatible komorebi state: {e}");
+                    post_state_workspaces(bars, &partial);
                 }
-            };
+                Err(_) => errors::report(
+                    &hwnd.get(),
+                    format!("Failed to parse komorebi notification string as json: {e}"),
+                ),
+            }
+            return;
+        }
+    };
 
-            log::info!(
-                "Received notification from komorebi: {:?}",
-                notification.event
-            );
+    log::info!(
+        "Received notification from komorebi: {:?}",
+        notification.event
+    );
+
+    post_state(bars, &notification.state);
+}
+
+/// Tells every bar whether komorebi can be reached.
+fn post_online(bars: &[BarTarget], online: bool) {
+    for bar in bars {
+        unsafe {
+            bar.hwnd
+                .get()
+                .PostMessage(KomorebiOnline::to_wndmsg(online))
+                .ok();
+        }
+    }
+}
+
+/// Why the running komorebi may not understand this build of the bar, if it
+/// reports another version than `KOMOREBI_CLIENT_VERSION` or none at all.
+fn version_mismatch() -> Option<String> {
+    let version = match komorebi_client::send_query(&SocketMessage::Query(StateQuery::Version)) {
+        Ok(version) => version,
+        Err(e) => {
+            log::warn!("Failed to query the komorebi version: {e}");
+            return Some(format!(
+                "komorebi did not report its version, komoswitch expects \
+                 {KOMOREBI_CLIENT_VERSION}"
+            ));
+        }
+    };
 
-            let new_workspaces = match workspaces_from_state(notification.state) {
-                Ok(workspaces) => workspaces,
+    let version = version.trim().trim_start_matches('v');
+    log::info!("komorebi version {version}, built against {KOMOREBI_CLIENT_VERSION}");
+    if version == KOMOREBI_CLIENT_VERSION {
+        return None;
+    }
+    Some(format!(
+        "komorebi {version} may not work with komoswitch, which expects {KOMOREBI_CLIENT_VERSION}"
+    ))
+}
+
+/// Tells every bar whether the running komorebi is the version it expects.
+fn post_version_check(bars: &[BarTarget]) {
+    let mismatch = version_mismatch();
+    for bar in bars {
+        bar.hwnd.send(BarUpdate::KomorebiVersion(mismatch.clone()));
+    }
+}
+
+/// Reads komorebi's full state and sends it to every bar, instead of waiting
+/// for the next notification.
+fn post_fresh_state(bars: &[BarTarget]) {
+    match read_state_workspaces() {
+        Ok(state) => post_state_workspaces(bars, &state),
+        Err(e) => errors::report(&bars[0].hwnd.get(), format!("Failed to read state: {e}")),
+    }
+}
+
+/// Sends every bar the workspaces of its monitor in `state`.
+fn post_state(bars: &[BarTarget], state: &State) {
+    let monitors = monitor_workspaces(state);
+    post_workspaces(bars, &monitors, state.monitors.focused_idx(), state.is_paused, true);
+}
+
+/// Sends every bar the workspaces of its monitor in `state`, which may have
+/// been read in part.
+fn post_state_workspaces(bars: &[BarTarget], state: &StateWorkspaces) {
+    let monitors: Vec<&Ring<Workspace>> = state.monitors.iter().collect();
+    post_workspaces(bars, &monitors, state.focused_monitor, state.paused, state.compatible);
+}
+
+/// Sends every bar the workspaces in its scope, and whether they come from a
+/// state that could only be read in part.
+fn post_workspaces(
+    bars: &[BarTarget],
+    monitors: &[&Ring<Workspace>],
+    focused_monitor: usize,
+    paused: bool,
+    compatible: bool,
+) {
+    for bar in bars {
+        let scoped = workspaces_in_scope(monitors, focused_monitor, bar.scope);
+        let (new_workspaces, groups) = match scoped {
+            Ok(workspaces) => workspaces,
+            Err(e) => {
+                errors::report(
+                    &bar.hwnd.get(),
+                    format!("Failed to read workspaces from state: {e}"),
+                );
+                continue;
+            }
+        };
+
+        bar.hwnd.send(BarUpdate::Workspaces {
+            workspaces: new_workspaces,
+            groups,
+            paused,
+            incompatible: !compatible,
+        });
+    }
+
+    log::debug!("Posted message to update workspaces");
+}
+
+/// Reads komorebi notifications from stdin, one JSON document per line (e.g.
+/// piped from `komorebic subscribe-pipe`), instead of subscribing directly.
+pub fn start_reading_stdin_events(bars: BarTargets, limits: NotificationLimits) -> JoinHandle<()> {
+    std::thread::spawn(move || {
+        log::debug!("Reading komorebi notifications from stdin...");
+        let mut stats = NotificationStats::new(limits);
+
+        for line in std::io::stdin().lock().lines() {
+            let line = match line {
+                Ok(line) => line,
                 Err(e) => {
-                    log::error!("Failed to read workspaces from state: {e}");
-                    continue;
+                    errors::report(
+                        &bars.first(),
+                        format!("Failed to read notification from stdin: {e}"),
+                    );
+                    break;
                 }
             };
 
-            unsafe {
-                hwnd.PostMessage(UpdateWorkspaces::to_wmdmsg(new_workspaces))
-                    .ok();
+            if !line.trim().is_empty() {
+                stats.record(line.len());
+                post_notification(&bars.get(), &line);
             }
-
-            log::debug!("Posted message to update workspaces");
         }
-    });
 
-    Ok(handle)
+        log::info!("Stopped reading notifications from stdin");
+    })
 }
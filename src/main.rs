@@ -5,32 +5,131 @@
   ),
   windows_subsystem = "windows"
 )]
-use crate::{komo::start_listen_for_workspaces, window::Window};
+use std::time::Duration;
 
+use anyhow::Context;
+use windows::{
+    Win32::{
+        Foundation::{ERROR_ALREADY_EXISTS, GetLastError},
+        System::Threading::CreateMutexW,
+    },
+    core::{PCWSTR, w},
+};
+use winsafe::prelude::*;
+
+use crate::{
+    alignment::start_watching_taskbar_alignment,
+    config::ConfigFile,
+    komo::{
+        NotificationLimits, start_listen_for_workspaces, start_periodic_resync,
+        start_reading_stdin_events,
+    },
+    msgs::{ShowInstance, ToggleVisibility},
+    presentation::start_watching_presentation,
+    window::{Bars, Window},
+};
+
+mod alignment;
+mod config;
+mod console;
+mod errors;
+mod eventlog;
 mod komo;
 mod window;
 mod msgs;
+mod presentation;
+
+fn begin_execution(stdin_events: bool) -> anyhow::Result<()> {
+    let config_file = ConfigFile::load();
+    let config = config_file.effective();
+    let presentation_processes = config.presentation_processes;
+    let resync_interval = config.resync_interval_secs;
+    let limits = NotificationLimits {
+        max_bytes: config.warn_notification_bytes,
+        max_per_second: config.warn_notifications_per_second,
+    };
+
+    let mut bars = Bars::new(config_file, config.per_monitor_bars)?;
+    if stdin_events {
+        start_reading_stdin_events(bars.targets(), limits);
+    } else {
+        start_listen_for_workspaces(bars.targets(), limits)?;
+    }
+    if resync_interval > 0 {
+        start_periodic_resync(bars.targets(), Duration::from_secs(resync_interval));
+    }
+    start_watching_presentation(bars.targets(), presentation_processes);
+    start_watching_taskbar_alignment(bars.targets());
+
+    bars.run_loop()
+}
+
+/// Asks an already running instance to show or hide its bar.
+fn toggle_running_instance() -> anyhow::Result<()> {
+    let hwnd = Window::find_running()?.context("komoswitch is not running")?;
+    unsafe {
+        hwnd.PostMessage(winsafe::msg::WndMsg::new(ToggleVisibility::id()?, 0, 0))?;
+    }
+    Ok(())
+}
 
-fn begin_execution() -> anyhow::Result<()> {
-    let mut window = Window::new()?;
-    window.prepare()?;
+/// Mutex held by the running instance, one per user session
+const INSTANCE_MUTEX: PCWSTR = w!("komoswitch.instance");
 
-    let hwnd = unsafe { window.hwnd.raw_copy() };
-    start_listen_for_workspaces(hwnd)?;
+/// Whether komoswitch already runs in this session. Otherwise this process
+/// holds the mutex until it exits.
+fn another_instance_running() -> anyhow::Result<bool> {
+    unsafe {
+        CreateMutexW(None, false, INSTANCE_MUTEX)?;
+        Ok(GetLastError() == ERROR_ALREADY_EXISTS)
+    }
+}
 
-    window.run_loop()
+/// Asks the running instance to reload its config and show its bar.
+fn show_running_instance() -> anyhow::Result<()> {
+    let Some(hwnd) = Window::find_running()? else {
+        // still starting up, it will show itself anyway
+        return Ok(());
+    };
+    unsafe {
+        hwnd.PostMessage(winsafe::msg::WndMsg::new(ShowInstance::id()?, 0, 0))?;
+    }
+    Ok(())
 }
 
 fn main() -> anyhow::Result<()> {
-    env_logger::builder()
+    let args: Vec<String> = std::env::args().collect();
+    let console = args.iter().any(|arg| arg == "--console");
+
+    let mut logger = env_logger::builder();
+    if console {
+        console::attach();
+        // asked for on purpose, so show more than the errors unless
+        // `RUST_LOG` says otherwise
+        logger
+            .parse_env(env_logger::Env::default().default_filter_or("info"))
+            .target(env_logger::Target::Stdout);
+    }
+    logger
         .format_timestamp(None)
         .format_file(true)
         .format_line_number(true)
         .init();
 
-    begin_execution().unwrap_or_else(|err| {
+    if args.iter().any(|arg| arg == "--toggle") {
+        return toggle_running_instance();
+    }
+    let stdin_events = args.iter().any(|arg| arg == "--stdin-events");
+
+    if another_instance_running()? {
+        log::info!("komoswitch is already running, showing its bar");
+        return show_running_instance();
+    }
+
+    begin_execution(stdin_events).unwrap_or_else(|err| {
         println!("{:?}", err.backtrace());
         log::error!("Application error: {}", err);
+        eventlog::error(&format!("komoswitch stopped: {err:#}"));
     });
 
     log::info!("Application exiting normally");
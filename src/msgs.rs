@@ -1,24 +1,197 @@
 use komorebi_client::{Ring, Workspace};
 use winsafe::{co::WM, msg::WndMsg};
 
-pub struct UpdateWorkspaces;
+use crate::{komo::MonitorGroup, window::TaskbarContent};
 
-impl UpdateWorkspaces {
+/// What the threads have for a bar that doesn't fit into a window message. It
+/// goes through the bar's channel, with `BarUpdated` only waking the bar up.
+pub enum BarUpdate {
+    /// `groups` tells the monitors apart in the all-monitors mode, `paused`
+    /// is whether komorebi's tiling is paused, `incompatible` whether the
+    /// workspaces come from a state that could only be read in part, e.g.
+    /// after a komorebi upgrade changed its shape
+    Workspaces {
+        workspaces: Ring<Workspace>,
+        groups: Vec<MonitorGroup>,
+        paused: bool,
+        incompatible: bool,
+    },
+    /// Why the running komorebi may not be the one the bar was built against,
+    /// or `None` when its version matches
+    KomorebiVersion(Option<String>),
+    /// The taskbar measured for the bar, `None` when that failed
+    TaskbarContent(Option<TaskbarContent>),
+}
+
+/// Updates are waiting in the bar's channel, see `BarUpdate`
+pub struct BarUpdated;
+
+impl BarUpdated {
     pub const ID: WM = unsafe { WM::from_raw(WM::APP.raw() + 1) };
 
-    pub fn to_wmdmsg(workspaces: Ring<Workspace>) -> WndMsg {
-        let data = Box::new(workspaces);
-        let ptr = Box::into_raw(data) as isize;
+    pub fn to_wndmsg() -> WndMsg {
+        WndMsg {
+            msg_id: Self::ID,
+            wparam: 0,
+            lparam: 0,
+        }
+    }
+}
+
+pub struct PresentationMode;
+
+impl PresentationMode {
+    pub const ID: WM = unsafe { WM::from_raw(WM::APP.raw() + 2) };
+
+    pub fn to_wndmsg(active: bool) -> WndMsg {
+        WndMsg {
+            msg_id: Self::ID,
+            wparam: active as usize,
+            lparam: 0,
+        }
+    }
+
+    pub fn from_wndmsg(p: WndMsg) -> bool {
+        p.wparam != 0
+    }
+}
+
+pub struct ErrorsChanged;
+
+impl ErrorsChanged {
+    pub const ID: WM = unsafe { WM::from_raw(WM::APP.raw() + 3) };
+
+    pub fn to_wndmsg() -> WndMsg {
+        WndMsg {
+            msg_id: Self::ID,
+            wparam: 0,
+            lparam: 0,
+        }
+    }
+}
+
+/// Callback message of the notification area icon
+pub struct TrayIcon;
+
+impl TrayIcon {
+    pub const ID: WM = unsafe { WM::from_raw(WM::APP.raw() + 4) };
+}
+
+/// The subscription to komorebi was lost (`false`) or established again (`true`)
+pub struct KomorebiOnline;
+
+impl KomorebiOnline {
+    pub const ID: WM = unsafe { WM::from_raw(WM::APP.raw() + 6) };
+
+    pub fn to_wndmsg(online: bool) -> WndMsg {
+        WndMsg {
+            msg_id: Self::ID,
+            wparam: online as usize,
+            lparam: 0,
+        }
+    }
+
+    pub fn from_wndmsg(p: WndMsg) -> bool {
+        p.wparam != 0
+    }
+}
+
+/// The user started (`false`) or finished (`true`) moving a window by its
+/// title bar, forwarded from the drag tracking hook
+pub struct WindowDragged;
+
+impl WindowDragged {
+    pub const ID: WM = unsafe { WM::from_raw(WM::APP.raw() + 5) };
 
+    pub fn to_wndmsg(finished: bool, hwnd: isize) -> WndMsg {
+        WndMsg {
+            msg_id: Self::ID,
+            wparam: finished as usize,
+            lparam: hwnd,
+        }
+    }
+
+    pub fn from_wndmsg(p: WndMsg) -> (bool, isize) {
+        (p.wparam != 0, p.lparam)
+    }
+}
+
+/// Another window came to the foreground, forwarded from the foreground hook
+pub struct ForegroundChanged;
+
+impl ForegroundChanged {
+    pub const ID: WM = unsafe { WM::from_raw(WM::APP.raw() + 9) };
+
+    pub fn to_wndmsg(hwnd: isize) -> WndMsg {
         WndMsg {
             msg_id: Self::ID,
             wparam: 0,
-            lparam: ptr,
+            lparam: hwnd,
         }
     }
 
-    pub fn from_wndmsg(p: WndMsg) -> Ring<Workspace> {
-        let workspaces = unsafe { Box::from_raw(p.lparam as *mut Ring<Workspace>) };
-        *workspaces
+    pub fn from_wndmsg(p: WndMsg) -> isize {
+        p.lparam
+    }
+}
+
+/// The system colors changed, forwarded from `UISettings::ColorValuesChanged`
+pub struct ColorsChanged;
+
+impl ColorsChanged {
+    pub const ID: WM = unsafe { WM::from_raw(WM::APP.raw() + 10) };
+
+    pub fn to_wndmsg() -> WndMsg {
+        WndMsg {
+            msg_id: Self::ID,
+            wparam: 0,
+            lparam: 0,
+        }
+    }
+}
+
+/// The Windows 11 taskbar's buttons moved to the center (`true`) or the left
+/// (`false`)
+pub struct TaskbarAlignment;
+
+impl TaskbarAlignment {
+    pub const ID: WM = unsafe { WM::from_raw(WM::APP.raw() + 11) };
+
+    pub fn to_wndmsg(centered: bool) -> WndMsg {
+        WndMsg {
+            msg_id: Self::ID,
+            wparam: centered as usize,
+            lparam: 0,
+        }
+    }
+
+    pub fn from_wndmsg(p: WndMsg) -> bool {
+        p.wparam != 0
+    }
+}
+
+/// Registered message asking a running instance to show or hide its bar, sent
+/// by `komoswitch --toggle`
+pub struct ToggleVisibility;
+
+impl ToggleVisibility {
+    const NAME: &str = "komoswitch.toggle";
+
+    pub fn id() -> anyhow::Result<WM> {
+        let id = winsafe::RegisterWindowMessage(Self::NAME)?;
+        Ok(unsafe { WM::from_raw(id) })
+    }
+}
+
+/// Registered message telling the running instance that komoswitch was
+/// launched again, which reloads the config and shows the bar
+pub struct ShowInstance;
+
+impl ShowInstance {
+    const NAME: &str = "komoswitch.show";
+
+    pub fn id() -> anyhow::Result<WM> {
+        let id = winsafe::RegisterWindowMessage(Self::NAME)?;
+        Ok(unsafe { WM::from_raw(id) })
     }
 }
@@ -0,0 +1,68 @@
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use winsafe::{co, HPROCESSLIST};
+
+use crate::msgs::PresentationMode;
+use crate::komo::BarTargets;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+fn running_processes() -> anyhow::Result<Vec<String>> {
+    let mut snapshot = HPROCESSLIST::CreateToolhelp32Snapshot(co::TH32CS::SNAPPROCESS, None)?;
+    let mut names = Vec::new();
+    for entry in snapshot.iter_processes() {
+        names.push(entry?.szExeFile());
+    }
+    Ok(names)
+}
+
+/// Polls the process list and tells the windows whenever one of the configured
+/// screen-recording/presentation tools starts or stops.
+pub fn start_watching_presentation(
+    bars: BarTargets,
+    processes: Vec<String>,
+) -> Option<JoinHandle<()>> {
+    if processes.is_empty() {
+        return None;
+    }
+
+    let handle = std::thread::spawn(move || {
+        log::debug!("Watching for presentation tools: {:?}", processes);
+        let mut active = false;
+
+        loop {
+            if crate::komo::polling_paused() {
+                std::thread::sleep(POLL_INTERVAL);
+                continue;
+            }
+            match running_processes() {
+                Ok(running) => {
+                    let now_active = running.iter().any(|name| {
+                        processes
+                            .iter()
+                            .any(|process| process.eq_ignore_ascii_case(name))
+                    });
+
+                    if now_active != active {
+                        active = now_active;
+                        log::info!("Presentation mode {}", if active { "on" } else { "off" });
+                        for bar in bars.get() {
+                            unsafe {
+                                bar.hwnd
+                                    .get()
+                                    .PostMessage(PresentationMode::to_wndmsg(active))
+                                    .ok();
+                            }
+                        }
+                    }
+                }
+                Err(e) => log::error!("Failed to enumerate processes: {e}"),
+            }
+
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    });
+
+    Some(handle)
+}
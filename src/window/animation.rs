@@ -0,0 +1,217 @@
+use std::time::{Duration, Instant};
+
+use winsafe::{prelude::*, *};
+
+use crate::config::IndicatorStyle;
+
+use super::{layout::WorkspaceButton, settings::ColorSettings, Visibility, Window};
+
+pub(super) const FOCUS_ANIMATION_TIMER: usize = 4;
+const FOCUS_ANIMATION_INTERVAL_MS: u32 = 15;
+const FOCUS_ANIMATION_DURATION: Duration = Duration::from_millis(150);
+
+pub(super) const WIDTH_ANIMATION_TIMER: usize = 5;
+const WIDTH_ANIMATION_INTERVAL_MS: u32 = 15;
+const WIDTH_ANIMATION_DURATION: Duration = Duration::from_millis(150);
+
+pub(super) const THEME_TRANSITION_TIMER: usize = 6;
+const THEME_TRANSITION_INTERVAL_MS: u32 = 15;
+const THEME_TRANSITION_DURATION: Duration = Duration::from_millis(300);
+
+/// The focus indicator sliding from the previously focused workspace
+#[derive(Clone, Copy)]
+pub(super) struct FocusAnimation {
+    from_idx: usize,
+    started: Instant,
+}
+
+/// The bar growing or shrinking to fit its content
+#[derive(Clone, Copy)]
+pub(super) struct WidthAnimation {
+    from: i32,
+    to: i32,
+    started: Instant,
+}
+
+/// The bar crossfading from the palette of the previous Windows theme
+pub(super) struct ThemeTransition {
+    from: ColorSettings,
+    to: ColorSettings,
+    started: Instant,
+}
+
+/// Ease-out progress between 0 and 1 of an animation lasting `duration`
+fn progress(started: Instant, duration: Duration) -> f32 {
+    let t = (started.elapsed().as_secs_f32() / duration.as_secs_f32()).min(1.0);
+    1.0 - (1.0 - t).powi(3)
+}
+
+fn lerp(from: i32, to: i32, t: f32) -> i32 {
+    from + ((to - from) as f32 * t).round() as i32
+}
+
+impl Window {
+    /// Starts sliding the focus indicator away from `from_idx`, unless the bar
+    /// is quiet, saving power or not fully shown.
+    pub(super) fn start_focus_animation(&mut self, from_idx: usize) {
+        // glyphs change in place rather than sliding, and a pill would slide
+        // over the labels it sits behind
+        if self.quiet
+            || self.saving_power()
+            || self.config.state_glyphs.is_some()
+            || self.config.indicator_styles.focused == IndicatorStyle::Pill
+            || self.visibility != Visibility::Shown
+            || from_idx == self.workspaces.focused_idx()
+            || from_idx >= self.workspaces.elements().len()
+        {
+            return;
+        }
+
+        self.focus_animation = Some(FocusAnimation {
+            from_idx,
+            started: Instant::now(),
+        });
+        if let Err(err) =
+            self.hwnd
+                .SetTimer(FOCUS_ANIMATION_TIMER, FOCUS_ANIMATION_INTERVAL_MS, None)
+        {
+            log::error!("Failed to start focus animation: {err}");
+            self.focus_animation = None;
+        }
+    }
+
+    pub(super) fn handle_focus_animation_timer(&mut self) -> anyhow::Result<isize> {
+        let finished = self
+            .focus_animation
+            .is_none_or(|animation| animation.started.elapsed() >= FOCUS_ANIMATION_DURATION);
+        if finished {
+            self.focus_animation = None;
+            self.hwnd.KillTimer(FOCUS_ANIMATION_TIMER)?;
+        }
+        self.render()?;
+        Ok(0)
+    }
+
+    /// Draws the focus indicator part way between the previously and the
+    /// currently focused workspace.
+    pub(super) fn paint_focus_animation(
+        &self,
+        hdc: &HDC,
+        rect: RECT,
+        buttons: &[WorkspaceButton],
+    ) -> anyhow::Result<()> {
+        let Some(animation) = self.focus_animation else {
+            return Ok(());
+        };
+        let find = |idx: usize| buttons.iter().find(|button| button.idx == idx);
+        let (Some(from), Some(to)) = (
+            find(animation.from_idx),
+            find(self.workspaces.focused_idx()),
+        ) else {
+            return Ok(());
+        };
+
+        let style = self.config.indicator_styles.focused;
+        let from = self.indicator_rect(rect, from, style, true);
+        let to = self.indicator_rect(rect, to, style, true);
+        let t = progress(animation.started, FOCUS_ANIMATION_DURATION);
+        let indicator = RECT {
+            left: lerp(from.left, to.left, t),
+            right: lerp(from.right, to.right, t),
+            top: lerp(from.top, to.top, t),
+            bottom: lerp(from.bottom, to.bottom, t),
+        };
+
+        self.paint_state_indicator(hdc, indicator, self.focused_color(), true)
+    }
+
+    /// Starts growing or shrinking the bar from `from` to `to` pixels wide,
+    /// returning false if it should be resized at once instead.
+    pub(super) fn start_width_animation(&self, from: i32, to: i32) -> bool {
+        // nothing to animate from before the first layout
+        if self.quiet || self.saving_power() || from <= 0 {
+            return false;
+        }
+        if self
+            .width_animation
+            .get()
+            .is_some_and(|animation| animation.to == to)
+        {
+            return true;
+        }
+
+        self.width_animation.set(Some(WidthAnimation {
+            from,
+            to,
+            started: Instant::now(),
+        }));
+        if let Err(err) =
+            self.hwnd
+                .SetTimer(WIDTH_ANIMATION_TIMER, WIDTH_ANIMATION_INTERVAL_MS, None)
+        {
+            log::error!("Failed to start width animation: {err}");
+            self.width_animation.set(None);
+            return false;
+        }
+        true
+    }
+
+    pub(super) fn handle_width_animation_timer(&mut self) -> anyhow::Result<isize> {
+        let Some(animation) = self.width_animation.get() else {
+            self.hwnd.KillTimer(WIDTH_ANIMATION_TIMER)?;
+            return Ok(0);
+        };
+
+        if animation.started.elapsed() >= WIDTH_ANIMATION_DURATION {
+            self.width_animation.set(None);
+            self.hwnd.KillTimer(WIDTH_ANIMATION_TIMER)?;
+        }
+        let t = progress(animation.started, WIDTH_ANIMATION_DURATION);
+        self.set_window_width(lerp(animation.from, animation.to, t))?;
+        self.render()?;
+        Ok(0)
+    }
+
+    /// Fades the freshly loaded colors in from `from`, unless the bar is quiet,
+    /// saving power or the colors didn't change.
+    pub(super) fn start_theme_transition(&mut self, from: ColorSettings) {
+        if self.quiet || self.saving_power() || from == self.settings.colors {
+            return;
+        }
+
+        let to = std::mem::replace(&mut self.settings.colors, from.clone());
+        self.theme_transition = Some(ThemeTransition {
+            from,
+            to,
+            started: Instant::now(),
+        });
+        if let Err(err) =
+            self.hwnd
+                .SetTimer(THEME_TRANSITION_TIMER, THEME_TRANSITION_INTERVAL_MS, None)
+        {
+            log::error!("Failed to start theme transition: {err}");
+            if let Some(transition) = self.theme_transition.take() {
+                self.settings.colors = transition.to;
+            }
+        }
+    }
+
+    pub(super) fn handle_theme_transition_timer(&mut self) -> anyhow::Result<isize> {
+        let Some(transition) = &self.theme_transition else {
+            self.hwnd.KillTimer(THEME_TRANSITION_TIMER)?;
+            return Ok(0);
+        };
+
+        if transition.started.elapsed() >= THEME_TRANSITION_DURATION {
+            self.hwnd.KillTimer(THEME_TRANSITION_TIMER)?;
+            if let Some(transition) = self.theme_transition.take() {
+                self.settings.colors = transition.to;
+            }
+        } else {
+            let t = progress(transition.started, THEME_TRANSITION_DURATION);
+            self.settings.colors = transition.from.blend(&transition.to, t);
+        }
+        self.render()?;
+        Ok(0)
+    }
+}
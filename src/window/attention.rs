@@ -0,0 +1,99 @@
+use windows::Win32::{
+    Foundation::HWND as WIN32_HWND,
+    UI::WindowsAndMessaging::{
+        DeregisterShellHookWindow, HSHELL_HIGHBIT, HSHELL_REDRAW, HSHELL_WINDOWACTIVATED,
+        HSHELL_WINDOWDESTROYED, RegisterShellHookWindow,
+    },
+};
+use winsafe::{prelude::*, *};
+
+use super::Window;
+
+/// Registered message the shell sends its hook windows
+const SHELL_HOOK_MESSAGE: &str = "SHELLHOOK";
+
+// missing from the bindings, see winuser.h
+const HSHELL_FLASH: u32 = HSHELL_REDRAW | HSHELL_HIGHBIT;
+const HSHELL_RUDEAPPACTIVATED: u32 = HSHELL_WINDOWACTIVATED | HSHELL_HIGHBIT;
+
+/// Id of the message the shell hook notifications arrive in.
+pub(super) fn shell_hook_message() -> anyhow::Result<co::WM> {
+    let id = RegisterWindowMessage(SHELL_HOOK_MESSAGE)?;
+    Ok(unsafe { co::WM::from_raw(id) })
+}
+
+impl Window {
+    /// Asks the shell to tell the bar about windows flashing for attention,
+    /// the same notifications that make taskbar buttons flash.
+    pub(super) fn register_shell_hook(&self) {
+        if !unsafe { RegisterShellHookWindow(WIN32_HWND(self.hwnd.ptr())) }.as_bool() {
+            crate::errors::report(
+                &self.hwnd,
+                "Failed to watch windows asking for attention".to_string(),
+            );
+        }
+    }
+
+    pub(super) fn unregister_shell_hook(&self) {
+        let _ = unsafe { DeregisterShellHookWindow(WIN32_HWND(self.hwnd.ptr())) };
+    }
+
+    /// Keeps track of which windows are flashing; a window stops asking for
+    /// attention once it's activated or closed.
+    pub(super) fn handle_shell_hook(
+        &mut self,
+        wparam: usize,
+        lparam: isize,
+    ) -> anyhow::Result<isize> {
+        let changed = match wparam as u32 {
+            HSHELL_FLASH => {
+                // the focused workspace needs no marker, the window is in view
+                let visible = self
+                    .workspaces
+                    .focused()
+                    .is_some_and(|workspace| workspace_contains(workspace, lparam));
+                !visible && self.attention.insert(lparam)
+            }
+            HSHELL_WINDOWACTIVATED | HSHELL_RUDEAPPACTIVATED | HSHELL_WINDOWDESTROYED => {
+                self.attention.remove(&lparam)
+            }
+            _ => false,
+        };
+        if changed {
+            log::debug!("Windows asking for attention: {:?}", self.attention);
+            self.refresh()?;
+        }
+        Ok(0)
+    }
+
+    /// Forgets the windows asking for attention that are on the focused
+    /// workspace or no longer on any workspace.
+    pub(super) fn prune_attention(&mut self) {
+        if self.attention.is_empty() {
+            return;
+        }
+        let focused_idx = self.workspaces.focused_idx();
+        let workspaces = self.workspaces.elements();
+        self.attention.retain(|hwnd| {
+            workspaces
+                .iter()
+                .position(|workspace| workspace_contains(workspace, *hwnd))
+                .is_some_and(|idx| idx != focused_idx)
+        });
+    }
+
+    /// Whether a window on the workspace at `idx` is asking for attention.
+    pub(super) fn wants_attention(&self, idx: usize) -> bool {
+        self.workspaces.elements().get(idx).is_some_and(|workspace| {
+            self.attention
+                .iter()
+                .any(|hwnd| workspace_contains(workspace, *hwnd))
+        })
+    }
+}
+
+fn workspace_contains(workspace: &komorebi_client::Workspace, hwnd: isize) -> bool {
+    crate::komo::workspace_windows(workspace)
+        .iter()
+        .any(|window| window.hwnd == hwnd)
+}
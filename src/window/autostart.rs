@@ -0,0 +1,79 @@
+use windows::{
+    Win32::{
+        Foundation::{ERROR_FILE_NOT_FOUND, ERROR_SUCCESS},
+        System::Registry::{
+            HKEY_CURRENT_USER, REG_SZ, RRF_RT_REG_SZ, RegDeleteKeyValueW, RegGetValueW,
+            RegSetKeyValueW,
+        },
+    },
+    core::{PCWSTR, w},
+};
+
+use super::Window;
+
+/// Programs started for the current user when they sign in
+const RUN_KEY: PCWSTR = w!(r"Software\Microsoft\Windows\CurrentVersion\Run");
+const VALUE_NAME: PCWSTR = w!("komoswitch");
+
+/// Whether komoswitch is set to start when the user signs in, from wherever
+/// it was installed at the time.
+pub(super) fn is_enabled() -> bool {
+    let result = unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            RUN_KEY,
+            VALUE_NAME,
+            RRF_RT_REG_SZ,
+            None,
+            None,
+            None,
+        )
+    };
+    result == ERROR_SUCCESS
+}
+
+/// Starts the running executable whenever the current user signs in, or no
+/// longer does.
+pub(super) fn set_enabled(enabled: bool) -> anyhow::Result<()> {
+    if !enabled {
+        let result = unsafe { RegDeleteKeyValueW(HKEY_CURRENT_USER, RUN_KEY, VALUE_NAME) };
+        if result != ERROR_FILE_NOT_FOUND {
+            result.ok()?;
+        }
+        return Ok(());
+    }
+
+    let exe = std::env::current_exe()?;
+    let command: Vec<u16> = format!("\"{}\"", exe.display())
+        .encode_utf16()
+        .chain(std::iter::once(0))
+        .collect();
+    unsafe {
+        RegSetKeyValueW(
+            HKEY_CURRENT_USER,
+            RUN_KEY,
+            VALUE_NAME,
+            REG_SZ.0,
+            Some(command.as_ptr() as _),
+            (command.len() * size_of::<u16>()) as u32,
+        )
+    }
+    .ok()?;
+    Ok(())
+}
+
+impl Window {
+    pub(super) fn toggle_autostart(&self) -> anyhow::Result<isize> {
+        let enabled = !is_enabled();
+        if let Err(err) = set_enabled(enabled) {
+            let action = if enabled { "enable" } else { "disable" };
+            crate::errors::report(
+                &self.hwnd,
+                format!("Failed to {action} starting with Windows: {err}"),
+            );
+            return Ok(0);
+        }
+        log::info!("Start with Windows: {enabled}");
+        Ok(0)
+    }
+}
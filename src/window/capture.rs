@@ -0,0 +1,36 @@
+use windows::Win32::{
+    Foundation::HWND as WIN32_HWND,
+    UI::WindowsAndMessaging::{SetWindowDisplayAffinity, WDA_EXCLUDEFROMCAPTURE, WDA_NONE},
+};
+use winsafe::prelude::*;
+
+use super::Window;
+
+impl Window {
+    /// Keeps the bar out of screenshots, recordings and streams with
+    /// `exclude_from_capture`, while it still shows on the screen.
+    pub(super) fn apply_capture_exclusion(&self) {
+        let exclude = self.config.exclude_from_capture;
+        let affinity = if exclude {
+            WDA_EXCLUDEFROMCAPTURE
+        } else {
+            WDA_NONE
+        };
+        let result = unsafe { SetWindowDisplayAffinity(WIN32_HWND(self.hwnd.ptr()), affinity) };
+        let Err(err) = result else {
+            return;
+        };
+        if !exclude {
+            log::debug!("Failed to reset the display affinity: {err}");
+        } else if self.embedded {
+            // Windows only takes top-level windows out of captures, so the
+            // user has to know the option isn't keeping anything private
+            crate::errors::report(&self.hwnd, self.strings().capture_embedded.to_string());
+        } else {
+            crate::errors::report(
+                &self.hwnd,
+                format!("Failed to exclude the bar from screen capture: {err}"),
+            );
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use komorebi_client::SocketMessage;
+use windows::Win32::UI::Input::KeyboardAndMouse::GetDoubleClickTime;
+use winsafe::prelude::*;
+
+use crate::config::{ClickAction, ClickBinding};
+
+use super::{Window, layout::WorkspaceButton};
+
+/// Holds back a single click until it is clear no double-click follows
+pub(super) const CLICK_TIMER: usize = 7;
+
+impl Window {
+    /// The actions bound to clicking `button`, which depend on whether its
+    /// workspace is the focused one.
+    fn click_binding(&self, button: &WorkspaceButton) -> ClickBinding {
+        if button.idx == self.workspaces.focused_idx() {
+            self.config.click_actions.focused.clone()
+        } else {
+            self.config.click_actions.workspace.clone()
+        }
+    }
+
+    /// Runs the single-click action of `button`, right away when no
+    /// double-click action would have to be told apart from it.
+    pub(super) fn handle_click(&mut self, button: &WorkspaceButton) -> anyhow::Result<isize> {
+        let binding = self.click_binding(button);
+        if binding.double == ClickAction::None {
+            return self.run_click_action(binding.single, button.idx);
+        }
+
+        self.pending_click = Some(button.idx);
+        self.hwnd.SetTimer(CLICK_TIMER, unsafe { GetDoubleClickTime() }, None)?;
+        Ok(0)
+    }
+
+    /// The double-click time passed without a second click.
+    pub(super) fn handle_click_timer(&mut self) -> anyhow::Result<isize> {
+        self.hwnd.KillTimer(CLICK_TIMER)?;
+        let Some(idx) = self.pending_click.take() else {
+            return Ok(0);
+        };
+        let single = if idx == self.workspaces.focused_idx() {
+            self.config.click_actions.focused.single.clone()
+        } else {
+            self.config.click_actions.workspace.single.clone()
+        };
+        self.run_click_action(single, idx)
+    }
+
+    pub(super) fn handle_double_click(
+        &mut self,
+        button: &WorkspaceButton,
+    ) -> anyhow::Result<isize> {
+        // the first click of the pair was only the start of this one
+        self.hwnd.KillTimer(CLICK_TIMER).ok();
+        self.pending_click = None;
+
+        let double = self.click_binding(button).double;
+        self.run_click_action(double, button.idx)
+    }
+
+    fn run_click_action(&mut self, action: ClickAction, idx: usize) -> anyhow::Result<isize> {
+        log::info!("Click action {action:?} on workspace {idx}");
+        let message = match action {
+            ClickAction::None => return Ok(0),
+            ClickAction::Focus => self.focus_workspace_message(idx),
+            ClickAction::PreviousWorkspace => match self
+                .previous_workspace
+                .filter(|previous| *previous < self.workspaces.elements().len())
+            {
+                Some(previous) => self.focus_workspace_message(previous),
+                None => return Ok(0),
+            },
+            ClickAction::SendWindow => self.send_window_message(idx, self.config.send_follows),
+            ClickAction::MoveWindow => self.send_window_message(idx, true),
+            ClickAction::ToggleMonocle => {
+                if idx != self.workspaces.focused_idx() {
+                    komorebi_client::send_query(&self.focus_workspace_message(idx))?;
+                }
+                SocketMessage::ToggleMonocle
+            }
+            ClickAction::Command(name) => return self.run_custom_command(&name),
+        };
+        komorebi_client::send_query(&message)?;
+        Ok(0)
+    }
+}
@@ -0,0 +1,42 @@
+use windows::Win32::{
+    Foundation::{GlobalFree, HANDLE, HWND as WIN32_HWND},
+    System::{
+        DataExchange::{CloseClipboard, EmptyClipboard, OpenClipboard, SetClipboardData},
+        Memory::{GlobalAlloc, GlobalLock, GlobalUnlock, GMEM_MOVEABLE},
+    },
+};
+use winsafe::{prelude::*, HWND};
+
+const CF_UNICODETEXT: u32 = 13;
+
+/// Replaces the clipboard contents with `text`.
+pub fn set_text(hwnd: &HWND, text: &str) -> anyhow::Result<()> {
+    let wide: Vec<u16> = text.encode_utf16().chain(std::iter::once(0)).collect();
+    let bytes = wide.len() * std::mem::size_of::<u16>();
+
+    unsafe {
+        OpenClipboard(Some(WIN32_HWND(hwnd.ptr())))?;
+        let result = (|| -> anyhow::Result<()> {
+            EmptyClipboard()?;
+
+            let hmem = GlobalAlloc(GMEM_MOVEABLE, bytes)?;
+            let dest = GlobalLock(hmem) as *mut u16;
+            if dest.is_null() {
+                // succeeds with NULL, which the wrapper calls an error
+                let _ = GlobalFree(Some(hmem));
+                anyhow::bail!("GlobalLock failed");
+            }
+            std::ptr::copy_nonoverlapping(wide.as_ptr(), dest, wide.len());
+            GlobalUnlock(hmem).ok();
+
+            // the clipboard owns the memory once this succeeds
+            if let Err(err) = SetClipboardData(CF_UNICODETEXT, Some(HANDLE(hmem.0))) {
+                let _ = GlobalFree(Some(hmem));
+                return Err(err.into());
+            }
+            Ok(())
+        })();
+        CloseClipboard()?;
+        result
+    }
+}
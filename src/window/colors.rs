@@ -0,0 +1,37 @@
+use windows::{Foundation::TypedEventHandler, UI::ViewManagement::UISettings, core::IInspectable};
+
+use super::Window;
+use crate::msgs::ColorsChanged;
+
+impl Window {
+    /// Asks to be told when the accent color or the light/dark mode changes,
+    /// as `WM_SETTINGCHANGE` doesn't always reach the bar once it is a child
+    /// of the taskbar.
+    pub(super) fn register_color_notification(&mut self) {
+        if self.color_notify.is_some() {
+            return;
+        }
+        let handle = self.handle();
+        let handler = TypedEventHandler::<UISettings, IInspectable>::new(move |_, _| {
+            // raised on a thread pool thread
+            unsafe {
+                handle.get().PostMessage(ColorsChanged::to_wndmsg()).ok();
+            }
+            Ok(())
+        });
+        let notify = UISettings::new().and_then(|ui_settings| {
+            let token = ui_settings.ColorValuesChanged(&handler)?;
+            Ok((ui_settings, token))
+        });
+        match notify {
+            Ok(notify) => self.color_notify = Some(notify),
+            Err(err) => log::error!("Failed to register for color changes: {err}"),
+        }
+    }
+
+    pub(super) fn unregister_color_notification(&mut self) {
+        if let Some((ui_settings, token)) = self.color_notify.take() {
+            ui_settings.RemoveColorValuesChanged(token).ok();
+        }
+    }
+}
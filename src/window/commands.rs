@@ -0,0 +1,35 @@
+use anyhow::Context;
+use komorebi_client::SocketMessage;
+
+use crate::config::CustomCommand;
+
+use super::Window;
+
+impl Window {
+    /// Sends the custom command called `name`, see `CustomCommand`.
+    pub(super) fn run_custom_command(&self, name: &str) -> anyhow::Result<isize> {
+        let command = self
+            .config
+            .custom_commands
+            .iter()
+            .find(|command| command.name == name)
+            .with_context(|| format!("No custom command named {name:?}"))?;
+        send_custom_command(command)
+    }
+
+    /// Sends the `idx`th custom command in the config.
+    pub(super) fn run_custom_command_at(&self, idx: usize) -> anyhow::Result<isize> {
+        match self.config.custom_commands.get(idx) {
+            Some(command) => send_custom_command(command),
+            None => Ok(0),
+        }
+    }
+}
+
+fn send_custom_command(command: &CustomCommand) -> anyhow::Result<isize> {
+    let message: SocketMessage = serde_json::from_value(command.message.clone())
+        .with_context(|| format!("Invalid message for custom command {:?}", command.name))?;
+    log::info!("Running custom command {:?}: {message:?}", command.name);
+    komorebi_client::send_query(&message)?;
+    Ok(0)
+}
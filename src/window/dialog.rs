@@ -0,0 +1,185 @@
+use winsafe::{prelude::*, *};
+
+use super::{Window, palette, rename_dialog, settings_dialog};
+
+// Notification codes in the high word of WM_COMMAND
+pub(super) const BN_CLICKED: u16 = 0;
+pub(super) const EN_CHANGE: u16 = 0x0300;
+pub(super) const LBN_DBLCLK: u16 = 2;
+
+// Control styles and messages as the types the generic window functions take
+pub(super) const ES_AUTOHSCROLL: co::WS = unsafe { co::WS::from_raw(0x0080) };
+pub(super) const BS_DEFPUSHBUTTON: co::WS = unsafe { co::WS::from_raw(0x0001) };
+pub(super) const LBS_NOTIFY: co::WS = unsafe { co::WS::from_raw(0x0001) };
+pub(super) const LBS_NOINTEGRALHEIGHT: co::WS = unsafe { co::WS::from_raw(0x0100) };
+pub(super) const EM_SETSEL: co::WM = unsafe { co::WM::from_raw(0x00b1) };
+pub(super) const LB_ADDSTRING: co::WM = unsafe { co::WM::from_raw(0x0180) };
+pub(super) const LB_RESETCONTENT: co::WM = unsafe { co::WM::from_raw(0x0184) };
+pub(super) const LB_SETCURSEL: co::WM = unsafe { co::WM::from_raw(0x0186) };
+pub(super) const LB_GETCURSEL: co::WM = unsafe { co::WM::from_raw(0x0188) };
+
+/// Sent by `IsDialogMessage` for Enter and Escape
+pub(super) const ID_OK: u16 = 1;
+pub(super) const ID_CANCEL: u16 = 2;
+
+/// What a dialog does with a message: its result, or `None` to leave it to
+/// `DefWindowProc`
+pub(super) type Handler = fn(&mut Window, msg::WndMsg) -> Option<anyhow::Result<isize>>;
+
+/// Lets the settings or rename dialog or the command palette handle Tab and
+/// Enter while it is the active window.
+pub(super) fn is_dialog_message(msg: &mut MSG) -> bool {
+    let Some(active) = HWND::GetActiveWindow() else {
+        return false;
+    };
+    active.GetClassName().is_ok_and(|class_name| {
+        class_name == settings_dialog::CLASS_NAME
+            || class_name == rename_dialog::CLASS_NAME
+            || class_name == palette::CLASS_NAME
+    }) && active.IsDialogMessage(msg)
+}
+
+/// Registers the window class of a dialog, unless an earlier one of its kind
+/// already did.
+pub(super) fn register_class(
+    hinst: &HINSTANCE,
+    class_name: &str,
+    proc: WNDPROC,
+) -> anyhow::Result<()> {
+    let mut wcx = WNDCLASSEX::default();
+    wcx.lpfnWndProc = Some(proc);
+    wcx.hInstance = unsafe { hinst.raw_copy() };
+    wcx.hCursor = HINSTANCE::NULL
+        .LoadCursor(IdIdcStr::Idc(co::IDC::ARROW))?
+        .leak();
+    wcx.hbrBackground = HBRUSH::from_sys_color(co::COLOR::BTNFACE);
+    let mut class_name = WString::from_str(class_name);
+    wcx.set_lpszClassName(Some(&mut class_name));
+
+    match unsafe { RegisterClassEx(&wcx) } {
+        Ok(_) | Err(co::ERROR::CLASS_ALREADY_EXISTS) => Ok(()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Creates a dialog of window class `class_name` around a client area of
+/// `client`, placed by `position` given its size. The bar handles it, see
+/// `dialog_proc`.
+pub(super) fn create_window(
+    window: &Window,
+    hinst: &HINSTANCE,
+    class_name: &str,
+    title: &str,
+    ex_style: co::WS_EX,
+    client: SIZE,
+    position: impl FnOnce(SIZE) -> anyhow::Result<POINT>,
+) -> anyhow::Result<HWND> {
+    let mut rect = RECT {
+        left: 0,
+        top: 0,
+        right: client.cx,
+        bottom: client.cy,
+    };
+    let style = co::WS::CAPTION | co::WS::SYSMENU | co::WS::POPUP;
+    let ex_style = co::WS_EX::TOOLWINDOW | ex_style;
+    AdjustWindowRectEx(&mut rect, style, false, ex_style)?;
+    let size = SIZE {
+        cx: rect.right - rect.left,
+        cy: rect.bottom - rect.top,
+    };
+    let pos = position(size)?;
+
+    let dialog = unsafe {
+        HWND::CreateWindowEx(
+            ex_style,
+            AtomStr::from_str(class_name),
+            Some(title),
+            style,
+            pos,
+            size,
+            None,
+            IdMenu::None,
+            hinst,
+            Some(window as *const _ as _),
+        )?
+    };
+    Ok(dialog)
+}
+
+/// Creates a control of window class `class` in `dialog`, in the dialog font.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn create_control(
+    dialog: &HWND,
+    hinst: &HINSTANCE,
+    class: &str,
+    text: Option<&str>,
+    id: u16,
+    style: co::WS,
+    ex_style: co::WS_EX,
+    pos: POINT,
+    size: SIZE,
+) -> anyhow::Result<HWND> {
+    let hwnd = unsafe {
+        HWND::CreateWindowEx(
+            ex_style,
+            AtomStr::from_str(class),
+            text,
+            co::WS::CHILD | co::WS::VISIBLE | style,
+            pos,
+            size,
+            Some(dialog),
+            IdMenu::Id(id),
+            hinst,
+            None,
+        )?
+    };
+    let font = HFONT::GetStockObject(co::STOCK_FONT::DEFAULT_GUI)?;
+    unsafe {
+        hwnd.SendMessage(msg::wm::SetFont {
+            hfont: font.raw_copy(),
+            redraw: false,
+        });
+    }
+    Ok(hwnd)
+}
+
+/// Window procedure shared by the dialogs, created with the bar as their
+/// creation parameter. Messages go to `handler` once `is_tracked` finds the
+/// dialog fully created and tracked by the bar, and its errors are reported
+/// as `what` failing.
+pub(super) fn dialog_proc(
+    hwnd: HWND,
+    wm_any: msg::WndMsg,
+    what: &str,
+    is_tracked: fn(&Window, &HWND) -> bool,
+    handler: Handler,
+) -> isize {
+    if wm_any.msg_id == co::WM::NCCREATE {
+        let msg = unsafe { msg::wm::NcCreate::from_generic_wm(wm_any) };
+        unsafe {
+            hwnd.SetWindowLongPtr(co::GWLP::USERDATA, msg.createstruct.lpCreateParams as _);
+        }
+        return unsafe { hwnd.DefWindowProc(wm_any) };
+    }
+
+    let ptr_self = hwnd.GetWindowLongPtr(co::GWLP::USERDATA) as *mut Window;
+    let ref_self = match unsafe { ptr_self.as_mut() } {
+        Some(ref_self) if is_tracked(ref_self, &hwnd) => ref_self,
+        _ => return unsafe { hwnd.DefWindowProc(wm_any) },
+    };
+
+    let Some(result) = handler(ref_self, wm_any) else {
+        return unsafe { hwnd.DefWindowProc(wm_any) };
+    };
+    result.unwrap_or_else(|err| {
+        crate::errors::report(&ref_self.hwnd, format!("{what} error: {err}"));
+        0
+    })
+}
+
+/// Control id and notification code of a `WM_COMMAND`.
+pub(super) fn command(wm_any: msg::WndMsg) -> (u16, u16) {
+    let id = wm_any.wparam & 0xffff;
+    let code = wm_any.wparam >> 16;
+    (id as u16, code as u16)
+}
@@ -0,0 +1,235 @@
+use winsafe::{prelude::*, *};
+
+use super::{BarMonitor, Window, dialog};
+use crate::{
+    config::ConfigFile,
+    komo::{BarTarget, BarTargets},
+};
+
+/// Class of the hidden top-level window receiving the display notifications,
+/// which Windows only sends to top-level windows and not to bars parented to
+/// the taskbar
+const WATCHER_CLASS_NAME: &str = "komoswitch.displays";
+
+/// Waits for Explorer and komorebi to catch up with a display change, which
+/// also bundles the burst of notifications e.g. docking a laptop sends
+const SETTLE_TIMER: usize = 1;
+const SETTLE_DELAY_MS: u32 = 2000;
+
+/// `WM_DEVICECHANGE` sent when a device, e.g. a monitor, was added or removed
+const DBT_DEVNODES_CHANGED: usize = 0x0007;
+
+/// One bar per monitor komorebi manages, or a single bar following the
+/// focused monitor when komorebi cannot be asked.
+fn bar_monitors() -> Vec<Option<BarMonitor>> {
+    match crate::komo::read_state() {
+        Ok(state) if !state.monitors.elements().is_empty() => state
+            .monitors
+            .elements()
+            .iter()
+            .enumerate()
+            .map(|(idx, monitor)| {
+                Some(BarMonitor {
+                    idx,
+                    handle: monitor.id,
+                })
+            })
+            .collect(),
+        Ok(_) => vec![None],
+        Err(err) => {
+            log::warn!("Could not list monitors, showing a single bar: {err}");
+            vec![None]
+        }
+    }
+}
+
+fn open_bar(config_file: ConfigFile, monitor: Option<BarMonitor>) -> anyhow::Result<Box<Window>> {
+    let mut window = Box::new(Window::new(config_file, monitor)?);
+    window.prepare()?;
+    Ok(window)
+}
+
+fn targets_of(windows: &[Box<Window>]) -> Vec<BarTarget> {
+    windows
+        .iter()
+        .map(|window| BarTarget {
+            hwnd: window.handle(),
+            scope: window.scope(),
+        })
+        .collect()
+}
+
+fn create_watcher() -> anyhow::Result<HWND> {
+    let hinstance = HINSTANCE::GetModuleHandle(None)?;
+    let mut wcx = WNDCLASSEX::default();
+    wcx.lpfnWndProc = Some(watcher_proc);
+    wcx.hInstance = unsafe { hinstance.raw_copy() };
+    let mut class_name = WString::from_str(WATCHER_CLASS_NAME);
+    wcx.set_lpszClassName(Some(&mut class_name));
+    let atom = unsafe { RegisterClassEx(&wcx) }?;
+
+    // never shown, but unlike a message-only window it gets broadcasts
+    let hwnd = unsafe {
+        HWND::CreateWindowEx(
+            co::WS_EX::TOOLWINDOW,
+            AtomStr::Atom(atom),
+            None,
+            co::WS::POPUP,
+            POINT::default(),
+            SIZE::default(),
+            None,
+            IdMenu::None,
+            &hinstance,
+            None,
+        )?
+    };
+    Ok(hwnd)
+}
+
+extern "system" fn watcher_proc(hwnd: HWND, msg: co::WM, wparam: usize, lparam: isize) -> isize {
+    match msg {
+        co::WM::DISPLAYCHANGE => log::info!("Display settings changed"),
+        co::WM::DEVICECHANGE if wparam == DBT_DEVNODES_CHANGED => log::debug!("Devices changed"),
+        // left running while a modal loop dispatches it, `Bars::run_loop`
+        // handles it once the modal loop is done
+        co::WM::TIMER => return 0,
+        _ => return unsafe { hwnd.DefWindowProc(msg::WndMsg::new(msg, wparam, lparam)) },
+    }
+
+    // restarted with every notification, so the last one starts the count
+    if let Err(err) = hwnd.SetTimer(SETTLE_TIMER, SETTLE_DELAY_MS, None) {
+        log::error!("Failed to wait for the displays to settle: {err}");
+    }
+    0
+}
+
+/// Every bar, one per monitor with `per_monitor_bars`, following the monitors
+/// as they are plugged in and out.
+pub struct Bars {
+    // boxed, as each window keeps a pointer to itself in its window data
+    windows: Vec<Box<Window>>,
+    targets: BarTargets,
+    per_monitor: bool,
+    watcher: HWND,
+}
+
+impl Bars {
+    pub fn new(config_file: ConfigFile, per_monitor: bool) -> anyhow::Result<Self> {
+        let monitors = if per_monitor {
+            bar_monitors()
+        } else {
+            vec![None]
+        };
+
+        let mut config_file = Some(config_file);
+        let mut windows = Vec::new();
+        for monitor in monitors {
+            let config_file = config_file.take().unwrap_or_else(ConfigFile::load);
+            windows.push(open_bar(config_file, monitor)?);
+        }
+
+        Ok(Self {
+            targets: BarTargets::new(targets_of(&windows)),
+            windows,
+            per_monitor,
+            watcher: create_watcher()?,
+        })
+    }
+
+    /// The bars for the background threads, kept up to date as bars come and
+    /// go
+    pub fn targets(&self) -> BarTargets {
+        self.targets.clone()
+    }
+
+    pub fn run_loop(&mut self) -> anyhow::Result<()> {
+        let mut msg = MSG::default();
+        while GetMessage(&mut msg, None, 0, 0)? {
+            // handled here rather than in a window procedure, as it may
+            // destroy the bars
+            if msg.hwnd == self.watcher && msg.message == co::WM::TIMER {
+                self.handle_displays_settled();
+                continue;
+            }
+            if dialog::is_dialog_message(&mut msg) {
+                continue;
+            }
+            TranslateMessage(&msg);
+            unsafe {
+                DispatchMessage(&msg);
+            }
+        }
+        Ok(())
+    }
+
+    /// Matches the bars to the monitors and has each fit itself to its
+    /// taskbar again, which may have moved, resized or changed DPI.
+    fn handle_displays_settled(&mut self) {
+        if let Err(err) = self.watcher.KillTimer(SETTLE_TIMER) {
+            log::warn!("Failed to stop the display settle timer: {err}");
+        }
+        if self.per_monitor {
+            self.sync_monitors();
+        }
+
+        for window in &self.windows {
+            unsafe {
+                window
+                    .hwnd
+                    .PostMessage(msg::WndMsg::new(co::WM::DISPLAYCHANGE, 0, 0))
+                    .ok();
+            }
+        }
+    }
+
+    /// Removes the bars of monitors that are gone, or that komorebi now knows
+    /// by another index, and opens bars for the monitors without one.
+    fn sync_monitors(&mut self) {
+        let monitors = bar_monitors();
+        let (mut kept, removed): (Vec<_>, Vec<_>) = std::mem::take(&mut self.windows)
+            .into_iter()
+            .partition(|window| monitors.contains(&window.monitor));
+        if removed.is_empty() && kept.len() == monitors.len() {
+            self.windows = kept;
+            return;
+        }
+
+        // before opening any, so a new primary bar can take the hotkeys and
+        // the tray icon
+        for mut window in removed {
+            log::info!("Monitor {:?} is gone, removing its bar", window.monitor);
+            window.remove();
+        }
+
+        for monitor in monitors {
+            if let Some(idx) = kept.iter().position(|window| window.monitor == monitor) {
+                self.windows.push(kept.swap_remove(idx));
+                continue;
+            }
+
+            log::info!("Monitor {monitor:?} was added, opening a bar");
+            match open_bar(ConfigFile::load(), monitor) {
+                Ok(window) => self.windows.push(window),
+                Err(err) => log::error!("Failed to open a bar: {err}"),
+            }
+        }
+
+        if self.windows.is_empty() {
+            log::error!("No bar left, exiting");
+            PostQuitMessage(0);
+            return;
+        }
+        self.targets.set(targets_of(&self.windows));
+    }
+}
+
+impl Window {
+    /// Destroys the bar for good once its monitor is gone, unlike when it
+    /// goes along with the taskbar.
+    fn remove(&mut self) {
+        self.removed = true;
+        if let Err(err) = self.hwnd.DestroyWindow() {
+            log::error!("Failed to destroy the bar: {err}");
+        }
+    }
+}
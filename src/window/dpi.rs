@@ -0,0 +1,70 @@
+use windows::Win32::{
+    Foundation::HWND as WIN32_HWND,
+    UI::HiDpi::{
+        DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2, GetDpiForSystem, GetDpiForWindow,
+        SetProcessDpiAwarenessContext,
+    },
+};
+use winsafe::{prelude::*, *};
+
+use super::Window;
+
+/// DPI at which all hardcoded pixel values are specified
+pub(super) const DEFAULT_DPI: u32 = 96;
+
+/// Opts into Per-Monitor V2 awareness so the bar renders crisply at the scale
+/// of the monitor it is on, falling back to system awareness on older Windows.
+pub(super) fn enable_dpi_awareness() -> anyhow::Result<()> {
+    if let Err(err) =
+        unsafe { SetProcessDpiAwarenessContext(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2) }
+    {
+        log::warn!("Per-monitor DPI awareness unavailable: {err}");
+        if IsWindowsVistaOrGreater()? {
+            SetProcessDPIAware()?;
+        }
+    }
+    Ok(())
+}
+
+/// DPI of the monitor `hwnd` is on, or of the primary monitor without a window.
+pub(super) fn dpi_for(hwnd: Option<&HWND>) -> u32 {
+    let dpi = match hwnd {
+        Some(hwnd) => unsafe { GetDpiForWindow(WIN32_HWND(hwnd.ptr())) },
+        None => unsafe { GetDpiForSystem() },
+    };
+    if dpi == 0 { DEFAULT_DPI } else { dpi }
+}
+
+impl Window {
+    /// Scales a length given at 96 DPI to the DPI of the bar.
+    pub(super) fn px(&self, value: i32) -> i32 {
+        (value * self.dpi as i32 + DEFAULT_DPI as i32 / 2) / DEFAULT_DPI as i32
+    }
+
+    /// Space around labels, from the config
+    pub(super) fn padding(&self) -> i32 {
+        self.px(self.config.padding.max(0))
+    }
+
+    pub(super) fn border_radius(&self) -> SIZE {
+        SIZE {
+            cx: self.px(super::BORDER_RADIUS.cx),
+            cy: self.px(super::BORDER_RADIUS.cy),
+        }
+    }
+
+    /// Rebuilds the DPI dependent resources after the bar moved to a monitor
+    /// with a different scale, or the scale of its monitor changed.
+    pub(super) fn handle_dpi_changed(&mut self) -> anyhow::Result<isize> {
+        let dpi = dpi_for(Some(&self.hwnd));
+        if dpi == self.dpi {
+            return Ok(0);
+        }
+
+        log::info!("DPI changed from {} to {}", self.dpi, dpi);
+        self.dpi = dpi;
+        self.reload_settings()?;
+        self.embed_or_standalone()?;
+        self.refresh()
+    }
+}
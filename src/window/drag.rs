@@ -0,0 +1,135 @@
+use parking_lot::Mutex;
+use windows::Win32::{
+    Foundation::HWND as WIN32_HWND,
+    UI::{
+        Accessibility::{HWINEVENTHOOK, SetWinEventHook, UnhookWinEvent},
+        WindowsAndMessaging::{
+            EVENT_SYSTEM_MOVESIZEEND, EVENT_SYSTEM_MOVESIZESTART, OBJID_WINDOW,
+            WINEVENT_OUTOFCONTEXT, WINEVENT_SKIPOWNPROCESS,
+        },
+    },
+};
+use winsafe::{prelude::*, *};
+
+use crate::msgs::WindowDragged;
+
+use super::Window;
+
+/// Each bar's drag tracking hook and the bar, for `win_event_proc` to forward
+/// window moves to the bar that registered the hook. With a bar per monitor,
+/// each one takes the drops on its own buttons.
+static BARS: Mutex<Vec<(isize, isize)>> = Mutex::new(Vec::new());
+
+/// Called on the UI thread whenever the user starts or stops moving a window.
+unsafe extern "system" fn win_event_proc(
+    hook: HWINEVENTHOOK,
+    event: u32,
+    hwnd: WIN32_HWND,
+    id_object: i32,
+    _id_child: i32,
+    _event_thread: u32,
+    _event_time: u32,
+) {
+    if id_object != OBJID_WINDOW.0 {
+        return;
+    }
+    let bar = BARS
+        .lock()
+        .iter()
+        .find(|(bar_hook, _)| *bar_hook == hook.0 as isize)
+        .map(|(_, bar)| *bar);
+    let Some(bar) = bar else {
+        return;
+    };
+
+    let finished = event == EVENT_SYSTEM_MOVESIZEEND;
+    unsafe {
+        HWND::from_ptr(bar as _)
+            .PostMessage(WindowDragged::to_wndmsg(finished, hwnd.0 as isize))
+            .ok();
+    }
+}
+
+impl Window {
+    /// Starts watching for windows being dragged by their title bar, so they
+    /// can be dropped on workspace buttons.
+    pub(super) fn register_drag_tracking(&mut self) {
+        if !self.config.drag_to_workspace || self.drag_hook.is_some() {
+            return;
+        }
+
+        let hook = unsafe {
+            SetWinEventHook(
+                EVENT_SYSTEM_MOVESIZESTART,
+                EVENT_SYSTEM_MOVESIZEEND,
+                None,
+                Some(win_event_proc),
+                0,
+                0,
+                WINEVENT_OUTOFCONTEXT | WINEVENT_SKIPOWNPROCESS,
+            )
+        };
+        if hook.is_invalid() {
+            crate::errors::report(&self.hwnd, "Failed to track dragged windows".to_string());
+            return;
+        }
+        BARS.lock().push((hook.0 as isize, self.hwnd.ptr() as isize));
+        self.drag_hook = Some(hook);
+    }
+
+    pub(super) fn unregister_drag_tracking(&mut self) {
+        if let Some(hook) = self.drag_hook.take() {
+            BARS.lock().retain(|(bar_hook, _)| *bar_hook != hook.0 as isize);
+            unsafe { UnhookWinEvent(hook) }.ok();
+        }
+        self.dragged_window = None;
+    }
+
+    /// Sends a window dropped on a workspace button to that workspace, on the
+    /// monitor the button belongs to when the bar shows several.
+    pub(super) fn handle_window_dragged(
+        &mut self,
+        (finished, hwnd): (bool, isize),
+    ) -> anyhow::Result<isize> {
+        if !finished {
+            self.dragged_window = Some(hwnd);
+            return Ok(0);
+        }
+        if self.dragged_window.take() != Some(hwnd) {
+            return Ok(0);
+        }
+
+        let cursor = GetCursorPos()?;
+        let rect = self.hwnd.GetWindowRect()?;
+        let over_bar = cursor.x >= rect.left
+            && cursor.x < rect.right
+            && cursor.y >= rect.top
+            && cursor.y < rect.bottom;
+        if !over_bar {
+            return Ok(0);
+        }
+        let point = POINT {
+            x: cursor.x - rect.left,
+            y: cursor.y - rect.top,
+        };
+        let Some(button) = self.workspace_at(point)? else {
+            return Ok(0);
+        };
+
+        // komorebi moves the focused container, which the dragged window
+        // should be unless something stole the focus on the way
+        let foreground = komorebi_client::WindowsApi::foreground_window().ok();
+        if foreground != Some(hwnd) {
+            log::warn!("Dropped window {hwnd:#x} is no longer focused, not moving it");
+            return Ok(0);
+        }
+
+        let (monitor_idx, workspace_idx) = self.workspace_target(button.idx);
+        log::info!(
+            "Sending dropped window {hwnd:#x} to monitor {monitor_idx:?} workspace {workspace_idx}"
+        );
+        let message = self.send_window_message(button.idx, self.config.send_follows);
+        komorebi_client::send_query(&message)?;
+        Ok(0)
+    }
+}
@@ -0,0 +1,101 @@
+use std::time::{Duration, Instant};
+
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    GetAsyncKeyState, VIRTUAL_KEY, VK_LBUTTON, VK_RBUTTON,
+};
+use winsafe::{prelude::*, *};
+
+use crate::config::HoverSwitch;
+
+use super::Window;
+
+pub(super) const HOVER_SWITCH_TIMER: usize = 8;
+
+/// How often the cursor is checked. Polling rather than `WM_MOUSEMOVE`,
+/// which the bar does not get while another window has captured the mouse
+/// for a drag.
+const POLL_INTERVAL_MS: u32 = 100;
+
+/// Workspace button the cursor has been resting on
+pub(super) struct Dwell {
+    idx: usize,
+    since: Instant,
+    /// Set once the workspace was switched to, so it happens once per visit
+    switched: bool,
+}
+
+impl Window {
+    pub(super) fn start_hover_switch(&mut self) {
+        if self.config.hover_switch == HoverSwitch::Off {
+            return;
+        }
+        if let Err(err) = self.hwnd.SetTimer(HOVER_SWITCH_TIMER, POLL_INTERVAL_MS, None) {
+            crate::errors::report(&self.hwnd, format!("Failed to start hover switching: {err}"));
+        }
+    }
+
+    pub(super) fn stop_hover_switch(&mut self) {
+        self.hwnd.KillTimer(HOVER_SWITCH_TIMER).ok();
+        self.dwell = None;
+    }
+
+    /// Switches to the workspace under the cursor once it has rested there for
+    /// `hover_switch_delay_ms`, while dragging or always, depending on config.
+    pub(super) fn handle_hover_switch_timer(&mut self) -> anyhow::Result<isize> {
+        let Some(idx) = self.dwell_target()? else {
+            self.dwell = None;
+            return Ok(0);
+        };
+
+        let dwell = match &mut self.dwell {
+            Some(dwell) if dwell.idx == idx => dwell,
+            dwell => dwell.insert(Dwell {
+                idx,
+                since: Instant::now(),
+                switched: false,
+            }),
+        };
+        let delay = Duration::from_millis(self.config.hover_switch_delay_ms.into());
+        if dwell.switched || dwell.since.elapsed() < delay {
+            return Ok(0);
+        }
+        dwell.switched = true;
+
+        if idx != self.workspaces.focused_idx() {
+            log::info!("Switching to hovered workspace {idx}");
+            komorebi_client::send_query(&self.focus_workspace_message(idx))?;
+        }
+        Ok(0)
+    }
+
+    /// The workspace button under the cursor, if hovering it may switch.
+    fn dwell_target(&self) -> anyhow::Result<Option<usize>> {
+        if !self.hwnd.IsWindowVisible() {
+            return Ok(None);
+        }
+        if self.config.hover_switch == HoverSwitch::Drag {
+            let held = |key: VIRTUAL_KEY| unsafe { GetAsyncKeyState(key.0 as i32) < 0 };
+            if !held(VK_LBUTTON) && !held(VK_RBUTTON) {
+                return Ok(None);
+            }
+        }
+
+        let cursor = GetCursorPos()?;
+        let rect = self.hwnd.GetWindowRect()?;
+        let over_bar = cursor.x >= rect.left
+            && cursor.x < rect.right
+            && cursor.y >= rect.top
+            && cursor.y < rect.bottom;
+        if !over_bar {
+            return Ok(None);
+        }
+        let point = POINT {
+            x: cursor.x - rect.left,
+            y: cursor.y - rect.top,
+        };
+        Ok(self
+            .workspace_at(point)?
+            .filter(|button| !button.new_workspace)
+            .map(|button| button.idx))
+    }
+}
@@ -0,0 +1,180 @@
+use parking_lot::Mutex;
+use windows::Win32::{
+    Foundation::{CloseHandle, HANDLE, HWND as WIN32_HWND},
+    Security::{GetTokenInformation, TOKEN_ELEVATION, TOKEN_QUERY, TokenElevation},
+    System::Threading::{
+        GetCurrentProcess, OpenProcess, OpenProcessToken, PROCESS_QUERY_LIMITED_INFORMATION,
+    },
+    UI::{
+        Accessibility::{HWINEVENTHOOK, SetWinEventHook, UnhookWinEvent},
+        WindowsAndMessaging::{
+            EVENT_SYSTEM_FOREGROUND, GetWindowThreadProcessId, WINEVENT_OUTOFCONTEXT,
+            WINEVENT_SKIPOWNPROCESS,
+        },
+    },
+};
+use winsafe::{prelude::*, *};
+
+use crate::msgs::ForegroundChanged;
+
+use super::Window;
+
+/// Shown in the state area while the foreground window is elevated
+pub(super) const ELEVATED_GLYPH: &str = "🛡";
+
+/// Each bar's foreground hook and the bar, see `drag::BARS`
+static BARS: Mutex<Vec<(isize, isize)>> = Mutex::new(Vec::new());
+
+/// Called on the UI thread whenever another window comes to the foreground.
+unsafe extern "system" fn win_event_proc(
+    hook: HWINEVENTHOOK,
+    _event: u32,
+    hwnd: WIN32_HWND,
+    _id_object: i32,
+    _id_child: i32,
+    _event_thread: u32,
+    _event_time: u32,
+) {
+    let bar = BARS
+        .lock()
+        .iter()
+        .find(|(bar_hook, _)| *bar_hook == hook.0 as isize)
+        .map(|(_, bar)| *bar);
+    if let Some(bar) = bar {
+        unsafe {
+            HWND::from_ptr(bar as _)
+                .PostMessage(ForegroundChanged::to_wndmsg(hwnd.0 as isize))
+                .ok();
+        }
+    }
+}
+
+/// Whether `process` runs elevated, `None` if its token can't be read.
+fn process_elevated(process: HANDLE) -> Option<bool> {
+    let mut token = HANDLE::default();
+    unsafe { OpenProcessToken(process, TOKEN_QUERY, &mut token) }.ok()?;
+
+    let mut elevation = TOKEN_ELEVATION::default();
+    let mut len = 0;
+    let queried = unsafe {
+        GetTokenInformation(
+            token,
+            TokenElevation,
+            Some(&mut elevation as *mut _ as _),
+            size_of::<TOKEN_ELEVATION>() as u32,
+            &mut len,
+        )
+    };
+    unsafe {
+        let _ = CloseHandle(token);
+    }
+    queried.ok()?;
+    Some(elevation.TokenIsElevated != 0)
+}
+
+/// Whether the process owning `hwnd` runs elevated. Not being allowed to read
+/// its token is taken as a sign that it is.
+fn window_elevated(hwnd: isize) -> bool {
+    let mut pid = 0;
+    unsafe { GetWindowThreadProcessId(WIN32_HWND(hwnd as _), Some(&mut pid as *mut _)) };
+    if pid == 0 {
+        return false;
+    }
+
+    let Ok(process) = (unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) })
+    else {
+        return false;
+    };
+    let elevated = process_elevated(process).unwrap_or(true);
+    unsafe {
+        let _ = CloseHandle(process);
+    }
+    elevated
+}
+
+impl Window {
+    /// Starts watching the foreground window for elevated ones, which a
+    /// komorebi running without admin rights can't manage. An elevated bar
+    /// assumes komorebi is elevated too.
+    pub(super) fn register_foreground_tracking(&mut self) {
+        if self.foreground_hook.is_some()
+            || process_elevated(unsafe { GetCurrentProcess() }).unwrap_or(false)
+        {
+            return;
+        }
+
+        let hook = unsafe {
+            SetWinEventHook(
+                EVENT_SYSTEM_FOREGROUND,
+                EVENT_SYSTEM_FOREGROUND,
+                None,
+                Some(win_event_proc),
+                0,
+                0,
+                WINEVENT_OUTOFCONTEXT | WINEVENT_SKIPOWNPROCESS,
+            )
+        };
+        if hook.is_invalid() {
+            log::error!("Failed to track the foreground window");
+            return;
+        }
+        BARS.lock().push((hook.0 as isize, self.hwnd.ptr() as isize));
+        self.foreground_hook = Some(hook);
+    }
+
+    pub(super) fn unregister_foreground_tracking(&mut self) {
+        if let Some(hook) = self.foreground_hook.take() {
+            BARS.lock().retain(|(bar_hook, _)| *bar_hook != hook.0 as isize);
+            unsafe { UnhookWinEvent(hook) }.ok();
+        }
+    }
+
+    pub(super) fn handle_foreground_changed(&mut self, hwnd: isize) -> anyhow::Result<isize> {
+        let elevated = window_elevated(hwnd);
+        if elevated == self.elevated_foreground {
+            return Ok(0);
+        }
+        log::info!("Foreground window {hwnd:#x} elevated: {elevated}");
+        self.elevated_foreground = elevated;
+        self.refresh()
+    }
+
+    /// Draws a warning glyph starting at `left` while the foreground window is
+    /// elevated, and returns the width it takes. Clicking it explains why.
+    pub(super) fn paint_elevated_glyph(
+        &self,
+        hdc: &HDC,
+        rect: RECT,
+        left: i32,
+        paint: bool,
+    ) -> anyhow::Result<i32> {
+        if !self.elevated_foreground {
+            return Ok(0);
+        }
+
+        let font = &self.settings.font;
+        let width = self.text_width(hdc, font, ELEVATED_GLYPH)? + self.padding();
+        if paint {
+            let glyph_rect = RECT {
+                left,
+                right: left + width,
+                top: rect.top,
+                bottom: rect.bottom,
+            };
+            hdc.SetTextColor(self.settings.colors.warning)?;
+            self.draw_text(hdc, font, ELEVATED_GLYPH, glyph_rect, true)?;
+            hdc.SetTextColor(self.settings.colors.foreground)?;
+            self.elevated_glyph_span.set(Some((left, left + width)));
+        }
+        Ok(width)
+    }
+
+    pub(super) fn explain_elevated_foreground(&self) -> anyhow::Result<isize> {
+        self.hwnd.MessageBox(
+            self.strings().elevated_foreground,
+            "komoswitch",
+            co::MB::OK | co::MB::ICONINFORMATION,
+        )?;
+        Ok(0)
+    }
+}
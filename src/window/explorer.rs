@@ -0,0 +1,65 @@
+use winsafe::{prelude::*, *};
+
+use super::{CLASS_NAME, Window};
+
+/// Registered message Explorer broadcasts to top-level windows whenever it
+/// (re)creates the taskbar
+const TASKBAR_CREATED_MESSAGE: &str = "TaskbarCreated";
+
+pub(super) fn taskbar_created_message() -> anyhow::Result<co::WM> {
+    let id = RegisterWindowMessage(TASKBAR_CREATED_MESSAGE)?;
+    Ok(unsafe { co::WM::from_raw(id) })
+}
+
+impl Window {
+    /// Brings the bar back after it was destroyed along with the taskbar it
+    /// was parented to, as a standalone window until the taskbar is back.
+    pub(super) fn recreate(&mut self) {
+        log::warn!("Bar destroyed along with the taskbar, recreating it");
+        if let Err(err) = self.try_recreate() {
+            log::error!("Failed to recreate the bar: {err}");
+            crate::eventlog::error(&format!("Failed to recreate the bar: {err:#}"));
+            PostQuitMessage(0);
+        }
+    }
+
+    fn try_recreate(&mut self) -> anyhow::Result<()> {
+        self.embedded = false;
+        self.vertical = false;
+
+        let hinstance = HINSTANCE::GetModuleHandle(None)?;
+        let atom = self.register_class(&hinstance, CLASS_NAME)?;
+        let width = self.get_window_width()?;
+        let height = self.px(super::taskbar::STANDALONE_HEIGHT);
+        self.create_window(
+            atom,
+            POINT::default(),
+            SIZE {
+                cx: width,
+                cy: height,
+            },
+            &hinstance,
+        )?;
+        // opened for the old window, whose options it was previewing
+        if self.settings_dialog.is_some() {
+            self.cancel_settings_dialog()?;
+        }
+
+        // Explorer is most likely still restarting, `TaskbarCreated` embeds
+        // the bar and brings the tray icon back once it's done
+        self.embed_or_standalone()?;
+        self.handle_dpi_changed()?;
+        self.register_listeners();
+        self.set_visibility(self.visibility)?;
+        Ok(())
+    }
+
+    /// Puts the bar back into the new taskbar and re-adds the tray icon, which
+    /// Explorer forgets when it restarts.
+    pub(super) fn handle_taskbar_created(&mut self) -> anyhow::Result<isize> {
+        log::info!("Taskbar created, re-embedding the bar");
+        self.tray_icon = false;
+        self.show_tray_icon_if_wanted();
+        self.handle_embed_retry()
+    }
+}
@@ -0,0 +1,91 @@
+use windows::Win32::{
+    Foundation::HWND as WIN32_HWND,
+    UI::Input::KeyboardAndMouse::{
+        HOT_KEY_MODIFIERS, MOD_ALT, MOD_CONTROL, MOD_NOREPEAT, MOD_SHIFT, MOD_WIN, RegisterHotKey,
+        UnregisterHotKey,
+    },
+};
+use winsafe::prelude::*;
+
+use crate::config::Hotkey;
+
+use super::Window;
+
+const HOTKEY_TOGGLE: i32 = 1;
+const HOTKEY_PALETTE: i32 = 2;
+const HOTKEY_WINDOW_SEARCH: i32 = 3;
+const HOTKEY_NAVIGATE: i32 = 4;
+/// Id of the first custom command's hotkey, offset by its position in the config
+const HOTKEY_CUSTOM: i32 = 100;
+
+fn modifiers(hotkey: &Hotkey) -> HOT_KEY_MODIFIERS {
+    let mut modifiers = MOD_NOREPEAT;
+    for (enabled, modifier) in [
+        (hotkey.ctrl, MOD_CONTROL),
+        (hotkey.alt, MOD_ALT),
+        (hotkey.shift, MOD_SHIFT),
+        (hotkey.win, MOD_WIN),
+    ] {
+        if enabled {
+            modifiers = modifiers | modifier;
+        }
+    }
+    modifiers
+}
+
+impl Window {
+    fn hotkeys(&self) -> Vec<(i32, Hotkey)> {
+        [
+            (HOTKEY_TOGGLE, self.config.toggle_hotkey),
+            (HOTKEY_PALETTE, self.config.palette_hotkey),
+            (HOTKEY_WINDOW_SEARCH, self.config.window_search_hotkey),
+            (HOTKEY_NAVIGATE, self.config.navigate_hotkey),
+        ]
+            .into_iter()
+            .chain(
+                self.config
+                    .custom_commands
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, command)| (HOTKEY_CUSTOM + idx as i32, command.hotkey)),
+            )
+            .filter_map(|(id, hotkey)| Some((id, hotkey?)))
+            .collect()
+    }
+
+    pub(super) fn register_hotkeys(&self) {
+        // hotkeys are per process, the other bars leave them to the first
+        if !self.is_primary() {
+            return;
+        }
+        let hwnd = WIN32_HWND(self.hwnd.ptr());
+        for (id, hotkey) in self.hotkeys() {
+            if let Err(err) =
+                unsafe { RegisterHotKey(Some(hwnd), id, modifiers(&hotkey), hotkey.key as u32) }
+            {
+                crate::errors::report(
+                    &self.hwnd,
+                    format!("Failed to register hotkey {hotkey}: {err}"),
+                );
+            }
+        }
+    }
+
+    pub(super) fn unregister_hotkeys(&self) {
+        let hwnd = WIN32_HWND(self.hwnd.ptr());
+        for (id, _) in self.hotkeys() {
+            unsafe { UnregisterHotKey(Some(hwnd), id) }.ok();
+        }
+    }
+
+    pub(super) fn handle_hotkey(&mut self, id: i32) -> anyhow::Result<isize> {
+        match id {
+            HOTKEY_TOGGLE => self.toggle_visibility(),
+            HOTKEY_PALETTE => self.show_command_palette(),
+            HOTKEY_WINDOW_SEARCH => self.show_window_search(),
+            HOTKEY_NAVIGATE => self.start_keyboard_navigation(),
+            id if id >= HOTKEY_CUSTOM => self.run_custom_command_at((id - HOTKEY_CUSTOM) as usize),
+            _ => Ok(0),
+        }
+    }
+}
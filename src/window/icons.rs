@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+
+use komorebi_client::Workspace;
+use windows::{
+    Win32::{
+        Foundation::{CloseHandle, HWND as WIN32_HWND, LPARAM, WPARAM},
+        Graphics::Gdi::HDC as WIN32_HDC,
+        System::Threading::{
+            OpenProcess, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+            QueryFullProcessImageNameW,
+        },
+        UI::{
+            Shell::ExtractIconExW,
+            WindowsAndMessaging::{
+                DI_NORMAL, DestroyIcon, DrawIconEx, GCLP_HICON, GCLP_HICONSM, GetClassLongPtrW,
+                GetWindowThreadProcessId, HICON, ICON_SMALL2, SMTO_ABORTIFHUNG,
+                SendMessageTimeoutW, WM_GETICON,
+            },
+        },
+    },
+    core::{PCWSTR, PWSTR},
+};
+use winsafe::{prelude::*, HDC};
+
+use super::Window;
+
+/// Size of an app icon at 96 DPI
+pub(super) const ICON_SIZE: i32 = 16;
+/// Space between app icons at 96 DPI
+pub(super) const ICON_GAP: i32 = 4;
+
+/// How long a hung window may take to answer `WM_GETICON`
+const GETICON_TIMEOUT_MS: u32 = 50;
+
+/// What is known about a window whose icon is shown
+#[derive(Clone)]
+pub(super) struct WindowApp {
+    /// Full path of the executable owning the window
+    exe: Option<String>,
+    /// Icon of the window itself, owned by that window
+    icon: Option<HICON>,
+}
+
+/// Icons extracted from executables, for windows that don't provide one.
+/// Unlike window icons these are owned by komoswitch.
+#[derive(Default)]
+pub(super) struct ExeIcons(HashMap<String, Option<HICON>>);
+
+impl Drop for ExeIcons {
+    fn drop(&mut self) {
+        for icon in self.0.values().flatten() {
+            unsafe {
+                let _ = DestroyIcon(*icon);
+            }
+        }
+    }
+}
+
+/// Windows whose icons represent `workspace`, in tiling order.
+fn workspace_windows(workspace: &Workspace) -> Vec<isize> {
+    let mut hwnds = Vec::new();
+    if let Some(container) = workspace.monocle_container() {
+        hwnds.extend(container.focused_window().map(|window| window.hwnd));
+    }
+    if let Some(window) = workspace.maximized_window() {
+        hwnds.push(window.hwnd);
+    }
+    for container in workspace.containers() {
+        hwnds.extend(container.focused_window().map(|window| window.hwnd));
+    }
+    hwnds
+}
+
+/// The small icon of another process' window. The icon stays owned by that
+/// window and must not be destroyed.
+fn query_window_icon(hwnd: isize) -> Option<HICON> {
+    let hwnd = WIN32_HWND(hwnd as _);
+    let mut result = 0usize;
+    let answered = unsafe {
+        SendMessageTimeoutW(
+            hwnd,
+            WM_GETICON,
+            WPARAM(ICON_SMALL2 as usize),
+            LPARAM(0),
+            SMTO_ABORTIFHUNG,
+            GETICON_TIMEOUT_MS,
+            Some(&mut result as *mut _),
+        )
+    };
+    if answered.0 != 0 && result != 0 {
+        return Some(HICON(result as _));
+    }
+
+    [GCLP_HICONSM, GCLP_HICON]
+        .into_iter()
+        .map(|index| unsafe { GetClassLongPtrW(hwnd, index) })
+        .find(|icon| *icon != 0)
+        .map(|icon| HICON(icon as _))
+}
+
+/// Full path of the executable that owns `hwnd`.
+fn query_window_exe(hwnd: isize) -> Option<String> {
+    let mut pid = 0;
+    unsafe { GetWindowThreadProcessId(WIN32_HWND(hwnd as _), Some(&mut pid as *mut _)) };
+    if pid == 0 {
+        return None;
+    }
+
+    let process = unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) }.ok()?;
+    let mut buffer = [0u16; 1024];
+    let mut len = buffer.len() as u32;
+    let queried = unsafe {
+        QueryFullProcessImageNameW(
+            process,
+            PROCESS_NAME_WIN32,
+            PWSTR(buffer.as_mut_ptr()),
+            &mut len,
+        )
+    };
+    unsafe {
+        let _ = CloseHandle(process);
+    }
+    queried.ok()?;
+    Some(String::from_utf16_lossy(&buffer[..len as usize]))
+}
+
+/// The first small icon embedded in the executable at `path`, owned by the
+/// caller.
+fn extract_exe_icon(path: &str) -> Option<HICON> {
+    let wide: Vec<u16> = path.encode_utf16().chain(Some(0)).collect();
+    let mut icon = HICON::default();
+    let extracted =
+        unsafe { ExtractIconExW(PCWSTR(wide.as_ptr()), 0, None, Some(&mut icon as *mut _), 1) };
+    (extracted > 0 && !icon.is_invalid()).then_some(icon)
+}
+
+impl Window {
+    fn window_app(&self, hwnd: isize) -> WindowApp {
+        self.icon_cache
+            .borrow_mut()
+            .entry(hwnd)
+            .or_insert_with(|| WindowApp {
+                exe: query_window_exe(hwnd),
+                icon: query_window_icon(hwnd),
+            })
+            .clone()
+    }
+
+    /// Windows of `workspace` to show icons for, one per application.
+    pub(super) fn workspace_app_windows(&self, workspace: &Workspace) -> Vec<isize> {
+        let mut seen_exes = Vec::new();
+        let mut hwnds = workspace_windows(workspace);
+        hwnds.retain(|hwnd| match self.window_app(*hwnd).exe {
+            Some(exe) if seen_exes.contains(&exe) => false,
+            Some(exe) => {
+                seen_exes.push(exe);
+                true
+            }
+            None => true,
+        });
+        hwnds
+    }
+
+    /// The icon of `hwnd`, falling back to the one of its executable.
+    fn window_icon(&self, hwnd: isize) -> Option<HICON> {
+        let app = self.window_app(hwnd);
+        if app.icon.is_some() {
+            return app.icon;
+        }
+
+        let exe = app.exe?;
+        *self
+            .exe_icons
+            .borrow_mut()
+            .0
+            .entry(exe)
+            .or_insert_with_key(|exe| extract_exe_icon(exe))
+    }
+
+    pub(super) fn draw_window_icon(&self, hdc: &HDC, hwnd: isize, x: i32, y: i32, size: i32) {
+        let Some(icon) = self.window_icon(hwnd) else {
+            return;
+        };
+
+        if let Err(err) = unsafe {
+            DrawIconEx(
+                WIN32_HDC(hdc.ptr()),
+                x,
+                y,
+                icon,
+                size,
+                size,
+                0,
+                None,
+                DI_NORMAL,
+            )
+        } {
+            log::debug!("Failed to draw icon of {hwnd:#x}: {err}");
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use winsafe::{prelude::*, *};
+
+use super::Window;
+
+impl Window {
+    /// Takes the keyboard focus, remembering who had it, and selects the
+    /// focused workspace for the arrow keys to move from.
+    pub(super) fn start_keyboard_navigation(&mut self) -> anyhow::Result<isize> {
+        let foreground = HWND::GetForegroundWindow();
+        // the bar may be a child of the taskbar, which then has to come forward
+        let root = self
+            .hwnd
+            .GetAncestor(co::GA::ROOT)
+            .unwrap_or_else(|| unsafe { self.hwnd.raw_copy() });
+        if foreground.as_ref() != Some(&root) {
+            self.navigation_return = foreground.map(|hwnd| hwnd.ptr() as isize);
+        }
+        root.SetForegroundWindow();
+        self.hwnd.SetFocus();
+
+        self.keyboard_selection = Some(self.workspaces.focused_idx());
+        self.render()?;
+        Ok(0)
+    }
+
+    pub(super) fn handle_keydown(&mut self, key: co::VK) -> anyhow::Result<isize> {
+        let Some(selected) = self.keyboard_selection else {
+            return Ok(0);
+        };
+        let last = self.workspaces.elements().len().saturating_sub(1);
+        // the arrows follow the buttons on screen, which run right to left
+        // while the bar is mirrored and top to bottom while it is vertical
+        let (back, forward) = match (self.vertical, self.settings.mirrored) {
+            (true, false) => (co::VK::UP, co::VK::DOWN),
+            (true, true) => (co::VK::DOWN, co::VK::UP),
+            (false, false) => (co::VK::LEFT, co::VK::RIGHT),
+            (false, true) => (co::VK::RIGHT, co::VK::LEFT),
+        };
+
+        match key {
+            key if key == back => {
+                self.keyboard_selection = Some(selected.saturating_sub(1));
+                self.render()?;
+            }
+            key if key == forward => {
+                self.keyboard_selection = Some((selected + 1).min(last));
+                self.render()?;
+            }
+            co::VK::HOME => {
+                self.keyboard_selection = Some(0);
+                self.render()?;
+            }
+            co::VK::END => {
+                self.keyboard_selection = Some(last);
+                self.render()?;
+            }
+            co::VK::RETURN | co::VK::SPACE => {
+                log::info!("Switching to workspace {selected} from the keyboard");
+                // komorebi focuses a window on the workspace, which ends the navigation
+                self.navigation_return = None;
+                komorebi_client::send_query(&self.focus_workspace_message(selected))?;
+            }
+            co::VK::ESCAPE => return self.end_keyboard_navigation(),
+            _ => {}
+        }
+        Ok(0)
+    }
+
+    /// Drops the selection and gives the focus back to the window that had it,
+    /// unless another window already took it.
+    pub(super) fn end_keyboard_navigation(&mut self) -> anyhow::Result<isize> {
+        if let Some(hwnd) = self.navigation_return.take() {
+            unsafe { HWND::from_ptr(hwnd as _) }.SetForegroundWindow();
+        }
+        self.handle_killfocus()
+    }
+
+    pub(super) fn handle_killfocus(&mut self) -> anyhow::Result<isize> {
+        self.navigation_return = None;
+        if self.keyboard_selection.take().is_some() {
+            self.render()?;
+        }
+        Ok(0)
+    }
+}
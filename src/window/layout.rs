@@ -0,0 +1,255 @@
+use winsafe::{prelude::*, *};
+
+use crate::{config::DisplayMode, komo::BarScope};
+
+use super::{
+    icons::{ICON_GAP, ICON_SIZE},
+    Visibility, Window,
+};
+
+// All lengths below are at 96 DPI and scaled with `Window::px`
+
+/// Width of a workspace button in icon-only mode
+const ICON_BUTTON_WIDTH: i32 = 32;
+
+/// Horizontal padding inside the "+N" overflow chip
+const OVERFLOW_PADDING: i32 = 4;
+
+const NEW_WORKSPACE_LABEL: &str = "+";
+
+/// Space taken by the separator between two monitors' workspaces
+pub(super) const MONITOR_SEPARATOR_GAP: i32 = 12;
+
+/// Number of a monitor, shown before its workspaces in the all-monitors mode
+pub struct MonitorLabel {
+    pub text: String,
+    pub left: i32,
+    pub width: i32,
+    /// Whether a separator sets it apart from the previous monitor
+    pub separator: bool,
+}
+
+/// Horizontal placement of a single workspace button, shared by painting and
+/// hit-testing so both always agree.
+pub struct WorkspaceButton {
+    pub idx: usize,
+    pub label: String,
+    pub left: i32,
+    pub width: i32,
+    pub label_width: i32,
+    /// Windows whose icons are drawn after the label
+    pub icons: Vec<isize>,
+    /// Left edge of the first icon
+    pub icons_left: i32,
+    /// Number of windows left out of `icons`
+    pub overflow: usize,
+    /// Left edge of the "+N" chip
+    pub overflow_left: i32,
+    pub overflow_width: i32,
+    /// The trailing "+" button, whose `idx` is the next unused workspace
+    pub new_workspace: bool,
+    /// Drawn before the first workspace of each monitor
+    pub monitor_label: Option<MonitorLabel>,
+}
+
+impl WorkspaceButton {
+    pub fn right(&self) -> i32 {
+        self.left + self.width
+    }
+
+    pub fn contains(&self, x: i32) -> bool {
+        x >= self.left && x < self.right()
+    }
+
+    /// Horizontal distance from `x` to the button, zero inside it.
+    pub fn distance(&self, x: i32) -> i32 {
+        if x < self.left {
+            self.left - x
+        } else {
+            (x - self.right() + 1).max(0)
+        }
+    }
+
+    pub fn has_content(&self) -> bool {
+        !self.label.is_empty() || !self.icons.is_empty() || self.overflow > 0
+    }
+}
+
+impl Window {
+    fn workspace_label(&self, idx: usize, name: Option<&String>) -> String {
+        match self.config.display_mode {
+            DisplayMode::Full => name.cloned().unwrap_or((idx + 1).to_string()),
+            DisplayMode::Compact => (idx + 1).to_string(),
+            DisplayMode::Icon => String::new(),
+        }
+    }
+
+    /// Width of a workspace label, measured once per text and font.
+    fn label_width(&self, hdc: &HDC, label: &str, focused: bool) -> anyhow::Result<i32> {
+        if label.is_empty() {
+            return Ok(0);
+        }
+        let key = (label.to_string(), focused);
+        if let Some(width) = self.settings.label_widths.borrow().get(&key) {
+            return Ok(*width);
+        }
+
+        let width = self.text_width(hdc, self.settings.label_font(focused), label)?;
+        self.settings.label_widths.borrow_mut().insert(key, width);
+        Ok(width)
+    }
+
+    pub(super) fn layout_workspaces(&self, hdc: &HDC) -> anyhow::Result<Vec<WorkspaceButton>> {
+        let _old_font = hdc.SelectObject(&self.settings.font)?;
+
+        let padding = self.padding();
+        let icon_size = self.px(ICON_SIZE);
+        let icon_gap = self.px(ICON_GAP);
+
+        let mut left = 0;
+        let mut buttons = Vec::with_capacity(self.workspaces.elements().len());
+        let focused_idx = self.workspaces.focused_idx();
+        for (idx, workspace) in self.workspaces.elements().iter().enumerate() {
+            if self.visibility == Visibility::Collapsed && idx != focused_idx {
+                continue;
+            }
+
+            let group = self
+                .monitor_groups
+                .iter()
+                .find(|group| group.start == idx && group.len > 0);
+            let monitor_label = match group {
+                Some(group)
+                    if self.scope == BarScope::All && self.visibility != Visibility::Collapsed =>
+                {
+                    let separator = group.start > 0;
+                    if separator {
+                        left += self.px(MONITOR_SEPARATOR_GAP);
+                    }
+                    let text = format!("M{}", group.monitor_idx + 1);
+                    let width = self.text_width(hdc, &self.settings.font, &text)?;
+                    let label = MonitorLabel {
+                        text,
+                        left,
+                        width,
+                        separator,
+                    };
+                    left += width + padding / 2;
+                    Some(label)
+                }
+                _ => None,
+            };
+
+            let label = self.workspace_label(idx, workspace.name.as_ref());
+            let label_width = self.label_width(hdc, &label, idx == focused_idx)?;
+
+            let mut icons = if self.config.app_icons {
+                self.workspace_app_windows(workspace)
+            } else {
+                Vec::new()
+            };
+            let overflow = icons.len().saturating_sub(self.config.max_app_icons);
+            icons.truncate(self.config.max_app_icons);
+            let overflow_width = if overflow > 0 {
+                hdc.GetTextExtentPoint32(&format!("+{overflow}"))?.cx
+                    + self.px(OVERFLOW_PADDING) * 2
+            } else {
+                0
+            };
+
+            let icons_left = if label.is_empty() {
+                left + padding
+            } else {
+                left + padding + label_width + icon_gap
+            };
+            let overflow_left = icons_left + icons.len() as i32 * (icon_size + icon_gap);
+
+            let content_width = if overflow > 0 {
+                overflow_left + overflow_width - left - padding
+            } else if !icons.is_empty() {
+                overflow_left - icon_gap - left - padding
+            } else {
+                label_width
+            };
+
+            let width = if content_width == 0 {
+                self.px(ICON_BUTTON_WIDTH)
+            } else {
+                content_width + padding * 2
+            };
+
+            buttons.push(WorkspaceButton {
+                idx,
+                label,
+                left,
+                width,
+                label_width,
+                icons,
+                icons_left,
+                overflow,
+                overflow_left,
+                overflow_width,
+                new_workspace: false,
+                monitor_label,
+            });
+            left += width;
+        }
+
+        if self.config.new_workspace_button && self.visibility != Visibility::Collapsed {
+            let label_width = self.text_width(hdc, &self.settings.font, NEW_WORKSPACE_LABEL)?;
+            buttons.push(WorkspaceButton {
+                idx: self.workspaces.elements().len(),
+                label: NEW_WORKSPACE_LABEL.to_string(),
+                left,
+                width: label_width + padding * 2,
+                label_width,
+                icons: Vec::new(),
+                icons_left: left + padding + label_width,
+                overflow: 0,
+                overflow_left: left + padding + label_width,
+                overflow_width: 0,
+                new_workspace: true,
+                monitor_label: None,
+            });
+        }
+
+        Ok(buttons)
+    }
+
+    /// Converts `point` in client coordinates to the coordinate along the bar
+    /// it is laid out in, which runs from the right while it is mirrored and
+    /// from the top while it stands in a vertical taskbar.
+    pub(super) fn layout_x(&self, point: POINT) -> anyhow::Result<i32> {
+        let x = if self.vertical { point.y } else { point.x };
+        if !self.settings.mirrored {
+            return Ok(x);
+        }
+        let rect = self.bar_rect()?;
+        Ok(rect.right - 1 - x)
+    }
+
+    pub(super) fn workspace_at(&self, point: POINT) -> anyhow::Result<Option<WorkspaceButton>> {
+        let x = self.layout_x(point)?;
+        let hdc = self.hwnd.GetDC()?;
+        Ok(self
+            .layout_workspaces(&hdc)?
+            .into_iter()
+            .find(|button| button.contains(x)))
+    }
+
+    /// Like `workspace_at`, but also hits the closest button up to `slop`
+    /// pixels away, for fingers that are less precise than a mouse.
+    pub(super) fn workspace_near(
+        &self,
+        point: POINT,
+        slop: i32,
+    ) -> anyhow::Result<Option<WorkspaceButton>> {
+        let x = self.layout_x(point)?;
+        let hdc = self.hwnd.GetDC()?;
+        Ok(self
+            .layout_workspaces(&hdc)?
+            .into_iter()
+            .filter(|button| button.distance(x) <= slop)
+            .min_by_key(|button| button.distance(x)))
+    }
+}
@@ -0,0 +1,315 @@
+use std::sync::OnceLock;
+
+use windows::Win32::{
+    Globalization::{GetUserDefaultUILanguage, LCIDToLocaleName},
+    System::SystemServices::LOCALE_NAME_MAX_LENGTH,
+};
+
+use super::Window;
+
+/// Text the bar shows in its menus, chips, tooltips, notifications, dialogs
+/// and command palette, in one language. Text with values in it is put
+/// together by a function.
+pub(super) struct Strings {
+    pub focus: &'static str,
+    pub windows: &'static str,
+    pub rename: &'static str,
+    pub send_here: &'static str,
+    pub send_here_and_follow: &'static str,
+    pub tile_windows: &'static str,
+    pub close_all_windows: &'static str,
+    /// Asked with the number of windows and the workspace's name
+    pub close_all_windows_confirm: fn(usize, &str) -> String,
+    pub move_to_monitor: &'static str,
+    /// Followed by the monitor's number and name
+    pub monitor: &'static str,
+    pub display_mode: &'static str,
+    pub mode_full: &'static str,
+    pub mode_compact: &'static str,
+    pub mode_icon: &'static str,
+    pub app_icons: &'static str,
+    pub layout: &'static str,
+    pub profile: &'static str,
+    pub default_profile: &'static str,
+    pub pause_tiling: &'static str,
+    pub refresh: &'static str,
+    pub commands: &'static str,
+    pub custom_commands: &'static str,
+    pub settings: &'static str,
+    pub autostart: &'static str,
+    pub show_bar: &'static str,
+    pub hide_bar: &'static str,
+    pub quit: &'static str,
+    pub copy_errors: &'static str,
+    pub clear_errors: &'static str,
+
+    pub maximized: &'static str,
+    pub monocle: &'static str,
+    pub paused: &'static str,
+    pub offline: &'static str,
+    pub incompatible: &'static str,
+    pub tiling: &'static str,
+    pub floating: &'static str,
+    pub minimized: &'static str,
+
+    /// Follows the apps komorebi's rules send to the hovered workspace
+    pub rules_here: &'static str,
+
+    pub reconnected: &'static str,
+    pub connected_detail: &'static str,
+    pub disconnected: &'static str,
+    pub disconnected_detail: &'static str,
+    pub elevated_foreground: &'static str,
+    pub overflow: &'static str,
+    pub capture_embedded: &'static str,
+
+    /// Asked with the workspace's number
+    pub rename_title: fn(usize) -> String,
+    pub settings_title: &'static str,
+    pub ok: &'static str,
+    pub cancel: &'static str,
+    pub padding: &'static str,
+    pub color_focused: &'static str,
+    pub color_focused_end: &'static str,
+    pub color_nonempty: &'static str,
+    pub color_empty: &'static str,
+    pub color_warning: &'static str,
+    pub color_hover: &'static str,
+    pub color_foreground: &'static str,
+    pub color_text_outline: &'static str,
+
+    pub palette_title: &'static str,
+    pub retile: &'static str,
+    pub toggle_float: &'static str,
+    pub toggle_monocle: &'static str,
+    pub toggle_maximize: &'static str,
+    pub promote: &'static str,
+    pub toggle_pause: &'static str,
+    pub toggle_tiling: &'static str,
+    pub flip_horizontally: &'static str,
+    pub flip_vertically: &'static str,
+    pub next_layout: &'static str,
+    pub previous_layout: &'static str,
+    pub minimize_window: &'static str,
+    pub close_window: &'static str,
+    /// A window found by the window search, with its title, its workspace's
+    /// name and its monitor's number
+    pub window_search_entry: fn(&str, &str, usize) -> String,
+}
+
+const ENGLISH: Strings = Strings {
+    focus: "Focus",
+    windows: "Windows",
+    rename: "Rename…",
+    send_here: "Send focused window here",
+    send_here_and_follow: "Send focused window here and follow",
+    tile_windows: "Tile windows",
+    close_all_windows: "Close all windows",
+    close_all_windows_confirm: |count, name| {
+        format!("Close all {count} windows on workspace {name}?")
+    },
+    move_to_monitor: "Move workspace to monitor",
+    monitor: "Monitor",
+    display_mode: "Display mode",
+    mode_full: "Full",
+    mode_compact: "Compact",
+    mode_icon: "Icon only",
+    app_icons: "App icons",
+    layout: "Layout",
+    profile: "Profile",
+    default_profile: "Default",
+    pause_tiling: "Pause tiling",
+    refresh: "Refresh now",
+    commands: "Commands…",
+    custom_commands: "Custom commands",
+    settings: "Settings…",
+    autostart: "Start with Windows",
+    show_bar: "Show bar",
+    hide_bar: "Hide bar",
+    quit: "Quit",
+    copy_errors: "Copy to clipboard",
+    clear_errors: "Clear",
+
+    maximized: "Maximized",
+    monocle: "Monocle",
+    paused: "Paused",
+    offline: "komorebi offline",
+    incompatible: "Incompatible komorebi state",
+    tiling: "Tiling",
+    floating: "Floating",
+    minimized: "Minimized",
+
+    rules_here: "→ here",
+
+    reconnected: "Reconnected to komorebi",
+    connected_detail: "The bar follows the workspaces again",
+    disconnected: "Disconnected from komorebi",
+    disconnected_detail: "The bar can't follow the workspaces until komorebi runs again",
+    elevated_foreground: "The focused window runs as administrator. komorebi can't manage it \
+                          unless komorebi runs as administrator too, so it may not react to \
+                          commands while this window is focused.",
+    overflow: "The bar is too long for the free space in the taskbar and covers some of its \
+               buttons; try the compact display mode or another anchor",
+    capture_embedded: "The bar can't be excluded from screen capture inside the taskbar, only \
+                       as a standalone bar",
+
+    rename_title: |number| format!("Rename workspace {number}"),
+    settings_title: "komoswitch settings",
+    ok: "OK",
+    cancel: "Cancel",
+    padding: "Padding",
+    color_focused: "Focused",
+    color_focused_end: "Focused end",
+    color_nonempty: "Non-empty",
+    color_empty: "Empty",
+    color_warning: "Warning",
+    color_hover: "Hover",
+    color_foreground: "Foreground",
+    color_text_outline: "Text outline",
+
+    palette_title: "komorebi commands",
+    retile: "Retile",
+    toggle_float: "Toggle float",
+    toggle_monocle: "Toggle monocle",
+    toggle_maximize: "Toggle maximize",
+    promote: "Promote",
+    toggle_pause: "Toggle pause",
+    toggle_tiling: "Toggle tiling",
+    flip_horizontally: "Flip layout horizontally",
+    flip_vertically: "Flip layout vertically",
+    next_layout: "Next layout",
+    previous_layout: "Previous layout",
+    minimize_window: "Minimize window",
+    close_window: "Close window",
+    window_search_entry: |title, workspace, monitor| {
+        format!("{title} — {workspace}, monitor {monitor}")
+    },
+};
+
+const GERMAN: Strings = Strings {
+    focus: "Fokussieren",
+    windows: "Fenster",
+    rename: "Umbenennen…",
+    send_here: "Fokussiertes Fenster hierher senden",
+    send_here_and_follow: "Fokussiertes Fenster hierher senden und folgen",
+    tile_windows: "Fenster kacheln",
+    close_all_windows: "Alle Fenster schließen",
+    close_all_windows_confirm: |count, name| {
+        format!("Alle {count} Fenster auf Arbeitsbereich {name} schließen?")
+    },
+    move_to_monitor: "Arbeitsbereich auf Monitor verschieben",
+    monitor: "Monitor",
+    display_mode: "Anzeigemodus",
+    mode_full: "Vollständig",
+    mode_compact: "Kompakt",
+    mode_icon: "Nur Symbol",
+    app_icons: "App-Symbole",
+    layout: "Layout",
+    profile: "Profil",
+    default_profile: "Standard",
+    pause_tiling: "Kacheln pausieren",
+    refresh: "Jetzt aktualisieren",
+    commands: "Befehle…",
+    custom_commands: "Eigene Befehle",
+    settings: "Einstellungen…",
+    autostart: "Mit Windows starten",
+    show_bar: "Leiste anzeigen",
+    hide_bar: "Leiste ausblenden",
+    quit: "Beenden",
+    copy_errors: "In die Zwischenablage kopieren",
+    clear_errors: "Leeren",
+
+    maximized: "Maximiert",
+    monocle: "Monocle",
+    paused: "Pausiert",
+    offline: "komorebi offline",
+    incompatible: "Inkompatibler komorebi-Zustand",
+    tiling: "Gekachelt",
+    floating: "Schwebend",
+    minimized: "Minimiert",
+
+    rules_here: "→ hierher",
+
+    reconnected: "Wieder mit komorebi verbunden",
+    connected_detail: "Die Leiste folgt den Arbeitsbereichen wieder",
+    disconnected: "Verbindung zu komorebi getrennt",
+    disconnected_detail: "Die Leiste kann den Arbeitsbereichen erst wieder folgen, wenn \
+                          komorebi läuft",
+    elevated_foreground: "Das fokussierte Fenster läuft als Administrator. komorebi kann es \
+                          nur verwalten, wenn komorebi ebenfalls als Administrator läuft, und \
+                          reagiert daher eventuell nicht auf Befehle, solange dieses Fenster \
+                          fokussiert ist.",
+    overflow: "Die Leiste ist zu lang für den freien Platz in der Taskleiste und verdeckt \
+               einige ihrer Schaltflächen; versuche den kompakten Anzeigemodus oder einen \
+               anderen Anker",
+    capture_embedded: "Die Leiste kann in der Taskleiste nicht von Bildschirmaufnahmen \
+                       ausgenommen werden, nur als eigenständige Leiste",
+
+    rename_title: |number| format!("Arbeitsbereich {number} umbenennen"),
+    settings_title: "komoswitch-Einstellungen",
+    ok: "OK",
+    cancel: "Abbrechen",
+    padding: "Abstand",
+    color_focused: "Fokussiert",
+    color_focused_end: "Fokussiert (Ende)",
+    color_nonempty: "Belegt",
+    color_empty: "Leer",
+    color_warning: "Warnung",
+    color_hover: "Hover",
+    color_foreground: "Vordergrund",
+    color_text_outline: "Textkontur",
+
+    palette_title: "komorebi-Befehle",
+    retile: "Neu kacheln",
+    toggle_float: "Schweben umschalten",
+    toggle_monocle: "Monocle umschalten",
+    toggle_maximize: "Maximieren umschalten",
+    promote: "Nach vorne holen",
+    toggle_pause: "Pause umschalten",
+    toggle_tiling: "Kacheln umschalten",
+    flip_horizontally: "Layout horizontal spiegeln",
+    flip_vertically: "Layout vertikal spiegeln",
+    next_layout: "Nächstes Layout",
+    previous_layout: "Vorheriges Layout",
+    minimize_window: "Fenster minimieren",
+    close_window: "Fenster schließen",
+    window_search_entry: |title, workspace, monitor| {
+        format!("{title} — {workspace}, Monitor {monitor}")
+    },
+};
+
+/// Languages by their ISO 639-1 code, the first one being the fallback
+const LOCALES: &[(&str, &Strings)] = &[("en", &ENGLISH), ("de", &GERMAN)];
+
+/// Language of the Windows user interface, e.g. `de` for `de-CH`.
+fn windows_language() -> &'static str {
+    static LANGUAGE: OnceLock<String> = OnceLock::new();
+    LANGUAGE.get_or_init(|| {
+        let mut name = [0u16; LOCALE_NAME_MAX_LENGTH as usize];
+        let len =
+            unsafe { LCIDToLocaleName(GetUserDefaultUILanguage() as u32, Some(&mut name), 0) };
+        // the length includes the terminating null, 0 meaning failure
+        let name = String::from_utf16_lossy(&name[..(len.max(1) - 1) as usize]);
+        name.split('-').next().unwrap_or_default().to_lowercase()
+    })
+}
+
+/// The strings for `locale`, a language code like `de` or `de-DE`, or for the
+/// Windows display language when it isn't set, English for any other.
+pub(super) fn strings(locale: Option<&str>) -> &'static Strings {
+    let language = match locale {
+        Some(locale) => locale.split(['-', '_']).next().unwrap_or_default(),
+        None => windows_language(),
+    };
+    LOCALES
+        .iter()
+        .find(|(code, _)| code.eq_ignore_ascii_case(language))
+        .map_or(LOCALES[0].1, |(_, strings)| *strings)
+}
+
+impl Window {
+    /// The strings in the language `locale` asks for
+    pub(super) fn strings(&self) -> &'static Strings {
+        strings(self.config.locale.as_deref())
+    }
+}
@@ -0,0 +1,667 @@
+use komorebi_client::{Layout, SocketMessage};
+use winsafe::{prelude::*, *};
+
+use crate::{config::DisplayMode, komo::DEFAULT_LAYOUTS};
+
+use super::Window;
+
+seq_ids! {
+    ID_EXIT = 1001;
+    ID_MODE_FULL
+    ID_MODE_COMPACT
+    ID_MODE_ICON
+    ID_COPY_ERRORS
+    ID_CLEAR_ERRORS
+    ID_HIDE
+    ID_SETTINGS
+    ID_APP_ICONS
+    ID_FOCUS_WORKSPACE
+    ID_RENAME_WORKSPACE
+    ID_SEND_TO_WORKSPACE
+    ID_SEND_TO_WORKSPACE_AND_FOLLOW
+    ID_CLOSE_WORKSPACE_WINDOWS
+    ID_TILE_WORKSPACE
+    ID_REFRESH
+    ID_PAUSE
+    ID_PALETTE
+    ID_AUTOSTART
+}
+
+/// Longest error message shown in the error popup before truncating
+const MAX_ERROR_LABEL: usize = 120;
+
+/// First command id of the "Move workspace to monitor" entries, offset by the
+/// monitor index.
+const ID_MOVE_WORKSPACE_TO_MONITOR: u16 = 2000;
+const MAX_MONITORS: u16 = 64;
+
+/// Command id of the "Default" profile entry; the named profiles follow it.
+const ID_PROFILE: u16 = 3000;
+const MAX_PROFILES: u16 = 100;
+
+/// First command id of the "Windows" entries, offset by their position.
+const ID_WINDOW: u16 = 4000;
+const MAX_WINDOWS: u16 = 100;
+
+/// First command id of the "Layout" entries, offset by their position in
+/// `DEFAULT_LAYOUTS`.
+const ID_LAYOUT: u16 = 5000;
+
+/// First command id of the "Custom commands" entries, offset by the command's
+/// position in the config.
+const ID_CUSTOM_COMMAND: u16 = 6000;
+const MAX_CUSTOM_COMMANDS: u16 = 100;
+
+/// Longest window title shown in the "Windows" submenu before truncating
+const MAX_WINDOW_LABEL: usize = 60;
+
+impl Window {
+    pub(super) fn handle_command(&mut self, mut p: msg::wm::Command) -> anyhow::Result<isize> {
+        match p.event.ctrl_id() {
+            ID_EXIT => {
+                log::info!("Exiting application...");
+                unsafe {
+                    self.hwnd
+                        .PostMessage(msg::WndMsg::new(co::WM::CLOSE, 0, 0))?;
+                }
+                Ok(0)
+            }
+            ID_MODE_FULL => self.set_display_mode(DisplayMode::Full),
+            ID_MODE_COMPACT => self.set_display_mode(DisplayMode::Compact),
+            ID_MODE_ICON => self.set_display_mode(DisplayMode::Icon),
+            ID_COPY_ERRORS => {
+                super::clipboard::set_text(
+                    &self.hwnd,
+                    &crate::errors::describe_all().join("\r\n"),
+                )?;
+                Ok(0)
+            }
+            ID_HIDE => self.toggle_visibility(),
+            ID_SETTINGS => self.show_settings_dialog(),
+            ID_PALETTE => self.show_command_palette(),
+            ID_REFRESH => {
+                log::info!("Refreshing from komorebi's full state");
+                self.resync()
+            }
+            ID_PAUSE => {
+                log::info!("Toggling pause, currently paused: {}", self.paused);
+                komorebi_client::send_query(&SocketMessage::TogglePause)?;
+                self.resync()
+            }
+            ID_AUTOSTART => self.toggle_autostart(),
+            ID_APP_ICONS => {
+                self.config_file.set("app_icons", !self.config.app_icons)?;
+                self.apply_config()
+            }
+            ID_FOCUS_WORKSPACE => match self.context_workspace.take() {
+                Some(idx) => {
+                    komorebi_client::send_query(&self.focus_workspace_message(idx))?;
+                    Ok(0)
+                }
+                None => Ok(0),
+            },
+            ID_RENAME_WORKSPACE => match self.context_workspace.take() {
+                Some(idx) => self.show_rename_dialog(idx),
+                None => Ok(0),
+            },
+            ID_SEND_TO_WORKSPACE => self.send_to_context_workspace(false),
+            ID_SEND_TO_WORKSPACE_AND_FOLLOW => self.send_to_context_workspace(true),
+            ID_CLOSE_WORKSPACE_WINDOWS => self.close_workspace_windows(),
+            ID_TILE_WORKSPACE => self.toggle_workspace_tiling(),
+            ID_CLEAR_ERRORS => {
+                crate::errors::clear();
+                Ok(self.handle_errors_changed())
+            }
+            id if (ID_MOVE_WORKSPACE_TO_MONITOR..ID_MOVE_WORKSPACE_TO_MONITOR + MAX_MONITORS)
+                .contains(&id) =>
+            {
+                let monitor_idx = (id - ID_MOVE_WORKSPACE_TO_MONITOR) as usize;
+                self.move_workspace_to_monitor(monitor_idx)
+            }
+            id if (ID_WINDOW..ID_WINDOW + MAX_WINDOWS).contains(&id) => {
+                self.focus_listed_window((id - ID_WINDOW) as usize)
+            }
+            id if (ID_CUSTOM_COMMAND..ID_CUSTOM_COMMAND + MAX_CUSTOM_COMMANDS).contains(&id) => {
+                self.run_custom_command_at((id - ID_CUSTOM_COMMAND) as usize)
+            }
+            id if (ID_LAYOUT..ID_LAYOUT + DEFAULT_LAYOUTS.len() as u16).contains(&id) => {
+                let (layout, name) = DEFAULT_LAYOUTS[(id - ID_LAYOUT) as usize];
+                log::info!("Changing the focused workspace's layout to {name}");
+                komorebi_client::send_query(&SocketMessage::ChangeLayout(layout))?;
+                Ok(0)
+            }
+            ID_PROFILE => self.set_profile(None),
+            id if (ID_PROFILE + 1..=ID_PROFILE + MAX_PROFILES).contains(&id) => {
+                let profiles = self.config_file.profiles();
+                match profiles.get((id - ID_PROFILE - 1) as usize) {
+                    Some(name) => self.set_profile(Some(name)),
+                    None => Ok(0),
+                }
+            }
+            _ => Ok(unsafe { self.hwnd.DefWindowProc(p.as_generic_wm()) }),
+        }
+    }
+
+    /// Sends the focused window to the context workspace, and follows it there
+    /// if `follow` is set.
+    fn send_to_context_workspace(&mut self, follow: bool) -> anyhow::Result<isize> {
+        let Some(idx) = self.context_workspace.take() else {
+            return Ok(0);
+        };
+        log::info!("Sending focused window to workspace {idx}, following: {follow}");
+        komorebi_client::send_query(&self.send_window_message(idx, follow))?;
+        Ok(0)
+    }
+
+    /// Turns komorebi's tiling of the context workspace on or off.
+    fn toggle_workspace_tiling(&mut self) -> anyhow::Result<isize> {
+        let Some(idx) = self.context_workspace.take() else {
+            return Ok(0);
+        };
+        let Some(tile) = self.workspaces.elements().get(idx).map(|workspace| workspace.tile)
+        else {
+            return Ok(0);
+        };
+
+        let (monitor_idx, workspace_idx) = self.workspace_target(idx);
+        let monitor_idx = match monitor_idx {
+            Some(monitor_idx) => monitor_idx,
+            None => crate::komo::read_state()?.monitors.focused_idx(),
+        };
+        log::info!("Turning tiling of workspace {idx} {}", if tile { "off" } else { "on" });
+        komorebi_client::send_query(&SocketMessage::WorkspaceTiling(
+            monitor_idx,
+            workspace_idx,
+            !tile,
+        ))?;
+        self.resync()
+    }
+
+    /// Asks every window on the context workspace to close, after confirming.
+    fn close_workspace_windows(&mut self) -> anyhow::Result<isize> {
+        let Some(idx) = self.context_workspace.take() else {
+            return Ok(0);
+        };
+        let Some(workspace) = self.workspaces.elements().get(idx) else {
+            return Ok(0);
+        };
+        let hwnds: Vec<isize> = crate::komo::workspace_windows(workspace)
+            .iter()
+            .map(|window| window.hwnd)
+            .collect();
+        if hwnds.is_empty() {
+            return Ok(0);
+        }
+
+        let name = workspace.name.clone().unwrap_or((idx + 1).to_string());
+        let answer = self.hwnd.MessageBox(
+            &(self.strings().close_all_windows_confirm)(hwnds.len(), &name),
+            "komoswitch",
+            co::MB::YESNO | co::MB::ICONWARNING,
+        )?;
+        if answer != co::DLGID::YES {
+            return Ok(0);
+        }
+
+        log::info!("Closing {} windows on workspace {idx}", hwnds.len());
+        for hwnd in hwnds {
+            unsafe {
+                HWND::from_ptr(hwnd as _)
+                    .PostMessage(msg::WndMsg::new(co::WM::CLOSE, 0, 0))
+                    .ok();
+            }
+        }
+        Ok(0)
+    }
+
+    /// Focuses the `pos`th window of the "Windows" submenu. komorebi switches
+    /// to its workspace, then picks the window up as focused once it comes to
+    /// the foreground.
+    fn focus_listed_window(&mut self, pos: usize) -> anyhow::Result<isize> {
+        let windows = std::mem::take(&mut self.context_windows);
+        let (Some(workspace_idx), Some(hwnd)) = (self.context_workspace.take(), windows.get(pos))
+        else {
+            return Ok(0);
+        };
+
+        log::info!("Focusing window {hwnd:#x} on workspace {workspace_idx}");
+        if workspace_idx != self.workspaces.focused_idx() {
+            komorebi_client::send_query(&self.focus_workspace_message(workspace_idx))?;
+        }
+        komorebi_client::WindowsApi::raise_and_focus_window(*hwnd)?;
+        Ok(0)
+    }
+
+    /// Lists the windows on workspace `idx` by title, or `None` when it has
+    /// none. Their handles are kept in `context_windows` for the command.
+    fn create_window_menu(&mut self, idx: usize) -> anyhow::Result<Option<HMENU>> {
+        let Some(workspace) = self.workspaces.elements().get(idx) else {
+            return Ok(None);
+        };
+        let (hwnds, labels): (Vec<isize>, Vec<String>) = crate::komo::workspace_windows(workspace)
+            .into_iter()
+            .take(MAX_WINDOWS as usize)
+            .map(|window| {
+                let title = window.title().unwrap_or_default();
+                let mut label: String = title.chars().take(MAX_WINDOW_LABEL).collect();
+                if label.len() < title.len() {
+                    label.push('…');
+                }
+                if label.is_empty() {
+                    label = window.exe().unwrap_or_else(|_| format!("{:#x}", window.hwnd));
+                }
+                (window.hwnd, label.replace('&', "&&"))
+            })
+            .unzip();
+        self.context_windows = hwnds;
+        if labels.is_empty() {
+            return Ok(None);
+        }
+
+        let menu = HMENU::CreatePopupMenu()?;
+        let items: Vec<MenuItem> = labels
+            .iter()
+            .enumerate()
+            .map(|(pos, text)| MenuItem::Entry {
+                cmd_id: ID_WINDOW + pos as u16,
+                text,
+            })
+            .collect();
+        menu.append_item(&items)?;
+        Ok(Some(menu))
+    }
+
+    fn move_workspace_to_monitor(&mut self, monitor_idx: usize) -> anyhow::Result<isize> {
+        let Some(workspace_idx) = self.context_workspace.take() else {
+            return Ok(0);
+        };
+
+        log::info!("Moving workspace {workspace_idx} to monitor {monitor_idx}");
+        komorebi_client::send_query(&self.focus_workspace_message(workspace_idx))?;
+        komorebi_client::send_query(&SocketMessage::MoveWorkspaceToMonitorNumber(monitor_idx))?;
+        self.resync()
+    }
+
+    /// Moves workspace `idx` to the next monitor, or with `swap` trades it for
+    /// the workspace focused there.
+    pub(super) fn move_workspace_to_next_monitor(
+        &mut self,
+        idx: usize,
+        swap: bool,
+    ) -> anyhow::Result<isize> {
+        let state = crate::komo::read_state()?;
+        let monitors = state.monitors.elements().len();
+        if monitors < 2 {
+            return Ok(0);
+        }
+        let monitor_idx = self
+            .workspace_target(idx)
+            .0
+            .unwrap_or(state.monitors.focused_idx());
+        let next_monitor = (monitor_idx + 1) % monitors;
+
+        komorebi_client::send_query(&self.focus_workspace_message(idx))?;
+        if swap {
+            log::info!("Swapping workspace {idx} with monitor {next_monitor}");
+            komorebi_client::send_query(&SocketMessage::SwapWorkspacesToMonitorNumber(
+                next_monitor,
+            ))?;
+        } else {
+            log::info!("Moving workspace {idx} to monitor {next_monitor}");
+            komorebi_client::send_query(&SocketMessage::MoveWorkspaceToMonitorNumber(
+                next_monitor,
+            ))?;
+        }
+        self.resync()
+    }
+
+    /// Lists every monitor other than workspace `idx`'s, or `None` when there
+    /// is nowhere to move it to.
+    fn create_monitor_menu(&self, idx: usize) -> anyhow::Result<Option<HMENU>> {
+        let state = crate::komo::read_state()?;
+        let focused_idx = self
+            .workspace_target(idx)
+            .0
+            .unwrap_or(state.monitors.focused_idx());
+
+        let labels: Vec<(u16, String)> = state
+            .monitors
+            .elements()
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| *idx != focused_idx && *idx < MAX_MONITORS as usize)
+            .map(|(idx, monitor)| {
+                (
+                    ID_MOVE_WORKSPACE_TO_MONITOR + idx as u16,
+                    format!("{} {} ({})", self.strings().monitor, idx + 1, monitor.name),
+                )
+            })
+            .collect();
+
+        if labels.is_empty() {
+            return Ok(None);
+        }
+
+        let menu = HMENU::CreatePopupMenu()?;
+        let items: Vec<MenuItem> = labels
+            .iter()
+            .map(|(cmd_id, text)| MenuItem::Entry {
+                cmd_id: *cmd_id,
+                text,
+            })
+            .collect();
+        menu.append_item(&items)?;
+        Ok(Some(menu))
+    }
+
+    /// Lists recent errors, opened by clicking the error badge.
+    pub(super) fn show_error_menu(&mut self, coords: POINT) -> anyhow::Result<isize> {
+        let labels: Vec<String> = crate::errors::describe_all()
+            .into_iter()
+            .map(|description| {
+                let mut label: String = description.chars().take(MAX_ERROR_LABEL).collect();
+                if label.len() < description.len() {
+                    label.push('…');
+                }
+                label.replace('&', "&&")
+            })
+            .collect();
+
+        let mut items: Vec<MenuItem> = labels
+            .iter()
+            .map(|text| MenuItem::Entry { cmd_id: 0, text })
+            .collect();
+        items.push(MenuItem::Separator);
+        items.push(MenuItem::Entry {
+            cmd_id: ID_COPY_ERRORS,
+            text: self.strings().copy_errors,
+        });
+        items.push(MenuItem::Entry {
+            cmd_id: ID_CLEAR_ERRORS,
+            text: self.strings().clear_errors,
+        });
+
+        let mut menu = HMENU::CreatePopupMenu()?;
+        menu.append_item(&items)?;
+        for pos in 0..labels.len() {
+            menu.EnableMenuItem(IdPos::Pos(pos as u32), false)?;
+        }
+
+        menu.track_popup_menu_at_point(coords, &self.hwnd, &self.hwnd)?;
+        menu.DestroyMenu()?;
+        Ok(0)
+    }
+
+    /// Lists the config profiles, or `None` when the config defines none.
+    fn create_profile_menu(&self) -> anyhow::Result<Option<HMENU>> {
+        let profiles = self.config_file.profiles();
+        if profiles.is_empty() {
+            return Ok(None);
+        }
+
+        let labels: Vec<String> = profiles
+            .iter()
+            .take(MAX_PROFILES as usize)
+            .map(|name| name.replace('&', "&&"))
+            .collect();
+
+        let mut items = vec![MenuItem::Entry {
+            cmd_id: ID_PROFILE,
+            text: self.strings().default_profile,
+        }];
+        items.extend(labels.iter().enumerate().map(|(idx, text)| MenuItem::Entry {
+            cmd_id: ID_PROFILE + 1 + idx as u16,
+            text,
+        }));
+
+        let menu = HMENU::CreatePopupMenu()?;
+        menu.append_item(&items)?;
+
+        let active = self
+            .config_file
+            .active_profile()
+            .and_then(|active| profiles.iter().position(|name| name == active))
+            .map_or(ID_PROFILE, |idx| ID_PROFILE + 1 + idx as u16);
+        menu.CheckMenuItem(IdPos::Id(active), true)?;
+        Ok(Some(menu))
+    }
+
+    /// Lists the custom commands to be shown in the menu, or `None` when there
+    /// are none.
+    fn create_custom_command_menu(&self) -> anyhow::Result<Option<HMENU>> {
+        let items: Vec<MenuItem> = self
+            .config
+            .custom_commands
+            .iter()
+            .take(MAX_CUSTOM_COMMANDS as usize)
+            .enumerate()
+            .filter(|(_, command)| command.menu)
+            .map(|(idx, command)| MenuItem::Entry {
+                cmd_id: ID_CUSTOM_COMMAND + idx as u16,
+                text: &command.name,
+            })
+            .collect();
+        if items.is_empty() {
+            return Ok(None);
+        }
+
+        let menu = HMENU::CreatePopupMenu()?;
+        menu.append_item(&items)?;
+        Ok(Some(menu))
+    }
+
+    /// Lists komorebi's built-in layouts, checking the focused workspace's.
+    fn create_layout_menu(&self) -> anyhow::Result<HMENU> {
+        let menu = HMENU::CreatePopupMenu()?;
+        let items: Vec<MenuItem> = DEFAULT_LAYOUTS
+            .iter()
+            .enumerate()
+            .map(|(pos, (_, name))| MenuItem::Entry {
+                cmd_id: ID_LAYOUT + pos as u16,
+                text: *name,
+            })
+            .collect();
+        menu.append_item(&items)?;
+
+        let current = self.workspaces.focused().and_then(|workspace| match &workspace.layout {
+            Layout::Default(current) => {
+                DEFAULT_LAYOUTS.iter().position(|(layout, _)| layout == current)
+            }
+            Layout::Custom(_) => None,
+        });
+        if let Some(pos) = current {
+            menu.CheckMenuItem(IdPos::Id(ID_LAYOUT + pos as u16), true)?;
+        }
+        Ok(menu)
+    }
+
+    fn create_mode_menu(&self) -> anyhow::Result<HMENU> {
+        let strings = self.strings();
+        let menu = HMENU::CreatePopupMenu()?;
+        menu.append_item(&[
+            MenuItem::Entry {
+                cmd_id: ID_MODE_FULL,
+                text: strings.mode_full,
+            },
+            MenuItem::Entry {
+                cmd_id: ID_MODE_COMPACT,
+                text: strings.mode_compact,
+            },
+            MenuItem::Entry {
+                cmd_id: ID_MODE_ICON,
+                text: strings.mode_icon,
+            },
+            MenuItem::Separator,
+            MenuItem::Entry {
+                cmd_id: ID_APP_ICONS,
+                text: strings.app_icons,
+            },
+        ])?;
+        let checked_mode = match self.config.display_mode {
+            DisplayMode::Full => ID_MODE_FULL,
+            DisplayMode::Compact => ID_MODE_COMPACT,
+            DisplayMode::Icon => ID_MODE_ICON,
+        };
+        menu.CheckMenuItem(IdPos::Id(checked_mode), true)?;
+        menu.CheckMenuItem(IdPos::Id(ID_APP_ICONS), self.config.app_icons)?;
+        Ok(menu)
+    }
+
+    pub(super) fn handle_rbuttondown(&mut self, p: msg::wm::RButtonDown) -> anyhow::Result<isize> {
+        log::info!("Handling WM_RBUTTONDOWN message");
+        log::info!("Cursor at: ({}, {})", p.coords.x, p.coords.y);
+
+        let workspace = self
+            .workspace_at(p.coords)?
+            .filter(|button| !button.new_workspace)
+            .map(|button| button.idx);
+        self.show_context_menu(p.coords, workspace)
+    }
+
+    /// Shows the context menu at `coords`, relative to the bar, with entries
+    /// for `workspace` if one was clicked.
+    pub(super) fn show_context_menu(
+        &mut self,
+        coords: POINT,
+        workspace: Option<usize>,
+    ) -> anyhow::Result<isize> {
+        self.context_workspace = workspace;
+        let monitor_menu = match self.context_workspace {
+            Some(idx) => self.create_monitor_menu(idx).unwrap_or_else(|err| {
+                log::error!("Failed to list monitors: {err}");
+                None
+            }),
+            None => None,
+        };
+        let window_menu = match self.context_workspace {
+            Some(idx) => self.create_window_menu(idx)?,
+            None => None,
+        };
+        let mode_menu = self.create_mode_menu()?;
+        let layout_menu = self.create_layout_menu()?;
+        let custom_command_menu = self.create_custom_command_menu()?;
+        let profile_menu = self.create_profile_menu()?;
+
+        let strings = self.strings();
+        let mut items = Vec::new();
+        let version_label = self.version_mismatch.as_ref().map(|text| text.replace('&', "&&"));
+        if let Some(text) = &version_label {
+            items.push(MenuItem::Entry { cmd_id: 0, text });
+            items.push(MenuItem::Separator);
+        }
+        if self.context_workspace.is_some() {
+            items.push(MenuItem::Entry {
+                cmd_id: ID_FOCUS_WORKSPACE,
+                text: strings.focus,
+            });
+            if let Some(window_menu) = &window_menu {
+                items.push(MenuItem::Submenu {
+                    submenu: window_menu,
+                    text: strings.windows,
+                });
+            }
+            items.push(MenuItem::Entry {
+                cmd_id: ID_RENAME_WORKSPACE,
+                text: strings.rename,
+            });
+            items.push(MenuItem::Entry {
+                cmd_id: ID_SEND_TO_WORKSPACE,
+                text: strings.send_here,
+            });
+            items.push(MenuItem::Entry {
+                cmd_id: ID_SEND_TO_WORKSPACE_AND_FOLLOW,
+                text: strings.send_here_and_follow,
+            });
+            items.push(MenuItem::Entry {
+                cmd_id: ID_TILE_WORKSPACE,
+                text: strings.tile_windows,
+            });
+            items.push(MenuItem::Entry {
+                cmd_id: ID_CLOSE_WORKSPACE_WINDOWS,
+                text: strings.close_all_windows,
+            });
+            items.push(MenuItem::Separator);
+        }
+        if let Some(monitor_menu) = &monitor_menu {
+            items.push(MenuItem::Submenu {
+                submenu: monitor_menu,
+                text: strings.move_to_monitor,
+            });
+            items.push(MenuItem::Separator);
+        }
+        items.push(MenuItem::Submenu {
+            submenu: &mode_menu,
+            text: strings.display_mode,
+        });
+        items.push(MenuItem::Submenu {
+            submenu: &layout_menu,
+            text: strings.layout,
+        });
+        if let Some(profile_menu) = &profile_menu {
+            items.push(MenuItem::Submenu {
+                submenu: profile_menu,
+                text: strings.profile,
+            });
+        }
+        items.push(MenuItem::Entry {
+            cmd_id: ID_PAUSE,
+            text: strings.pause_tiling,
+        });
+        items.push(MenuItem::Entry {
+            cmd_id: ID_REFRESH,
+            text: strings.refresh,
+        });
+        items.push(MenuItem::Entry {
+            cmd_id: ID_PALETTE,
+            text: strings.commands,
+        });
+        if let Some(custom_command_menu) = &custom_command_menu {
+            items.push(MenuItem::Submenu {
+                submenu: custom_command_menu,
+                text: strings.custom_commands,
+            });
+        }
+        items.push(MenuItem::Entry {
+            cmd_id: ID_SETTINGS,
+            text: strings.settings,
+        });
+        items.push(MenuItem::Entry {
+            cmd_id: ID_AUTOSTART,
+            text: strings.autostart,
+        });
+        items.push(MenuItem::Separator);
+        // without a way to bring it back, hiding the bar would need a restart
+        if self.tray_icon || self.config.toggle_hotkey.is_some() {
+            items.push(MenuItem::Entry {
+                cmd_id: ID_HIDE,
+                text: if self.visibility == super::Visibility::Hidden {
+                    strings.show_bar
+                } else {
+                    strings.hide_bar
+                },
+            });
+        }
+        items.push(MenuItem::Entry {
+            cmd_id: ID_EXIT,
+            text: strings.quit,
+        });
+
+        let mut menu = HMENU::CreatePopupMenu()?;
+        menu.append_item(&items)?;
+        if version_label.is_some() {
+            // informational, like the errors in `show_error_menu`
+            menu.EnableMenuItem(IdPos::Pos(0), false)?;
+        }
+        menu.CheckMenuItem(IdPos::Id(ID_PAUSE), self.paused)?;
+        menu.CheckMenuItem(IdPos::Id(ID_AUTOSTART), super::autostart::is_enabled())?;
+        if let Some(workspace) = workspace.and_then(|idx| self.workspaces.elements().get(idx)) {
+            menu.CheckMenuItem(IdPos::Id(ID_TILE_WORKSPACE), workspace.tile)?;
+        }
+
+        menu.track_popup_menu_at_point(coords, &self.hwnd, &self.hwnd)?;
+        log::debug!("Menu displayed");
+        menu.DestroyMenu()?;
+        log::debug!("Menu destroyed");
+        Ok(0)
+    }
+}
@@ -1,37 +1,406 @@
-use crate::{msgs::UpdateWorkspaces, window::settings::Settings};
-use komorebi_client::{DefaultLayout, Layout, Ring, SocketMessage, Workspace};
-use windows::Win32::UI::WindowsAndMessaging::WM_SETTINGCHANGE;
+use crate::{
+    config::{Config, ConfigFile, DisplayMode, StartupVisibility},
+    komo::{BarScope, MonitorGroup},
+    msgs::{
+        BarUpdate, BarUpdated, ColorsChanged, ErrorsChanged, ForegroundChanged, KomorebiOnline,
+        PresentationMode, ShowInstance, TaskbarAlignment, ToggleVisibility, TrayIcon,
+        WindowDragged,
+    },
+    window::settings::Settings,
+};
+use komorebi_client::{
+    CycleDirection, DefaultLayout, Layout, OperationDirection, Ring, SocketMessage, Workspace,
+};
+use windows::Win32::Foundation::HWND as WIN32_HWND;
+use windows::UI::ViewManagement::UISettings;
+use windows::Win32::System::Power::HPOWERNOTIFY;
+use windows::Win32::UI::Accessibility::HWINEVENTHOOK;
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    GetKeyState, TME_LEAVE, TRACKMOUSEEVENT, TrackMouseEvent, VK_MENU,
+};
+use windows::Win32::UI::WindowsAndMessaging::{
+    WM_DPICHANGED, WM_DPICHANGED_AFTERPARENT, WM_POINTERDOWN, WM_POINTERUP, WM_POWERBROADCAST,
+    WM_SETTINGCHANGE, WM_WTSSESSION_CHANGE,
+};
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicIsize, Ordering};
+use std::sync::mpsc::{Receiver, Sender};
 use winsafe::{prelude::*, *};
 
+mod animation;
+mod attention;
+mod autostart;
+mod capture;
+mod click;
+mod clipboard;
+mod colors;
+mod commands;
+mod dialog;
+mod displays;
+mod dpi;
+mod drag;
+mod dwell;
+mod elevation;
+mod explorer;
+mod hotkeys;
+mod icons;
+mod keyboard;
+mod layout;
+mod locale;
+mod menu;
+mod pager;
+mod paint;
+mod palette;
+mod power;
+mod rename_dialog;
+mod render;
 mod settings;
+mod session;
+mod settings_dialog;
+mod standalone;
+mod taskbar;
+mod taskbar_icons;
+mod text;
+mod tooltip;
+mod touch;
+mod tray;
+mod workspace_drag;
+
+pub use displays::Bars;
+pub use taskbar::TaskbarContent;
+
+pub const CLASS_NAME: &str = "komoswitch";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Visibility {
+    Shown,
+    /// Only the focused workspace button is drawn
+    Collapsed,
+    Hidden,
+}
+
+/// The monitor a bar shows when `per_monitor_bars` gives each its own
+#[derive(Debug, PartialEq, Eq)]
+pub struct BarMonitor {
+    /// Index of the monitor in komorebi's state
+    pub idx: usize,
+    /// `HMONITOR` of the monitor, as komorebi reports it
+    pub handle: isize,
+}
 
-seq_ids! {
-    ID_EXIT = 1001;
+/// The bar's window as seen by the threads posting to it, which keeps
+/// pointing at the bar when it is recreated after Explorer restarts.
+#[derive(Clone)]
+pub struct BarHandle {
+    hwnd: Arc<AtomicIsize>,
+    updates: Sender<BarUpdate>,
 }
+
+impl BarHandle {
+    pub fn get(&self) -> HWND {
+        unsafe { HWND::from_ptr(self.hwnd.load(Ordering::Acquire) as _) }
+    }
+
+    /// Queues `update` for the bar and wakes it up to take it. Once the bar is
+    /// gone the update is dropped right away, and one that never gets taken
+    /// is dropped along with the bar.
+    pub fn send(&self, update: BarUpdate) {
+        if self.updates.send(update).is_err() {
+            return;
+        }
+        unsafe {
+            self.get().PostMessage(BarUpdated::to_wndmsg()).ok();
+        }
+    }
+}
+
 pub struct Window {
     pub hwnd: HWND,
+    /// Follows `hwnd`, see `BarHandle`
+    handle: BarHandle,
+    /// What `handle` sends the bar, taken on `BarUpdated`
+    updates: Receiver<BarUpdate>,
+    /// Monitor the bar is pinned to, `None` following the focused monitor
+    monitor: Option<BarMonitor>,
+    scope: BarScope,
     workspaces: Ring<Workspace>,
+    /// Which monitor each of `workspaces` is on, as of the last update
+    monitor_groups: Vec<MonitorGroup>,
+    /// Apps routed to each workspace by monitor and workspace index, read
+    /// from the static komorebi config at startup
+    workspace_rules: Vec<Vec<Vec<String>>>,
     settings: Settings,
+    config_file: ConfigFile,
+    /// Options in effect, derived from `config_file`
+    config: Config,
+    /// Set while a presentation tool is running; repaints are throttled
+    quiet: bool,
+    pending_refresh: bool,
+    /// Workspace the open context menu was requested for
+    context_workspace: Option<usize>,
+    /// Windows listed in the context menu's "Windows" submenu, in menu order
+    context_windows: Vec<isize>,
+    error_badge_visible: bool,
+    /// Left edge of the error badge as of the last paint
+    error_badge_left: Cell<Option<i32>>,
+    /// Wheel movement not yet amounting to a switch, see `ScrollOptions::notches`
+    wheel_delta: i32,
+    /// Left and right edges of the container pager as of the last paint
+    pager_span: Cell<Option<(i32, i32)>>,
+    /// Left and right edges of the "Paused" chip as of the last paint
+    paused_chip_span: Cell<Option<(i32, i32)>>,
+    /// Left and right edges of the workspace layer chip as of the last paint
+    layer_chip_span: Cell<Option<(i32, i32)>>,
+    /// Left and right edges of the layout name chip as of the last paint
+    layout_chip_span: Cell<Option<(i32, i32)>>,
+    /// Left and right edges of the stack position chip as of the last paint
+    stack_chip_span: Cell<Option<(i32, i32)>>,
+    /// Left and right edges of the elevated window glyph as of the last paint
+    elevated_glyph_span: Cell<Option<(i32, i32)>>,
+    /// Whether the bar is parented to the taskbar rather than standalone
+    embedded: bool,
+    /// Top-left corner the user moved the standalone bar to, see
+    /// `standalone_origin`
+    standalone_position: Option<POINT>,
+    /// Whether the bar stands upright in a taskbar docked to the left or
+    /// right; it is still laid out and painted lying down, then rotated
+    vertical: bool,
+    /// Taskbar buttons and clock as of the last resize, see `TaskbarContent`
+    taskbar_content: RefCell<taskbar::TaskbarContent>,
+    /// Set while the taskbar's content is being measured for the bar
+    content_requested: Cell<bool>,
+    /// Set while the bar doesn't fit between the taskbar's buttons
+    overflow: Cell<bool>,
+    /// Set once the bar is closed on purpose, any other destruction comes
+    /// from the taskbar it is parented to going away
+    closing: bool,
+    /// Set once the bar's monitor is gone, see `Bars`
+    removed: bool,
+    visibility: Visibility,
+    tray_icon: bool,
+    /// Set while the tray icon is only there for a balloon, see `show_balloon`
+    balloon_tray_icon: bool,
+    /// Registered `ToggleVisibility` message
+    toggle_msg: co::WM,
+    /// Registered `ShowInstance` message
+    show_msg: co::WM,
+    /// Registered message of the shell hook notifications
+    shell_hook_msg: co::WM,
+    /// Registered message Explorer broadcasts once its taskbar is back
+    taskbar_created_msg: co::WM,
+    /// Windows on unfocused workspaces flashing for attention
+    attention: HashSet<isize>,
+    /// App icons by window, cleared whenever the workspaces change
+    icon_cache: RefCell<HashMap<isize, icons::WindowApp>>,
+    exe_icons: RefCell<icons::ExeIcons>,
+    /// DPI of the monitor the bar is on; see `px`
+    dpi: u32,
+    settings_dialog: Option<HWND>,
+    rename_dialog: Option<rename_dialog::RenameDialog>,
+    command_palette: Option<palette::CommandPalette>,
+    focus_animation: Option<animation::FocusAnimation>,
+    /// Set while the bar grows or shrinks to its new width
+    width_animation: Cell<Option<animation::WidthAnimation>>,
+    theme_transition: Option<animation::ThemeTransition>,
+    /// Workspace focused before the current one, which clicking the focused
+    /// button goes back to
+    previous_workspace: Option<usize>,
+    /// Button clicked once, waiting to see whether a double-click follows
+    pending_click: Option<usize>,
+    /// Whether komorebi's tiling is paused, shown in the focused indicator
+    paused: bool,
+    /// Set while komorebi can't be reached; the bar is dimmed as its
+    /// workspaces may be out of date
+    offline: bool,
+    /// Set once komorebi was reached, see `notify_connection`
+    ever_online: bool,
+    /// Set while komorebi's state could only be read in part, see
+    /// `BarUpdate::Workspaces`
+    incompatible_state: bool,
+    /// Warning shown in the context menu and tray tooltip while the running
+    /// komorebi is not the version the bar was built against
+    version_mismatch: Option<String>,
+    /// Where the finger touching the bar came down, in screen coordinates
+    touch_start: Option<POINT>,
+    /// Workspace button the arrow keys moved to while the bar has the focus
+    keyboard_selection: Option<usize>,
+    /// Window to give the focus back to when keyboard navigation is cancelled
+    navigation_return: Option<isize>,
+    /// Workspace button under the cursor, tracked until `WM_MOUSELEAVE`
+    hovered_workspace: Option<usize>,
+    /// Workspace button held down, see `WorkspaceDrag`
+    workspace_drag: Option<workspace_drag::WorkspaceDrag>,
+    /// Title of the focused window, refreshed with every komorebi update
+    focused_title: String,
+    drag_hook: Option<HWINEVENTHOOK>,
+    /// Window being moved by the user, which may be dropped on a button
+    dragged_window: Option<isize>,
+    foreground_hook: Option<HWINEVENTHOOK>,
+    /// Registration for resume notifications, see `register_resume_notification`
+    power_notify: Option<HPOWERNOTIFY>,
+    /// Registration for color changes, see `register_color_notification`
+    color_notify: Option<(UISettings, i64)>,
+    /// Whether the bar gets lock and unlock notifications
+    session_notify: bool,
+    /// Registration for display state changes, see
+    /// `register_session_notifications`
+    display_notify: Option<HPOWERNOTIFY>,
+    /// Set while the session is locked, see `unseen`
+    locked: bool,
+    /// Set while the display is off, see `unseen`
+    display_off: bool,
+    /// Registrations for power source and battery saver changes, see
+    /// `register_power_source_notifications`
+    power_source_notify: Vec<HPOWERNOTIFY>,
+    /// Set while running on battery, see `saving_power`
+    on_battery: bool,
+    /// Set while Windows' battery saver is on, see `saving_power`
+    battery_saver: bool,
+    /// Hash of the frame last handed to the screen, see `render_dirty`
+    last_frame: Cell<Option<u64>>,
+    /// Whether the foreground window runs elevated, out of komorebi's reach
+    elevated_foreground: bool,
+    dwell: Option<dwell::Dwell>,
+    /// Lists the windows of the hovered workspace, created on first hover
+    tooltip: Option<HWND>,
 }
 
-const TEXT_PADDING: i32 = 20; // Padding around text in pixels
+const BORDER_RADIUS: SIZE = SIZE { cx: 10, cy: 10 };
+
+const QUIET_REFRESH_TIMER: usize = 1;
+const QUIET_REFRESH_INTERVAL_MS: u32 = 1000;
+
+const ERROR_BADGE_TIMER: usize = 2;
+const ERROR_BADGE_INTERVAL_MS: u32 = 30_000;
+const ERROR_BADGE: &str = "⚠";
 
 impl Window {
-    pub fn new() -> anyhow::Result<Self> {
+    pub fn new(config_file: ConfigFile, monitor: Option<BarMonitor>) -> anyhow::Result<Self> {
+        let config = config_file.effective();
+        let scope = match &monitor {
+            Some(monitor) => BarScope::Monitor(monitor.idx),
+            None if config.all_monitors => BarScope::All,
+            None => BarScope::Focused,
+        };
+        let (workspaces, monitor_groups, incompatible_state) = Self::initial_workspaces(scope);
+        let workspace_rules =
+            crate::komo::workspace_rules_from_static_config().unwrap_or_else(|err| {
+                log::debug!("Could not read workspace rules from komorebi.json: {err}");
+                Vec::new()
+            });
+        let (sender, updates) = std::sync::mpsc::channel();
         Ok(Self {
             hwnd: HWND::NULL,
-            workspaces: loop {
-                let Ok(new_workspaces) = crate::komo::read_workspaces() else {
-                    log::error!("Failed to read workspaces, retrying...");
-                    std::thread::sleep(std::time::Duration::from_secs(2));
-                    continue;
-                };
-                break new_workspaces;
+            handle: BarHandle {
+                hwnd: Arc::new(AtomicIsize::new(0)),
+                updates: sender,
             },
-            settings: Settings::new()?,
+            updates,
+            monitor,
+            scope,
+            workspaces,
+            monitor_groups,
+            workspace_rules,
+            settings: Settings::new(&config, dpi::DEFAULT_DPI)?,
+            config_file,
+            config,
+            quiet: false,
+            pending_refresh: false,
+            context_workspace: None,
+            context_windows: Vec::new(),
+            error_badge_visible: false,
+            error_badge_left: Cell::new(None),
+            wheel_delta: 0,
+            pager_span: Cell::new(None),
+            paused_chip_span: Cell::new(None),
+            layer_chip_span: Cell::new(None),
+            layout_chip_span: Cell::new(None),
+            stack_chip_span: Cell::new(None),
+            elevated_glyph_span: Cell::new(None),
+            embedded: false,
+            standalone_position: None,
+            vertical: false,
+            taskbar_content: RefCell::new(taskbar::TaskbarContent::default()),
+            content_requested: Cell::new(false),
+            overflow: Cell::new(false),
+            closing: false,
+            removed: false,
+            visibility: Visibility::Shown,
+            tray_icon: false,
+            balloon_tray_icon: false,
+            toggle_msg: ToggleVisibility::id()?,
+            show_msg: ShowInstance::id()?,
+            shell_hook_msg: attention::shell_hook_message()?,
+            taskbar_created_msg: explorer::taskbar_created_message()?,
+            attention: HashSet::new(),
+            icon_cache: RefCell::new(HashMap::new()),
+            exe_icons: RefCell::new(icons::ExeIcons::default()),
+            dpi: dpi::DEFAULT_DPI,
+            settings_dialog: None,
+            rename_dialog: None,
+            command_palette: None,
+            focus_animation: None,
+            width_animation: Cell::new(None),
+            theme_transition: None,
+            previous_workspace: None,
+            pending_click: None,
+            paused: false,
+            offline: false,
+            ever_online: false,
+            incompatible_state,
+            version_mismatch: None,
+            touch_start: None,
+            keyboard_selection: None,
+            navigation_return: None,
+            hovered_workspace: None,
+            workspace_drag: None,
+            focused_title: String::new(),
+            drag_hook: None,
+            dragged_window: None,
+            foreground_hook: None,
+            power_notify: None,
+            color_notify: None,
+            session_notify: false,
+            display_notify: None,
+            locked: false,
+            display_off: false,
+            power_source_notify: Vec::new(),
+            on_battery: false,
+            battery_saver: false,
+            last_frame: Cell::new(None),
+            elevated_foreground: false,
+            dwell: None,
+            tooltip: None,
         })
     }
 
+    /// Reads the workspaces from komorebi, falling back to the names in the
+    /// static komorebi config until the listener thread delivers the real ones.
+    /// Also tells whether komorebi's state could only be read in part.
+    fn initial_workspaces(scope: BarScope) -> (Ring<Workspace>, Vec<MonitorGroup>, bool) {
+        loop {
+            if let Ok(state) = crate::komo::read_state_workspaces() {
+                if let Ok((workspaces, groups)) = state.in_scope(scope) {
+                    return (workspaces, groups, !state.compatible);
+                }
+            }
+
+            let monitor_idx = match scope {
+                BarScope::Monitor(idx) => Some(idx),
+                _ => None,
+            };
+            match crate::komo::workspaces_from_static_config(monitor_idx) {
+                Ok(workspaces) => return (workspaces, Vec::new(), false),
+                Err(err) => log::debug!("Could not seed workspaces from komorebi.json: {err}"),
+            }
+
+            log::error!("Failed to read workspaces, retrying...");
+            std::thread::sleep(std::time::Duration::from_secs(2));
+        }
+    }
+
     pub fn register_class(&self, hinst: &HINSTANCE, class_name: &str) -> anyhow::Result<ATOM> {
         let mut wcx = WNDCLASSEX::default();
         wcx.lpfnWndProc = Some(Self::wnd_proc);
@@ -39,6 +408,7 @@ impl Window {
         wcx.hCursor = HINSTANCE::NULL
             .LoadCursor(IdIdcStr::Idc(co::IDC::ARROW))?
             .leak();
+        wcx.style = co::CS::DBLCLKS;
 
         let mut wclass_name = if class_name.trim().is_empty() {
             WString::from_str(&format!(
@@ -120,6 +490,7 @@ impl Window {
                 log::info!("HWND NCCREATE: {:#?}", hwnd);
                 let ref_self = unsafe { &mut *ptr_self };
                 ref_self.hwnd = unsafe { hwnd.raw_copy() };
+                ref_self.handle.hwnd.store(hwnd.ptr() as isize, Ordering::Release);
                 return unsafe { hwnd.DefWindowProc(wm_any) }; // continue processing
             }
             _ => hwnd.GetWindowLongPtr(co::GWLP::USERDATA) as *mut Self, // retrieve
@@ -138,322 +509,520 @@ impl Window {
                 ref_self.hwnd.SetWindowLongPtr(co::GWLP::USERDATA, 0); // clear passed pointer
             }
             ref_self.cleanup();
+            if !ref_self.closing && !ref_self.removed {
+                ref_self.recreate();
+            }
             return 0;
         }
 
         ref_self.handle_message(wm_any).unwrap_or_else(|err| {
-            log::error!("Application error: {err}");
+            crate::errors::report(&hwnd, format!("Application error: {err}"));
             0
         })
     }
 
     fn handle_message(&mut self, p: msg::WndMsg) -> anyhow::Result<isize> {
         const SETTINGCHANGED: co::WM = unsafe { co::WM::from_raw(WM_SETTINGCHANGE) };
+        // top-level windows get the former, the bar embedded in the taskbar the latter
+        const DPICHANGED: co::WM = unsafe { co::WM::from_raw(WM_DPICHANGED) };
+        const DPICHANGED_AFTERPARENT: co::WM =
+            unsafe { co::WM::from_raw(WM_DPICHANGED_AFTERPARENT) };
+        const POINTERDOWN: co::WM = unsafe { co::WM::from_raw(WM_POINTERDOWN) };
+        const POINTERUP: co::WM = unsafe { co::WM::from_raw(WM_POINTERUP) };
+        const POWERBROADCAST: co::WM = unsafe { co::WM::from_raw(WM_POWERBROADCAST) };
+        const WTSSESSION_CHANGE: co::WM = unsafe { co::WM::from_raw(WM_WTSSESSION_CHANGE) };
+        if p.msg_id == self.toggle_msg {
+            return self.toggle_visibility();
+        }
+        if p.msg_id == self.show_msg {
+            return self.handle_show_instance();
+        }
+        if p.msg_id == self.shell_hook_msg {
+            return self.handle_shell_hook(p.wparam, p.lparam);
+        }
+        if p.msg_id == self.taskbar_created_msg {
+            return self.handle_taskbar_created();
+        }
+
         match p.msg_id {
             co::WM::CREATE => self.handle_create(),
             co::WM::PAINT => self.handle_paint(),
             co::WM::LBUTTONDOWN => {
                 self.handle_lbuttondown(unsafe { msg::wm::RButtonDown::from_generic_wm(p) })
             }
+            co::WM::LBUTTONDBLCLK => {
+                self.handle_lbuttondblclk(unsafe { msg::wm::LButtonDblClk::from_generic_wm(p) })
+            }
+            co::WM::MOUSEMOVE => {
+                self.handle_mousemove(unsafe { msg::wm::MouseMove::from_generic_wm(p) })
+            }
+            co::WM::LBUTTONUP => {
+                let p = unsafe { msg::wm::LButtonUp::from_generic_wm(p) };
+                self.handle_workspace_drop(p.vkey_code.has(co::MK::CONTROL))
+            }
+            co::WM::CAPTURECHANGED => self.handle_capture_changed(),
+            co::WM::MOUSELEAVE => self.handle_mouseleave(),
+            co::WM::XBUTTONDOWN => self.handle_xbuttondown(p.wparam),
+            co::WM::MOUSEWHEEL => self.handle_mousewheel(p.wparam, p.lparam),
+            co::WM::KEYDOWN => {
+                self.handle_keydown(unsafe { msg::wm::KeyDown::from_generic_wm(p) }.vkey_code)
+            }
+            co::WM::KILLFOCUS => self.handle_killfocus(),
+            POINTERDOWN | POINTERUP => self.handle_pointer(p),
             co::WM::RBUTTONDOWN => {
                 self.handle_rbuttondown(unsafe { msg::wm::RButtonDown::from_generic_wm(p) })
             }
             co::WM::COMMAND => self.handle_command(unsafe { msg::wm::Command::from_generic_wm(p) }),
-            UpdateWorkspaces::ID => self.handle_update_workspaces(UpdateWorkspaces::from_wndmsg(p)),
-            SETTINGCHANGED => self.handle_setting_changed(),
+            co::WM::TIMER => self.handle_timer(unsafe { msg::wm::Timer::from_generic_wm(p) }),
+            BarUpdated::ID => self.handle_bar_updates(),
+            PresentationMode::ID => {
+                self.handle_presentation_mode(PresentationMode::from_wndmsg(p))
+            }
+            ErrorsChanged::ID => Ok(self.handle_errors_changed()),
+            KomorebiOnline::ID => self.handle_komorebi_online(KomorebiOnline::from_wndmsg(p)),
+            SETTINGCHANGED | ColorsChanged::ID => self.handle_setting_changed(),
+            DPICHANGED | DPICHANGED_AFTERPARENT => self.handle_dpi_changed(),
+            POWERBROADCAST => self.handle_power_broadcast(p.wparam, p.lparam),
+            WTSSESSION_CHANGE => self.handle_session_change(p.wparam),
+            co::WM::DISPLAYCHANGE => self.handle_display_change(),
+            co::WM::EXITSIZEMOVE => self.handle_exit_size_move(),
+            co::WM::HOTKEY => self.handle_hotkey(p.wparam as i32),
+            TrayIcon::ID => self.handle_tray_icon(p),
+            TaskbarAlignment::ID => {
+                self.handle_taskbar_alignment(TaskbarAlignment::from_wndmsg(p))
+            }
+            WindowDragged::ID => self.handle_window_dragged(WindowDragged::from_wndmsg(p)),
+            ForegroundChanged::ID => {
+                self.handle_foreground_changed(ForegroundChanged::from_wndmsg(p))
+            }
+            co::WM::CLOSE => {
+                self.closing = true;
+                Ok(unsafe { self.hwnd.DefWindowProc(p) })
+            }
             co::WM::DESTROY => {
-                PostQuitMessage(0);
+                self.remove_tray_icon();
+                self.unregister_listeners();
+                // owned by the bar, so destroyed along with it
+                self.tooltip = None;
+                if self.closing {
+                    PostQuitMessage(0);
+                }
                 Ok(0)
             }
             _ => Ok(unsafe { self.hwnd.DefWindowProc(p) }),
         }
     }
 
-    fn handle_command(&mut self, mut p: msg::wm::Command) -> anyhow::Result<isize> {
-        match p.event.ctrl_id() {
-            ID_EXIT => {
-                log::info!("Exiting application...");
-                unsafe {
-                    self.hwnd
-                        .PostMessage(msg::WndMsg::new(co::WM::CLOSE, 0, 0))?;
-                }
-                Ok(0)
+    fn handle_lbuttondown(&mut self, p: msg::wm::RButtonDown) -> anyhow::Result<isize> {
+        log::info!("Handling WM_LBUTTONDOWN message");
+        if self.visibility == Visibility::Collapsed {
+            return self.set_visibility(Visibility::Shown);
+        }
+
+        let x = self.layout_x(p.coords)?;
+        if self
+            .error_badge_left
+            .get()
+            .is_some_and(|badge_left| x >= badge_left)
+        {
+            return self.show_error_menu(p.coords);
+        }
+        if self
+            .elevated_glyph_span
+            .get()
+            .is_some_and(|(left, right)| x >= left && x < right)
+        {
+            return self.explain_elevated_foreground();
+        }
+        if self
+            .paused_chip_span
+            .get()
+            .is_some_and(|(left, right)| x >= left && x < right)
+        {
+            log::info!("Resuming tiling from the paused chip");
+            komorebi_client::send_query(&SocketMessage::TogglePause)?;
+            return self.resync();
+        }
+        if self
+            .layer_chip_span
+            .get()
+            .is_some_and(|(left, right)| x >= left && x < right)
+        {
+            log::info!("Toggling the focused workspace's layer");
+            komorebi_client::send_query(&SocketMessage::ToggleWorkspaceLayer)?;
+            return Ok(0);
+        }
+        if self
+            .layout_chip_span
+            .get()
+            .is_some_and(|(left, right)| x >= left && x < right)
+        {
+            log::info!("Cycling the focused workspace's layout");
+            komorebi_client::send_query(&SocketMessage::CycleLayout(CycleDirection::Next))?;
+            return Ok(0);
+        }
+
+        let Some(button) = self.workspace_at(p.coords)? else {
+            if !self.embedded {
+                return self.start_standalone_move();
             }
-            _ => Ok(unsafe { self.hwnd.DefWindowProc(p.as_generic_wm()) }),
+            return Ok(0);
+        };
+        // Alt is not among the MK_* flags the message carries
+        if unsafe { GetKeyState(VK_MENU.0 as i32) } < 0 {
+            return self.show_rename_dialog(button.idx);
+        }
+        if p.vkey_code.has(co::MK::SHIFT) && !button.new_workspace {
+            let swap = p.vkey_code.has(co::MK::CONTROL);
+            return self.move_workspace_to_next_monitor(button.idx, swap);
+        }
+        if p.vkey_code.has(co::MK::CONTROL) {
+            // komorebi's move follows the window, its send stays behind
+            let message = self.send_window_message(button.idx, self.config.send_follows);
+            log::info!("Sending focused window to workspace {}", button.idx);
+            komorebi_client::send_query(&message)?;
+        } else if button.new_workspace {
+            // komorebi creates the workspace when asked to focus past the last one
+            log::info!("Creating workspace {}", button.idx);
+            komorebi_client::send_query(&self.focus_workspace_message(button.idx))?;
+        } else {
+            self.arm_workspace_drag(&button, p.coords);
+            return self.handle_click(&button);
+        }
+        Ok(0)
+    }
+
+    fn handle_lbuttondblclk(&mut self, p: msg::wm::LButtonDblClk) -> anyhow::Result<isize> {
+        match self.workspace_at(p.coords)? {
+            Some(button) if !button.new_workspace => self.handle_double_click(&button),
+            _ => Ok(0),
         }
     }
 
-    fn handle_rbuttondown(&mut self, p: msg::wm::RButtonDown) -> anyhow::Result<isize> {
-        log::info!("Handling WM_RBUTTONDOWN message");
-        log::info!("Cursor at: ({}, {})", p.coords.x, p.coords.y);
-        let mut menu = HMENU::CreatePopupMenu()?;
-        menu.append_item(&[winsafe::MenuItem::Entry {
-            cmd_id: ID_EXIT,
-            text: "Quit",
-        }])?;
+    /// Scrolling over the pager moves between containers, over the stack chip
+    /// between the windows of the focused stack, anywhere else it cycles
+    /// workspaces.
+    fn handle_mousewheel(&mut self, wparam: usize, lparam: isize) -> anyhow::Result<isize> {
+        const WHEEL_DELTA: i32 = 120;
 
-        menu.track_popup_menu_at_point(p.coords, &self.hwnd, &self.hwnd)?;
-        log::debug!("Menu displayed");
-        menu.DestroyMenu()?;
-        log::debug!("Menu destroyed");
+        let mut delta = (wparam >> 16) as u16 as i16 as i32;
+        if self.config.scroll.reverse {
+            delta = -delta;
+        }
+        // high resolution wheels send fractions of a notch, which add up
+        self.wheel_delta = self.wheel_delta.saturating_add(delta);
+        let notches = i32::try_from(self.config.scroll.notches.max(1)).unwrap_or(i32::MAX);
+        let step = WHEEL_DELTA.saturating_mul(notches);
+        if self.wheel_delta.abs() < step {
+            return Ok(0);
+        }
+        let up = self.wheel_delta > 0;
+        self.wheel_delta = 0;
+
+        // the cursor position comes in screen coordinates
+        let rect = self.hwnd.GetWindowRect()?;
+        let x = self.layout_x(POINT {
+            x: (lparam & 0xffff) as u16 as i16 as i32 - rect.left,
+            y: (lparam >> 16 & 0xffff) as u16 as i16 as i32 - rect.top,
+        })?;
+
+        let over_pager = self
+            .pager_span
+            .get()
+            .is_some_and(|(left, right)| x >= left && x < right);
+        let over_stack = self
+            .stack_chip_span
+            .get()
+            .is_some_and(|(left, right)| x >= left && x < right);
+        let message = if over_stack {
+            SocketMessage::CycleStack(if up {
+                CycleDirection::Previous
+            } else {
+                CycleDirection::Next
+            })
+        } else if over_pager && !self.focused_layout_scrolls() {
+            // containers of other layouts aren't all side by side
+            SocketMessage::CycleFocusWindow(if up {
+                CycleDirection::Previous
+            } else {
+                CycleDirection::Next
+            })
+        } else if over_pager {
+            SocketMessage::FocusWindow(if up {
+                OperationDirection::Left
+            } else {
+                OperationDirection::Right
+            })
+        } else if self.config.scroll.wrap && self.scope == BarScope::Focused {
+            SocketMessage::CycleFocusWorkspace(if up {
+                CycleDirection::Previous
+            } else {
+                CycleDirection::Next
+            })
+        } else {
+            // komorebi only cycles the focused monitor, other bars wrap themselves
+            let focused_idx = self.workspaces.focused_idx();
+            let count = self.workspaces.elements().len();
+            let target = if count == 0 {
+                None
+            } else if self.config.scroll.wrap {
+                Some((focused_idx + if up { count - 1 } else { 1 }) % count)
+            } else if up {
+                focused_idx.checked_sub(1)
+            } else {
+                Some(focused_idx + 1).filter(|idx| *idx < count)
+            };
+            let Some(target) = target else {
+                return Ok(0);
+            };
+            self.focus_workspace_message(target)
+        };
+        let direction = if up { "up" } else { "down" };
+        log::info!("Scrolling {direction}, over pager: {over_pager}, over stack: {over_stack}");
+        komorebi_client::send_query(&message)?;
         Ok(0)
     }
-    fn handle_lbuttondown(&mut self, p: msg::wm::RButtonDown) -> anyhow::Result<isize> {
-        log::info!("Handling WM_LBUTTONDOWN message");
-        let mut left = 0;
-        let hdc = self.hwnd.GetDC()?;
-        let rect = self.hwnd.GetClientRect()?;
-        let focused_idx = self.workspaces.focused_idx();
-        for (idx, workspace) in self.workspaces.elements().iter().enumerate() {
-            let workspace_name = workspace.name.clone().unwrap_or((idx + 1).to_string());
-            let sz = hdc.GetTextExtentPoint32(&workspace_name)?;
-
-            let h_padding = if focused_idx == idx { 5 } else { 10 };
-            let focused_rect = RECT {
-                left: left + h_padding,
-                right: left + sz.cx + TEXT_PADDING * 2 - h_padding,
-                top: rect.bottom - 20,
-                bottom: rect.bottom - 10,
+
+    /// The back and forward mouse buttons cycle the monitor the bar follows.
+    fn handle_xbuttondown(&mut self, wparam: usize) -> anyhow::Result<isize> {
+        const XBUTTON1: usize = 1;
+        let direction = if (wparam >> 16) & 0xffff == XBUTTON1 {
+            CycleDirection::Previous
+        } else {
+            CycleDirection::Next
+        };
+        log::info!("Cycling monitor focus: {direction:?}");
+        komorebi_client::send_query(&SocketMessage::CycleFocusMonitor(direction))?;
+        // the X button messages expect TRUE back when handled
+        Ok(1)
+    }
+
+    fn handle_mousemove(&mut self, p: msg::wm::MouseMove) -> anyhow::Result<isize> {
+        if self.handle_workspace_drag_move(p.coords)? {
+            return Ok(0);
+        }
+        if self.hovered_workspace.is_none() {
+            // ask for WM_MOUSELEAVE, which is only sent once per request
+            let mut track = TRACKMOUSEEVENT {
+                cbSize: size_of::<TRACKMOUSEEVENT>() as u32,
+                dwFlags: TME_LEAVE,
+                hwndTrack: WIN32_HWND(self.hwnd.ptr()),
+                dwHoverTime: 0,
             };
+            unsafe { TrackMouseEvent(&mut track)? };
+        }
 
-            if p.coords.x >= focused_rect.left && p.coords.x <= focused_rect.right {
-                log::info!("Switching to workspace {}: {}", idx, workspace_name);
-                komorebi_client::send_query(&SocketMessage::FocusWorkspaceNumber(idx))?;
-                break;
-            }
+        let hovered = self.workspace_at(p.coords)?.map(|button| button.idx);
+        if hovered != self.hovered_workspace {
+            self.hovered_workspace = hovered;
+            self.update_workspace_tooltip()?;
+            self.render()?;
+        }
+        Ok(0)
+    }
 
-            left += sz.cx + TEXT_PADDING * 2;
+    fn handle_mouseleave(&mut self) -> anyhow::Result<isize> {
+        if self.hovered_workspace.take().is_some() {
+            self.update_workspace_tooltip()?;
+            self.render()?;
         }
         Ok(0)
     }
 
+    fn set_display_mode(&mut self, mode: DisplayMode) -> anyhow::Result<isize> {
+        log::info!("Switching display mode to {:?}", mode);
+        self.config_file.set("display_mode", mode)?;
+        self.apply_config()
+    }
+
+    fn set_profile(&mut self, name: Option<&str>) -> anyhow::Result<isize> {
+        log::info!("Switching to profile {:?}", name);
+        self.config_file.set_active_profile(name);
+        self.apply_config()
+    }
+
+    /// Saves the config file and applies the resulting options.
+    fn apply_config(&mut self) -> anyhow::Result<isize> {
+        if let Err(err) = self.config_file.save() {
+            log::error!("Failed to save config: {err}");
+        }
+        self.use_config_file()
+    }
+
+    /// Picks up changes made to the config file outside the bar.
+    fn reload_config_file(&mut self) -> anyhow::Result<isize> {
+        self.config_file = ConfigFile::load();
+        self.use_config_file()
+    }
+
+    fn use_config_file(&mut self) -> anyhow::Result<isize> {
+        self.unregister_hotkeys();
+        self.unregister_drag_tracking();
+        self.stop_hover_switch();
+        self.config = self.config_file.effective();
+        self.report_invalid_options();
+        self.register_hotkeys();
+        self.register_drag_tracking();
+        self.start_hover_switch();
+        self.apply_capture_exclusion();
+        self.reload_settings()
+    }
+
     fn handle_setting_changed(&mut self) -> anyhow::Result<isize> {
-        log::info!("Handling WM_SETTINGCHANGE message");
-        self.settings = Settings::new()?;
-        self.hwnd.SetLayeredWindowAttributes(
-            self.settings.colors.get_color_key(),
-            0,
-            co::LWA::COLORKEY,
-        )?;
-        self.resize_to_fit()?;
-        self.hwnd.InvalidateRect(None, true)?;
-        Ok(0)
+        log::info!("Handling a change of the system settings");
+        let previous_colors = self.settings.colors.clone();
+        self.settings = Settings::new(&self.config, self.dpi)?;
+        self.start_theme_transition(previous_colors);
+        // also sent when the taskbar changes size and thereby the work area
+        self.sync_taskbar_height()?;
+        self.refresh()
+    }
+
+    /// Monitors were plugged in, unplugged or rearranged: fits the bar to its
+    /// taskbar again, which may also have moved to a monitor with another DPI.
+    fn handle_display_change(&mut self) -> anyhow::Result<isize> {
+        log::info!("Handling WM_DISPLAYCHANGE message");
+        self.handle_dpi_changed()?;
+        self.sync_taskbar_height()?;
+        self.refresh()
+    }
+
+    fn reload_settings(&mut self) -> anyhow::Result<isize> {
+        self.theme_transition = None;
+        self.settings = Settings::new(&self.config, self.dpi)?;
+        self.refresh()
     }
 
     fn paint_and_get_width(&self, hdc: &HDC, paint: bool) -> anyhow::Result<i32> {
         let _old_font = hdc.SelectObject(&self.settings.font)?;
 
         let rect = if paint {
-            self.hwnd.GetClientRect()?
+            self.bar_rect()?
         } else {
             RECT::default()
         };
 
-        let _old_pen = hdc.SelectObject(&self.settings.transparent_pen)?;
+        let null_pen = HPEN::GetStockObject(co::STOCK_PEN::NULL)?;
+        let _old_pen = hdc.SelectObject(&null_pen)?;
 
         if paint {
-            hdc.FillRect(rect, &self.settings.transparent_brush)?;
             hdc.SetTextColor(self.settings.colors.foreground)?;
             hdc.SetBkMode(co::BKMODE::TRANSPARENT)?;
+            if let Some(background) = self.settings.colors.background {
+                hdc.FillRect(rect, &*HBRUSH::CreateSolidBrush(background)?)?;
+            }
+            if self.paused {
+                self.paint_paused_stripes(hdc, rect)?;
+            }
         }
 
-        const BORDER_RADIUS: SIZE = SIZE { cx: 10, cy: 10 };
-
-        let mut left = 0;
-
-        let focused_idx = self.workspaces.focused_idx();
-        for (idx, workspace) in self.workspaces.elements().iter().enumerate() {
-            let workspace_name = workspace.name.clone().unwrap_or((idx + 1).to_string());
-            let sz = hdc.GetTextExtentPoint32(&workspace_name)?;
-
-            if paint {
-                let text_rect = RECT {
-                    left,
-                    right: left + sz.cx + TEXT_PADDING * 2,
-                    top: 0,
-                    bottom: rect.bottom - 10,
-                };
-                hdc.DrawText(
-                    &workspace_name,
-                    text_rect,
-                    co::DT::CENTER | co::DT::VCENTER | co::DT::SINGLELINE,
-                )?;
-
-                let h_padding = if focused_idx == idx { 5 } else { 10 };
-
-                let focused_rect = RECT {
-                    left: left + h_padding,
-                    right: left + sz.cx + TEXT_PADDING * 2 - h_padding,
-                    top: rect.bottom - 20,
-                    bottom: rect.bottom - 10,
-                };
+        let buttons = self.layout_workspaces(hdc)?;
+        let mut left = buttons.last().map_or(0, |button| button.right());
+        if paint {
+            self.pager_span.set(None);
+            self.paused_chip_span.set(None);
+            self.layer_chip_span.set(None);
+            self.layout_chip_span.set(None);
+            self.stack_chip_span.set(None);
+            self.elevated_glyph_span.set(None);
+        }
 
-                let focused_brush = HBRUSH::CreateSolidBrush(if focused_idx == idx {
-                    self.settings.colors.focused
-                } else if workspace.is_empty() {
-                    self.settings.colors.empty
-                } else {
-                    self.settings.colors.nonempty
-                })?;
-                let _old_brush = hdc.SelectObject(&*focused_brush);
-                hdc.RoundRect(focused_rect, BORDER_RADIUS)?;
+        if paint {
+            for button in &buttons {
+                self.paint_workspace_button(hdc, rect, button)?;
             }
-
-            left += sz.cx + TEXT_PADDING * 2;
+            self.paint_focus_animation(hdc, rect, &buttons)?;
+            self.paint_workspace_drag(hdc, rect, &buttons)?;
         }
 
-        if let Some(cw) = self.workspaces.focused() {
-            let mut current_state = String::new();
+        let state_area_visible = self.visibility == Visibility::Shown;
+        if state_area_visible {
+            left += self.paint_offline_chip(hdc, rect, left, paint)?;
+            left += self.paint_elevated_glyph(hdc, rect, left, paint)?;
+            left += self.paint_incompatible_chip(hdc, rect, left, paint)?;
+            left += self.paint_paused_chip(hdc, rect, left, paint)?;
+            left += self.paint_layer_chip(hdc, rect, left, paint)?;
+            left += self.paint_layout_chip(hdc, rect, left, paint)?;
+            left += self.paint_hidden_window_counts(hdc, rect, left, paint)?;
+            left += self.paint_stack_chip(hdc, rect, left, paint)?;
+        }
+        if let Some(cw) = self.workspaces.focused().filter(|_| state_area_visible) {
+            let strings = self.strings();
+            let mut current_state = None;
 
             if let Some(hwnd) = komorebi_client::WindowsApi::foreground_window().ok() {
                 if let Some(window) = cw.maximized_window() {
                     if hwnd == window.hwnd {
-                        current_state = "Maximized".to_string();
+                        current_state = Some((strings.maximized, self.settings.colors.maximized));
                     }
                 }
                 if let Some(container) = cw.monocle_container() {
                     if container.contains_window(hwnd) {
-                        current_state = "Monocle".to_string();
+                        current_state = Some((strings.monocle, self.settings.colors.monocle));
                     }
                 }
             }
 
-            if current_state.is_empty() {
-                if matches!(cw.layout, Layout::Default(DefaultLayout::Scrolling)) {
-                    let focused_idx = cw.containers.focused_idx();
-                    let total_containers = cw.containers().len();
-
-                    if total_containers > 1 {
-                        let draw_small_box = |text: &String,
-                                              padding: i32,
-                                              bg_color: COLORREF,
-                                              lb: &mut i32,
-                                              v_padding: i32|
-                         -> anyhow::Result<()> {
-                            const TEXT_WIDTH: i32 = 20;
-                            if paint {
-                                let text_rect = RECT {
-                                    left: *lb,
-                                    right: *lb + TEXT_WIDTH + padding * 2,
-                                    top: rect.top + v_padding,
-                                    bottom: rect.bottom - v_padding,
-                                };
-
-                                let focused_brush = HBRUSH::CreateSolidBrush(bg_color)?;
-                                let _old_brush = hdc.SelectObject(&*focused_brush);
-                                hdc.RoundRect(text_rect, BORDER_RADIUS)?;
-                                if !text.is_empty() {
-                                    hdc.DrawText(
-                                        text,
-                                        text_rect,
-                                        co::DT::CENTER | co::DT::VCENTER | co::DT::SINGLELINE,
-                                    )?;
-                                }
-                            }
-
-                            *lb += TEXT_WIDTH + padding * 2;
-
-                            Ok(())
+            match current_state {
+                None => {
+                    if self.focused_layout_scrolls() || self.config.pager_all_layouts {
+                        let pager_left = left;
+                        left = self.paint_pager(hdc, rect, cw, left, paint)?;
+                        if paint && left > pager_left {
+                            self.pager_span.set(Some((pager_left, left)));
+                        }
+                    }
+                }
+                Some((current_state, color)) => {
+                    let sz = hdc.GetTextExtentPoint32(current_state)?;
+                    if paint {
+                        let text_rect = RECT {
+                            left: left,
+                            right: left + sz.cx + self.padding() * 2,
+                            top: rect.top + self.px(12),
+                            bottom: rect.bottom - self.px(12),
                         };
 
-                        left += TEXT_PADDING;
-
-                        if total_containers >= 3 {
-                            draw_small_box(
-                                &(if focused_idx > 1 {
-                                    "•".to_string()
-                                } else {
-                                    "".to_string()
-                                }),
-                                0,
-                                self.settings.colors.get_color_key(),
-                                &mut left,
-                                20,
-                            )?;
-                        }
-                        if total_containers > 2 || (total_containers == 2 && focused_idx == 1) {
-                            draw_small_box(
-                                &(if focused_idx > 0 {
-                                    (focused_idx).to_string()
-                                } else {
-                                    "".to_string()
-                                }),
-                                12,
-                                if focused_idx > 0 {
-                                    self.settings.colors.empty
-                                } else {
-                                    self.settings.colors.get_color_key()
-                                },
-                                &mut left,
-                                16,
-                            )?;
-                        }
-                        draw_small_box(
-                            &(focused_idx + 1).to_string(),
-                            16,
-                            self.settings.colors.monocle,
-                            &mut left,
-                            14,
+                        let focused_brush = HBRUSH::CreateSolidBrush(color)?;
+                        let _old_brush = hdc.SelectObject(&*focused_brush);
+                        hdc.RoundRect(text_rect, self.border_radius())?;
+                        hdc.DrawText(
+                            current_state,
+                            text_rect,
+                            co::DT::CENTER | co::DT::VCENTER | co::DT::SINGLELINE,
                         )?;
-                        if total_containers >= 2 {
-                            draw_small_box(
-                                &(if focused_idx + 1 < total_containers {
-                                    (focused_idx + 2).to_string()
-                                } else {
-                                    "".to_string()
-                                }),
-                                12,
-                                if focused_idx + 1 < total_containers {
-                                    self.settings.colors.empty
-                                } else {
-                                    self.settings.colors.get_color_key()
-                                },
-                                &mut left,
-                                16,
-                            )?;
-                        }
-                        if total_containers >= 3 {
-                            draw_small_box(
-                                &(if focused_idx + 2 < total_containers {
-                                    "•".to_string()
-                                } else {
-                                    "".to_string()
-                                }),
-                                0,
-                                self.settings.colors.get_color_key(),
-                                &mut left,
-                                20,
-                            )?;
-                        }
                     }
+
+                    left += sz.cx + self.padding() * 2;
                 }
-            } else {
-                let sz = hdc.GetTextExtentPoint32(&current_state)?;
-                if paint {
-                    let text_rect = RECT {
-                        left: left,
-                        right: left + sz.cx + TEXT_PADDING * 2,
-                        top: rect.top + 12,
-                        bottom: rect.bottom - 12,
-                    };
-
-                    let focused_brush =
-                        HBRUSH::CreateSolidBrush(if current_state == "Maximized" {
-                            self.settings.colors.maximized
-                        } else {
-                            self.settings.colors.monocle
-                        })?;
-                    let _old_brush = hdc.SelectObject(&*focused_brush);
-                    hdc.RoundRect(text_rect, BORDER_RADIUS)?;
-                    hdc.DrawText(
-                        &current_state,
-                        text_rect,
-                        co::DT::CENTER | co::DT::VCENTER | co::DT::SINGLELINE,
-                    )?;
-                }
+            }
+        }
+
+        if state_area_visible {
+            left += self.paint_window_title(hdc, rect, left, paint)?;
+        }
 
-                left += sz.cx + TEXT_PADDING * 2;
+        if paint {
+            self.error_badge_left.set(None);
+        }
+        if crate::errors::has_recent() {
+            let width = hdc.GetTextExtentPoint32(ERROR_BADGE)?.cx + self.padding();
+            if paint {
+                hdc.SetTextColor(self.settings.colors.warning)?;
+                hdc.DrawText(
+                    ERROR_BADGE,
+                    RECT {
+                        left,
+                        right: left + width,
+                        top: 0,
+                        bottom: rect.bottom,
+                    },
+                    co::DT::CENTER | co::DT::VCENTER | co::DT::SINGLELINE,
+                )?;
+                hdc.SetTextColor(self.settings.colors.foreground)?;
+                self.error_badge_left.set(Some(left));
             }
+            left += width;
         }
 
         Ok(left)
@@ -466,44 +1035,321 @@ impl Window {
 
     fn resize_to_fit(&self) -> anyhow::Result<bool> {
         let total_width = self.get_window_width()?;
+        self.measure_taskbar_content();
 
-        let rect = self.hwnd.GetClientRect()?;
+        let rect = self.bar_rect()?;
+        let width = rect.right - rect.left;
 
-        if rect.right - rect.left == total_width {
+        if width == total_width {
+            self.width_animation.set(None);
+            // the clock or the taskbar may have moved the anchor regardless
+            self.set_window_width(total_width)?;
             return Ok(false);
         }
 
+        if !self.start_width_animation(width, total_width) {
+            self.set_window_width(total_width)?;
+        }
+        Ok(true)
+    }
+
+    /// Resizes the bar to `total_width`, moving it to where that width puts it
+    /// within the taskbar or on the screen.
+    fn set_window_width(&self, total_width: i32) -> anyhow::Result<()> {
+        let rect = self.bar_rect()?;
+
+        let size = self.bar_size(total_width, rect.bottom - rect.top);
+        let position = if self.embedded {
+            self.bar_position(total_width)?
+        } else {
+            self.standalone_origin(size)?
+        };
         self.hwnd.SetWindowPos(
             winsafe::HwndPlace::Place(co::HWND_PLACE::default()),
-            POINT::default(),
-            SIZE {
-                cx: total_width,
-                cy: rect.bottom - rect.top,
-            },
-            co::SWP::NOACTIVATE | co::SWP::NOZORDER | co::SWP::NOMOVE | co::SWP::NOREDRAW,
+            position,
+            size,
+            co::SWP::NOACTIVATE | co::SWP::NOZORDER | co::SWP::NOREDRAW,
         )?;
 
-        Ok(true)
+        Ok(())
     }
+
+    /// Takes everything queued for the bar, in the order it was sent. A
+    /// failed update doesn't hold back the ones after it.
+    fn handle_bar_updates(&mut self) -> anyhow::Result<isize> {
+        let mut result = Ok(0);
+        // a wake-up finding nothing left was beaten to it by an earlier one
+        while let Ok(update) = self.updates.try_recv() {
+            let handled = match update {
+                BarUpdate::Workspaces {
+                    workspaces,
+                    groups,
+                    paused,
+                    incompatible,
+                } => {
+                    self.incompatible_state = incompatible;
+                    self.handle_update_workspaces(workspaces, groups, paused)
+                }
+                BarUpdate::KomorebiVersion(mismatch) => self.handle_komorebi_version(mismatch),
+                BarUpdate::TaskbarContent(content) => self.handle_taskbar_content(content),
+            };
+            if let Err(err) = handled {
+                result = Err(err);
+            }
+        }
+        result
+    }
+
     pub fn handle_update_workspaces(
         &mut self,
         workspaces: Ring<Workspace>,
+        monitor_groups: Vec<MonitorGroup>,
+        paused: bool,
     ) -> anyhow::Result<isize> {
+        let previous_focus = self.workspaces.focused_idx();
+        let renamed = crate::komo::renamed_workspace(&self.workspaces, &workspaces)
+            .filter(|_| paused == self.paused && !self.quiet && !self.unseen());
         self.workspaces = workspaces;
+        self.monitor_groups = monitor_groups;
+        if self.workspaces.focused_idx() != previous_focus {
+            self.previous_workspace = Some(previous_focus);
+        }
+        self.paused = paused;
+        if let Some(idx) = renamed {
+            return self.refresh_renamed(idx);
+        }
+        self.prune_attention();
+        self.icon_cache.borrow_mut().clear();
+        if self.config.window_title {
+            self.focused_title = crate::komo::focused_window_title(&self.workspaces)
+                .unwrap_or_default();
+        }
+        if self.unseen() {
+            // painted once someone can see it again
+            return Ok(0);
+        }
+        self.start_focus_animation(previous_focus);
+        if self.quiet || self.saving_power() {
+            if !self.pending_refresh {
+                self.pending_refresh = true;
+                let interval = if self.quiet {
+                    QUIET_REFRESH_INTERVAL_MS
+                } else {
+                    power::SAVER_REFRESH_INTERVAL_MS
+                };
+                self.hwnd.SetTimer(QUIET_REFRESH_TIMER, interval, None)?;
+            }
+            return Ok(0);
+        }
+        self.refresh()
+    }
+
+    fn handle_komorebi_online(&mut self, online: bool) -> anyhow::Result<isize> {
+        let was_online = std::mem::replace(&mut self.ever_online, self.ever_online || online);
+        if self.offline != online {
+            return Ok(0);
+        }
+        log::info!("komorebi {}", if online { "back online" } else { "offline" });
+        self.offline = !online;
+        self.notify_connection(online, was_online);
+        self.refresh()
+    }
+
+    /// Tells about komorebi going away or coming back, which the bar alone
+    /// only shows by dimming. komorebi coming up after komoswitch, as it does
+    /// when both start with Windows, is left to the bar.
+    fn notify_connection(&mut self, online: bool, was_online: bool) {
+        if !self.config.connection_notifications || !self.is_primary() || !was_online {
+            return;
+        }
+        let strings = self.strings();
+        if online {
+            self.show_balloon(strings.reconnected, strings.connected_detail, false);
+        } else {
+            self.show_balloon(strings.disconnected, strings.disconnected_detail, true);
+        }
+    }
+
+    fn handle_komorebi_version(&mut self, mismatch: Option<String>) -> anyhow::Result<isize> {
+        if let Some(mismatch) = &mismatch {
+            log::warn!("{mismatch}");
+        }
+        self.version_mismatch = mismatch;
+        self.update_tray_tooltip();
+        Ok(0)
+    }
+
+    /// Re-reads the full komorebi state instead of waiting for the next event
+    fn resync(&mut self) -> anyhow::Result<isize> {
+        let state = crate::komo::read_state_workspaces()?;
+        let (workspaces, groups) = state.in_scope(self.scope)?;
+        self.incompatible_state = !state.compatible;
+        self.handle_update_workspaces(workspaces, groups, state.paused)
+    }
+
+    /// Whether the focused workspace has the scrolling layout.
+    fn focused_layout_scrolls(&self) -> bool {
+        self.workspaces.focused().is_some_and(|workspace| {
+            matches!(workspace.layout, Layout::Default(DefaultLayout::Scrolling))
+        })
+    }
+
+    pub fn scope(&self) -> BarScope {
+        self.scope
+    }
+
+    /// Index of the monitor the bar is pinned to, see `BarMonitor`
+    fn monitor_idx(&self) -> Option<usize> {
+        self.monitor.as_ref().map(|monitor| monitor.idx)
+    }
+
+    /// Monitor and workspace index komorebi knows button `idx` by. The monitor
+    /// is only `None` for workspaces seeded from the static config.
+    fn workspace_target(&self, idx: usize) -> (Option<usize>, usize) {
+        // past the last group is the "+" button, creating on the last monitor
+        let group = self
+            .monitor_groups
+            .iter()
+            .find(|group| group.contains(idx))
+            .or(self.monitor_groups.last());
+        match group {
+            Some(group) => (Some(group.monitor_idx), idx - group.start),
+            None => (self.monitor_idx(), idx),
+        }
+    }
+
+    /// Whether this is the first bar, which owns the tray icon and the hotkeys
+    /// shared by all bars.
+    fn is_primary(&self) -> bool {
+        self.monitor_idx().is_none_or(|idx| idx == 0)
+    }
+
+    /// Focuses workspace `idx` on the monitor it is shown for, which may no
+    /// longer be the focused one by the time the bar is clicked.
+    fn focus_workspace_message(&self, idx: usize) -> SocketMessage {
+        match self.workspace_target(idx) {
+            (Some(monitor_idx), idx) => {
+                SocketMessage::FocusMonitorWorkspaceNumber(monitor_idx, idx)
+            }
+            (None, idx) => SocketMessage::FocusWorkspaceNumber(idx),
+        }
+    }
+
+    /// Sends the focused window to workspace `idx`, with `follow` moving the
+    /// focus along with it.
+    fn send_window_message(&self, idx: usize, follow: bool) -> SocketMessage {
+        match (self.workspace_target(idx), follow) {
+            ((Some(monitor_idx), idx), false) => {
+                SocketMessage::SendContainerToMonitorWorkspaceNumber(monitor_idx, idx)
+            }
+            ((Some(monitor_idx), idx), true) => {
+                SocketMessage::MoveContainerToMonitorWorkspaceNumber(monitor_idx, idx)
+            }
+            ((None, idx), false) => SocketMessage::SendContainerToWorkspaceNumber(idx),
+            ((None, idx), true) => SocketMessage::MoveContainerToWorkspaceNumber(idx),
+        }
+    }
+
+    fn refresh(&self) -> anyhow::Result<isize> {
         self.resize_to_fit()?;
-        self.hwnd.InvalidateRect(None, true)?;
+        self.render()?;
+        Ok(0)
+    }
+
+    /// Refreshes the bar after workspace `idx` was renamed, redrawing only its
+    /// button unless the new name changes the width of the bar.
+    fn refresh_renamed(&self, idx: usize) -> anyhow::Result<isize> {
+        log::debug!("Workspace {idx} renamed");
+        if self.resize_to_fit()? {
+            self.render()?;
+        } else {
+            self.render_dirty(Some(idx))?;
+        }
+        Ok(0)
+    }
+
+    fn flush_pending_refresh(&mut self) -> anyhow::Result<isize> {
+        if !self.pending_refresh {
+            return Ok(0);
+        }
+        self.hwnd.KillTimer(QUIET_REFRESH_TIMER)?;
+        self.pending_refresh = false;
+        self.refresh()
+    }
+
+    fn handle_timer(&mut self, p: msg::wm::Timer) -> anyhow::Result<isize> {
+        match p.timer_id {
+            QUIET_REFRESH_TIMER => self.flush_pending_refresh(),
+            ERROR_BADGE_TIMER => Ok(self.handle_errors_changed()),
+            click::CLICK_TIMER => self.handle_click_timer(),
+            dwell::HOVER_SWITCH_TIMER => self.handle_hover_switch_timer(),
+            taskbar::ALIGNMENT_TIMER => self.handle_alignment_settled(),
+            taskbar::EMBED_RETRY_TIMER => self.handle_embed_retry(),
+            animation::FOCUS_ANIMATION_TIMER => self.handle_focus_animation_timer(),
+            animation::WIDTH_ANIMATION_TIMER => self.handle_width_animation_timer(),
+            animation::THEME_TRANSITION_TIMER => self.handle_theme_transition_timer(),
+            _ => Ok(0),
+        }
+    }
+
+    /// Shows or hides the error badge. Never fails, so that errors raised while
+    /// repainting can't keep re-triggering it.
+    fn handle_errors_changed(&mut self) -> isize {
+        let visible = crate::errors::has_recent();
+        if visible == self.error_badge_visible {
+            return 0;
+        }
+        self.error_badge_visible = visible;
+
+        let timer = if visible {
+            self.hwnd
+                .SetTimer(ERROR_BADGE_TIMER, ERROR_BADGE_INTERVAL_MS, None)
+                .map(|_| ())
+        } else {
+            self.hwnd.KillTimer(ERROR_BADGE_TIMER)
+        };
+        if let Err(err) = timer {
+            log::error!("Failed to update error badge timer: {err}");
+        }
+
+        if let Err(err) = self.refresh() {
+            log::error!("Failed to repaint error badge: {err}");
+        }
+        0
+    }
+
+    fn handle_presentation_mode(&mut self, active: bool) -> anyhow::Result<isize> {
+        log::info!("Quiet mode {}", if active { "enabled" } else { "disabled" });
+        self.quiet = active;
+        if !active {
+            return self.flush_pending_refresh();
+        }
         Ok(0)
     }
 
     fn handle_create(&self) -> anyhow::Result<isize> {
         log::info!("Handling WM_CREATE message");
+        self.report_invalid_options();
         Ok(0)
     }
 
+    /// Shows the options the config file got wrong, and which were left at
+    /// their default, in the error badge.
+    fn report_invalid_options(&self) {
+        // the other bars read the same file
+        if !self.is_primary() {
+            return;
+        }
+        for problem in self.config_file.effective_checked().1 {
+            crate::errors::report(&self.hwnd, format!("Invalid config: {problem}"));
+        }
+    }
+
     fn handle_paint(&self) -> anyhow::Result<isize> {
         log::info!("Handling WM_PAINT message...");
-        let hdc = self.hwnd.BeginPaint()?;
-        self.paint_and_get_width(&*hdc, true)?;
+        // the contents are set through `UpdateLayeredWindow`, only validate
+        let _hdc = self.hwnd.BeginPaint()?;
+        self.render()?;
         log::info!("WM_PAINT handled.");
         Ok(0)
     }
@@ -512,51 +1358,124 @@ impl Window {
         self.hwnd = HWND::NULL;
     }
 
-    pub fn run_loop(&self) -> anyhow::Result<()> {
-        let mut msg = MSG::default();
-        while GetMessage(&mut msg, None, 0, 0)? {
-            TranslateMessage(&msg);
-            unsafe {
-                DispatchMessage(&msg);
-            }
-        }
-        Ok(())
-    }
-
     pub fn prepare(&mut self) -> anyhow::Result<()> {
-        // Ensure the process is DPI aware for high DPI displays
-        if IsWindowsVistaOrGreater()? {
-            SetProcessDPIAware()?;
-        }
+        dpi::enable_dpi_awareness()?;
 
         let hinstance = HINSTANCE::GetModuleHandle(None)?;
 
-        let atom = self.register_class(&hinstance, "komoswitch")?;
+        let atom = self.register_class(&hinstance, CLASS_NAME)?;
 
-        let taskbar_atom = AtomStr::from_str("Shell_TrayWnd");
-        let taskbar = HWND::FindWindow(Some(taskbar_atom), None)?
-            .ok_or(anyhow::anyhow!("Taskbar not found"))?;
+        let taskbar = self.find_taskbar().ok();
+        self.dpi = dpi::dpi_for(taskbar.as_ref());
+        self.settings = Settings::new(&self.config, self.dpi)?;
 
-        let rect = taskbar.GetClientRect()?;
+        let height = match &taskbar {
+            Some(taskbar) => self.fit_to_taskbar(taskbar)?,
+            None => self.px(taskbar::STANDALONE_HEIGHT),
+        };
 
+        let width = self.get_window_width()?;
         self.create_window(
             atom,
-            POINT { x: 15, y: 0 },
-            SIZE {
-                cx: self.get_window_width()?,
-                cy: rect.bottom - rect.top,
-            },
+            self.bar_position(width).unwrap_or_default(),
+            self.bar_size(width, height),
             &hinstance,
         )?;
 
-        self.hwnd.SetParent(&taskbar)?;
+        self.embed_or_standalone()?;
+        self.handle_dpi_changed()?;
 
-        self.hwnd.SetLayeredWindowAttributes(
-            self.settings.colors.get_color_key(),
-            0,
-            co::LWA::COLORKEY,
-        )?;
+        self.show_tray_icon_if_wanted();
+        self.register_listeners();
+
+        self.set_visibility(match self.config.startup {
+            StartupVisibility::Visible => Visibility::Shown,
+            StartupVisibility::Hidden => Visibility::Hidden,
+            StartupVisibility::Minimized => Visibility::Collapsed,
+        })?;
 
         Ok(())
     }
+
+    fn show_tray_icon_if_wanted(&mut self) {
+        let wants_tray_icon =
+            self.config.tray_icon || self.config.startup == StartupVisibility::Hidden;
+        if wants_tray_icon && self.is_primary() {
+            if let Err(err) = self.add_tray_icon() {
+                crate::errors::report(&self.hwnd, format!("{err}"));
+            }
+        }
+    }
+
+    /// Hotkeys, hooks and notifications tied to the bar's window.
+    fn register_listeners(&mut self) {
+        self.register_hotkeys();
+        self.register_drag_tracking();
+        self.register_foreground_tracking();
+        self.register_resume_notification();
+        self.register_color_notification();
+        self.register_session_notifications();
+        self.register_power_source_notifications();
+        self.register_shell_hook();
+        self.start_hover_switch();
+    }
+
+    fn unregister_listeners(&mut self) {
+        self.unregister_shell_hook();
+        self.unregister_hotkeys();
+        self.unregister_drag_tracking();
+        self.unregister_foreground_tracking();
+        self.unregister_resume_notification();
+        self.unregister_color_notification();
+        self.unregister_session_notifications();
+        self.unregister_power_source_notifications();
+        self.stop_hover_switch();
+    }
+
+    /// Handle of the bar for the threads posting to it.
+    pub fn handle(&self) -> BarHandle {
+        self.handle.clone()
+    }
+
+    /// Finds the bar of an already running instance, embedded or standalone.
+    pub fn find_running() -> anyhow::Result<Option<HWND>> {
+        if let Ok(taskbar) = taskbar::find_taskbar() {
+            if let Some(hwnd) =
+                taskbar.FindWindowEx(None, AtomStr::from_str(CLASS_NAME), None)?
+            {
+                return Ok(Some(hwnd));
+            }
+        }
+
+        Ok(HWND::FindWindow(Some(AtomStr::from_str(CLASS_NAME)), None)?)
+    }
+
+    fn set_visibility(&mut self, visibility: Visibility) -> anyhow::Result<isize> {
+        log::info!("Bar visibility: {:?}", visibility);
+        self.visibility = visibility;
+        self.hwnd.ShowWindow(match visibility {
+            Visibility::Hidden => co::SW::HIDE,
+            _ => co::SW::SHOWNOACTIVATE,
+        });
+        self.refresh()
+    }
+
+    /// komoswitch was launched again while running: picks up any change to the
+    /// config and brings the bar back into view.
+    fn handle_show_instance(&mut self) -> anyhow::Result<isize> {
+        log::info!("Launched again, reloading the config and showing the bar");
+        self.reload_config_file()?;
+        self.set_visibility(Visibility::Shown)
+    }
+
+    /// Shows the bar, or hides it the way it was hidden at startup.
+    fn toggle_visibility(&mut self) -> anyhow::Result<isize> {
+        self.set_visibility(match self.visibility {
+            Visibility::Shown => match self.config.startup {
+                StartupVisibility::Minimized => Visibility::Collapsed,
+                _ => Visibility::Hidden,
+            },
+            _ => Visibility::Shown,
+        })
+    }
 }
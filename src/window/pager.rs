@@ -0,0 +1,164 @@
+use komorebi_client::Workspace;
+use winsafe::{prelude::*, *};
+
+use crate::config::PagerStyle;
+
+use super::Window;
+
+// Dot metrics at 96 DPI
+const DOT_SIZE: i32 = 6;
+const FOCUSED_DOT_SIZE: i32 = 10;
+const DOT_GAP: i32 = 6;
+
+impl Window {
+    /// Draws the container pager of workspace `cw` starting at `left`, and
+    /// returns the left edge of whatever follows it.
+    pub(super) fn paint_pager(
+        &self,
+        hdc: &HDC,
+        rect: RECT,
+        cw: &Workspace,
+        mut left: i32,
+        paint: bool,
+    ) -> anyhow::Result<i32> {
+        let focused_idx = cw.containers.focused_idx();
+        let total_containers = cw.containers().len();
+
+        if total_containers <= 1 {
+            return Ok(left);
+        }
+
+        left += self.padding();
+
+        if self.config.pager_style == PagerStyle::Dots {
+            return self.paint_pager_dots(hdc, rect, focused_idx, total_containers, left, paint);
+        }
+
+        let draw_small_box = |text: &String,
+                              padding: i32,
+                              bg_color: Option<COLORREF>,
+                              lb: &mut i32,
+                              v_padding: i32|
+         -> anyhow::Result<()> {
+            let text_width = self.px(20);
+            let padding = self.px(padding);
+            if paint {
+                let v_padding = self.px(v_padding);
+                let text_rect = RECT {
+                    left: *lb,
+                    right: *lb + text_width + padding * 2,
+                    top: rect.top + v_padding,
+                    bottom: rect.bottom - v_padding,
+                };
+
+                if let Some(bg_color) = bg_color {
+                    let focused_brush = HBRUSH::CreateSolidBrush(bg_color)?;
+                    let _old_brush = hdc.SelectObject(&*focused_brush);
+                    hdc.RoundRect(text_rect, self.border_radius())?;
+                }
+                if !text.is_empty() {
+                    self.draw_text(hdc, &self.settings.font, text, text_rect, true)?;
+                }
+            }
+
+            *lb += text_width + padding * 2;
+
+            Ok(())
+        };
+
+        // with more containers than slots every slot is drawn, empty or not,
+        // and dots mark containers out of view
+        // more neighbors than containers would only draw nothing, or overflow
+        let neighbors = self.config.pager_neighbors.min(total_containers);
+        let all_slots = total_containers > neighbors * 2;
+        let draw_neighbor = |idx: Option<usize>, left: &mut i32| -> anyhow::Result<()> {
+            if !all_slots && idx.is_none() {
+                return Ok(());
+            }
+            draw_small_box(
+                &idx.map(|idx| self.container_label(cw, idx)).unwrap_or_default(),
+                12,
+                Some(self.settings.colors.empty).filter(|_| idx.is_some()),
+                left,
+                16,
+            )
+        };
+        let overflow_marker = |hidden: bool| {
+            if hidden {
+                "•".to_string()
+            } else {
+                "".to_string()
+            }
+        };
+
+        if all_slots {
+            draw_small_box(
+                &overflow_marker(focused_idx > neighbors),
+                0,
+                None,
+                &mut left,
+                20,
+            )?;
+        }
+        for distance in (1..=neighbors).rev() {
+            draw_neighbor(focused_idx.checked_sub(distance), &mut left)?;
+        }
+        draw_small_box(
+            &self.container_label(cw, focused_idx),
+            16,
+            Some(self.settings.colors.monocle),
+            &mut left,
+            14,
+        )?;
+        for distance in 1..=neighbors {
+            let idx = Some(focused_idx + distance).filter(|idx| *idx < total_containers);
+            draw_neighbor(idx, &mut left)?;
+        }
+        if all_slots {
+            draw_small_box(
+                &overflow_marker(focused_idx + neighbors + 1 < total_containers),
+                0,
+                None,
+                &mut left,
+                20,
+            )?;
+        }
+
+        Ok(left)
+    }
+
+    /// One dot per container, the focused one larger and highlighted.
+    fn paint_pager_dots(
+        &self,
+        hdc: &HDC,
+        rect: RECT,
+        focused_idx: usize,
+        total_containers: usize,
+        mut left: i32,
+        paint: bool,
+    ) -> anyhow::Result<i32> {
+        let center = (rect.top + rect.bottom) / 2;
+        for idx in 0..total_containers {
+            let focused = idx == focused_idx;
+            let size = self.px(if focused { FOCUSED_DOT_SIZE } else { DOT_SIZE });
+            if paint {
+                let dot_rect = RECT {
+                    left,
+                    right: left + size,
+                    top: center - size / 2,
+                    bottom: center - size / 2 + size,
+                };
+                let brush = HBRUSH::CreateSolidBrush(if focused {
+                    self.settings.colors.monocle
+                } else {
+                    self.settings.colors.nonempty
+                })?;
+                let _old_brush = hdc.SelectObject(&*brush);
+                hdc.Ellipse(dot_rect)?;
+            }
+            left += size + self.px(DOT_GAP);
+        }
+
+        Ok(left - self.px(DOT_GAP) + self.padding())
+    }
+}
@@ -0,0 +1,764 @@
+use std::ffi::c_void;
+
+use komorebi_client::{Workspace, WorkspaceLayer};
+use windows::Win32::Graphics::Gdi::{
+    BeginPath, EndPath, GRADIENT_FILL_RECT_H, GRADIENT_RECT, GradientFill, HDC as WIN32_HDC,
+    RGN_COPY, SelectClipPath, SelectClipRgn, TRIVERTEX,
+};
+use winsafe::{prelude::*, *};
+
+use crate::config::{ContainerLabels, IndicatorStyle};
+
+use super::{
+    icons::{ICON_GAP, ICON_SIZE},
+    layout::{MONITOR_SEPARATOR_GAP, MonitorLabel, WorkspaceButton},
+    Window,
+};
+
+/// Windows on `workspace`, tiled, stacked, floating, maximized or in monocle.
+fn window_count(workspace: &Workspace) -> usize {
+    let tiled: usize = workspace
+        .containers()
+        .iter()
+        .map(|container| container.windows().len())
+        .sum();
+    let monocle = workspace
+        .monocle_container()
+        .as_ref()
+        .map_or(0, |container| container.windows().len());
+    let maximized = usize::from(workspace.maximized_window().is_some());
+
+    tiled + monocle + maximized + workspace.floating_windows().len()
+}
+
+impl Window {
+    /// Draws the label, app icons and state indicator of one workspace button.
+    /// `rect` is the client area of the bar.
+    pub(super) fn paint_workspace_button(
+        &self,
+        hdc: &HDC,
+        rect: RECT,
+        button: &WorkspaceButton,
+    ) -> anyhow::Result<()> {
+        if let Some(label) = &button.monitor_label {
+            self.paint_monitor_label(hdc, rect, label)?;
+        }
+        if button.new_workspace {
+            return self.paint_new_workspace_button(hdc, rect, button);
+        }
+
+        let workspace = &self.workspaces.elements()[button.idx];
+        // while animating, the focus indicator is drawn on its own
+        let focused =
+            self.workspaces.focused_idx() == button.idx && self.focus_animation.is_none();
+        let (style, color) = if focused {
+            (self.config.indicator_styles.focused, self.focused_color())
+        } else if workspace.is_empty() {
+            (self.config.indicator_styles.empty, self.settings.colors.empty)
+        } else {
+            (self.config.indicator_styles.nonempty, self.settings.colors.nonempty)
+        };
+        let indicator_rect = self.indicator_rect(rect, button, style, focused);
+
+        self.paint_hover(hdc, rect, button)?;
+
+        // a pill sits behind the label, the other styles are drawn over it
+        if style == IndicatorStyle::Pill && self.config.state_glyphs.is_none() {
+            self.paint_state_indicator(hdc, indicator_rect, color, focused)?;
+        }
+
+        if button.has_content() {
+            let content_bottom = rect.bottom - self.px(10);
+            if !button.label.is_empty() {
+                let text_rect = RECT {
+                    left: button.left + self.padding(),
+                    right: button.left + self.padding() + button.label_width,
+                    top: 0,
+                    bottom: content_bottom,
+                };
+                let font = self
+                    .settings
+                    .label_font(self.workspaces.focused_idx() == button.idx);
+                self.draw_text(hdc, font, &button.label, text_rect, true)?;
+            }
+
+            let icon_size = self.px(ICON_SIZE);
+            let icon_top = (content_bottom - icon_size) / 2;
+            let mut icon_left = button.icons_left;
+            for hwnd in &button.icons {
+                self.draw_window_icon(hdc, *hwnd, icon_left, icon_top, icon_size);
+                icon_left += icon_size + self.px(ICON_GAP);
+            }
+
+            if button.overflow > 0 {
+                let chip_rect = RECT {
+                    left: button.overflow_left,
+                    right: button.overflow_left + button.overflow_width,
+                    top: icon_top - self.px(2),
+                    bottom: icon_top + icon_size + self.px(2),
+                };
+                let brush = HBRUSH::CreateSolidBrush(self.settings.colors.empty)?;
+                let _old_brush = hdc.SelectObject(&*brush);
+                hdc.RoundRect(chip_rect, self.border_radius())?;
+                hdc.DrawText(
+                    &format!("+{}", button.overflow),
+                    chip_rect,
+                    co::DT::CENTER | co::DT::VCENTER | co::DT::SINGLELINE,
+                )?;
+            }
+        }
+
+        if self.config.window_count_badges {
+            self.paint_window_count_badge(hdc, button, workspace)?;
+        }
+        self.paint_corner_markers(hdc, button, workspace, focused)?;
+
+        if let Some(glyphs) = &self.config.state_glyphs {
+            let glyph = if focused {
+                &glyphs.focused
+            } else if workspace.is_empty() {
+                &glyphs.empty
+            } else {
+                &glyphs.nonempty
+            };
+            return self.paint_state_glyph(hdc, rect, button, glyph, focused);
+        }
+
+        if style != IndicatorStyle::Pill {
+            self.paint_state_indicator(hdc, indicator_rect, color, focused)?;
+        }
+        Ok(())
+    }
+
+    /// Color of the focused indicator, the warning color while tiling is paused.
+    pub(super) fn focused_color(&self) -> COLORREF {
+        if self.paused {
+            self.settings.colors.warning
+        } else {
+            self.settings.colors.focused
+        }
+    }
+
+    fn paint_hover(&self, hdc: &HDC, rect: RECT, button: &WorkspaceButton) -> anyhow::Result<()> {
+        let highlighted = self.hovered_workspace == Some(button.idx)
+            || self.keyboard_selection == Some(button.idx)
+            || self.workspace_drop_target() == Some(button.idx);
+        if !highlighted {
+            return Ok(());
+        }
+        let hover_rect = RECT {
+            left: button.left + self.px(2),
+            right: button.right() - self.px(2),
+            top: rect.top + self.px(4),
+            bottom: rect.bottom - self.px(4),
+        };
+        let brush = HBRUSH::CreateSolidBrush(self.settings.colors.hover)?;
+        let _old_brush = hdc.SelectObject(&*brush);
+        hdc.RoundRect(hover_rect, self.border_radius())?;
+        Ok(())
+    }
+
+    /// Draws a monitor's number before its workspaces, and the line setting
+    /// them apart from the previous monitor's.
+    fn paint_monitor_label(
+        &self,
+        hdc: &HDC,
+        rect: RECT,
+        label: &MonitorLabel,
+    ) -> anyhow::Result<()> {
+        if label.separator {
+            let gap = self.px(MONITOR_SEPARATOR_GAP);
+            let line_left = label.left - gap / 2;
+            let line_rect = RECT {
+                left: line_left,
+                right: line_left + self.px(1).max(1),
+                top: rect.top + self.px(12),
+                bottom: rect.bottom - self.px(12),
+            };
+            hdc.FillRect(line_rect, &*HBRUSH::CreateSolidBrush(self.settings.colors.empty)?)?;
+        }
+        let text_rect = RECT {
+            left: label.left,
+            right: label.left + label.width,
+            top: 0,
+            bottom: rect.bottom - self.px(10),
+        };
+        self.draw_text(hdc, &self.settings.font, &label.text, text_rect, true)?;
+        Ok(())
+    }
+
+    /// The "+" button only has a label, there is no workspace behind it yet.
+    fn paint_new_workspace_button(
+        &self,
+        hdc: &HDC,
+        rect: RECT,
+        button: &WorkspaceButton,
+    ) -> anyhow::Result<()> {
+        self.paint_hover(hdc, rect, button)?;
+        let text_rect = RECT {
+            left: button.left + self.padding(),
+            right: button.left + self.padding() + button.label_width,
+            top: 0,
+            bottom: rect.bottom - self.px(10),
+        };
+        self.draw_text(hdc, &self.settings.font, &button.label, text_rect, true)?;
+        Ok(())
+    }
+
+    /// Draws a state indicator in `color`, or in the focused gradient if
+    /// enabled and `focused`.
+    pub(super) fn paint_state_indicator(
+        &self,
+        hdc: &HDC,
+        indicator_rect: RECT,
+        color: COLORREF,
+        focused: bool,
+    ) -> anyhow::Result<()> {
+        if focused && self.config.focused_gradient && !self.paused {
+            self.paint_gradient_indicator(hdc, indicator_rect)
+        } else {
+            self.paint_indicator(hdc, indicator_rect, color)
+        }
+    }
+
+    fn paint_gradient_indicator(&self, hdc: &HDC, indicator_rect: RECT) -> anyhow::Result<()> {
+        let vertex = |x: i32, y: i32, color: COLORREF| TRIVERTEX {
+            x,
+            y,
+            Red: (color.GetRValue() as u16) << 8,
+            Green: (color.GetGValue() as u16) << 8,
+            Blue: (color.GetBValue() as u16) << 8,
+            Alpha: 0xff00,
+        };
+        let vertices = [
+            vertex(
+                indicator_rect.left,
+                indicator_rect.top,
+                self.settings.colors.focused,
+            ),
+            vertex(
+                indicator_rect.right,
+                indicator_rect.bottom,
+                self.settings.colors.focused_end,
+            ),
+        ];
+        let mesh = GRADIENT_RECT {
+            UpperLeft: 0,
+            LowerRight: 1,
+        };
+
+        // clip to the rounded shape through a path, which unlike a region is
+        // in logical coordinates and so follows a mirrored layout
+        let raw_hdc = WIN32_HDC(hdc.ptr());
+        unsafe {
+            let _ = BeginPath(raw_hdc);
+        }
+        let shaped = hdc.RoundRect(indicator_rect, self.border_radius());
+        let filled = unsafe {
+            let _ = EndPath(raw_hdc);
+            let clipped = SelectClipPath(raw_hdc, RGN_COPY).as_bool();
+            let filled = clipped
+                && GradientFill(
+                    raw_hdc,
+                    &vertices,
+                    &mesh as *const _ as *const c_void,
+                    1,
+                    GRADIENT_FILL_RECT_H,
+                )
+                .as_bool();
+            SelectClipRgn(raw_hdc, None);
+            filled
+        };
+        shaped?;
+        if !filled {
+            anyhow::bail!("Failed to fill the focus gradient");
+        }
+        Ok(())
+    }
+
+    pub(super) fn paint_indicator(
+        &self,
+        hdc: &HDC,
+        indicator_rect: RECT,
+        color: COLORREF,
+    ) -> anyhow::Result<()> {
+        let brush = HBRUSH::CreateSolidBrush(color)?;
+        let _old_brush = hdc.SelectObject(&*brush);
+        hdc.RoundRect(indicator_rect, self.border_radius())?;
+        Ok(())
+    }
+
+    /// Draws `glyph` where the state indicator of `button` would be: below the
+    /// label, or centered in buttons without one.
+    fn paint_state_glyph(
+        &self,
+        hdc: &HDC,
+        rect: RECT,
+        button: &WorkspaceButton,
+        glyph: &str,
+        focused: bool,
+    ) -> anyhow::Result<()> {
+        let (font, top, bottom) = if button.has_content() {
+            let indicator = self.indicator_rect(rect, button, IndicatorStyle::Underline, focused);
+            let center = (indicator.top + indicator.bottom) / 2;
+            (&self.settings.badge_font, center - self.px(10), center + self.px(10))
+        } else {
+            (&self.settings.font, rect.top, rect.bottom)
+        };
+        let glyph_rect = RECT {
+            left: button.left,
+            right: button.right(),
+            top,
+            bottom,
+        };
+
+        if focused {
+            hdc.SetTextColor(self.focused_color())?;
+        }
+        self.draw_text(hdc, font, glyph, glyph_rect, true)?;
+        hdc.SetTextColor(self.settings.colors.foreground)?;
+        Ok(())
+    }
+
+    /// Marks the top left corner of `button` with a row of dots: one when its
+    /// workspace is in monocle or has a maximized window, which the state chip
+    /// only shows once focused, one in the warning color when komorebi doesn't
+    /// tile it and one in the accent color while a window on it is flashing
+    /// for attention.
+    fn paint_corner_markers(
+        &self,
+        hdc: &HDC,
+        button: &WorkspaceButton,
+        workspace: &Workspace,
+        focused: bool,
+    ) -> anyhow::Result<()> {
+        let colors = &self.settings.colors;
+        let state = if focused {
+            None
+        } else if workspace.monocle_container().is_some() {
+            Some(colors.monocle)
+        } else if workspace.maximized_window().is_some() {
+            Some(colors.maximized)
+        } else {
+            None
+        };
+        let untiled = Some(colors.warning).filter(|_| !workspace.tile);
+        let attention =
+            Some(colors.focused).filter(|_| !focused && self.wants_attention(button.idx));
+
+        for (slot, color) in [state, untiled, attention].into_iter().flatten().enumerate() {
+            self.paint_corner_dot(hdc, button, slot as i32, color)?;
+        }
+        Ok(())
+    }
+
+    /// Draws the `slot`th dot from the top-left corner of a workspace button.
+    fn paint_corner_dot(
+        &self,
+        hdc: &HDC,
+        button: &WorkspaceButton,
+        slot: i32,
+        color: COLORREF,
+    ) -> anyhow::Result<()> {
+        let size = self.px(6);
+        let left = button.left + self.px(4) + slot * (size + self.px(3));
+        let marker_rect = RECT {
+            left,
+            right: left + size,
+            top: self.px(4),
+            bottom: self.px(4) + size,
+        };
+        let brush = HBRUSH::CreateSolidBrush(color)?;
+        let _old_brush = hdc.SelectObject(&*brush);
+        hdc.Ellipse(marker_rect)?;
+        Ok(())
+    }
+
+    /// Draws the number of windows on `workspace` in the top right corner of
+    /// its button.
+    fn paint_window_count_badge(
+        &self,
+        hdc: &HDC,
+        button: &WorkspaceButton,
+        workspace: &Workspace,
+    ) -> anyhow::Result<()> {
+        let count = window_count(workspace);
+        if count == 0 {
+            return Ok(());
+        }
+
+        let text = if count > 9 {
+            "9+".to_string()
+        } else {
+            count.to_string()
+        };
+        let _old_font = hdc.SelectObject(&self.settings.badge_font)?;
+        let text_size = hdc.GetTextExtentPoint32(&text)?;
+        let width = (text_size.cx + self.px(6)).max(text_size.cy);
+        let badge_rect = RECT {
+            left: button.right() - self.px(4) - width,
+            right: button.right() - self.px(4),
+            top: self.px(4),
+            bottom: self.px(4) + text_size.cy,
+        };
+
+        let brush = HBRUSH::CreateSolidBrush(self.settings.colors.nonempty)?;
+        let _old_brush = hdc.SelectObject(&*brush);
+        hdc.RoundRect(
+            badge_rect,
+            SIZE {
+                cx: text_size.cy,
+                cy: text_size.cy,
+            },
+        )?;
+        hdc.DrawText(
+            &text,
+            badge_rect,
+            co::DT::CENTER | co::DT::VCENTER | co::DT::SINGLELINE,
+        )?;
+        Ok(())
+    }
+
+    /// Covers the bar in stripes of the warning color while tiling is paused.
+    pub(super) fn paint_paused_stripes(&self, hdc: &HDC, rect: RECT) -> anyhow::Result<()> {
+        let brush = HBRUSH::CreateHatchBrush(co::HS::BDIAGONAL, self.settings.colors.warning)?;
+        hdc.FillRect(rect, &*brush)?;
+        Ok(())
+    }
+
+    /// Draws the offline chip starting at `left` while komorebi can't be
+    /// reached, and returns the width it takes.
+    pub(super) fn paint_offline_chip(
+        &self,
+        hdc: &HDC,
+        rect: RECT,
+        left: i32,
+        paint: bool,
+    ) -> anyhow::Result<i32> {
+        if !self.offline {
+            return Ok(0);
+        }
+        let color = self.settings.colors.warning;
+        self.paint_chip(hdc, rect, left, self.strings().offline, color, paint)
+    }
+
+    /// Draws a warning chip starting at `left` while komorebi's state can only
+    /// be read in part, and returns the width it takes. Workspaces may then be
+    /// missing their windows.
+    pub(super) fn paint_incompatible_chip(
+        &self,
+        hdc: &HDC,
+        rect: RECT,
+        left: i32,
+        paint: bool,
+    ) -> anyhow::Result<i32> {
+        if !self.incompatible_state {
+            return Ok(0);
+        }
+        let color = self.settings.colors.warning;
+        self.paint_chip(hdc, rect, left, self.strings().incompatible, color, paint)
+    }
+
+    /// Draws the "Paused" chip starting at `left` while tiling is paused, and
+    /// returns the width it takes. Clicking it resumes tiling.
+    pub(super) fn paint_paused_chip(
+        &self,
+        hdc: &HDC,
+        rect: RECT,
+        left: i32,
+        paint: bool,
+    ) -> anyhow::Result<i32> {
+        if !self.paused {
+            return Ok(0);
+        }
+
+        let color = self.settings.colors.warning;
+        let width = self.paint_chip(hdc, rect, left, self.strings().paused, color, paint)?;
+        if paint {
+            self.paused_chip_span.set(Some((left, left + width)));
+        }
+        Ok(width)
+    }
+
+    /// Draws the focused workspace's layer as a chip starting at `left` if
+    /// enabled, and returns the width it takes. Clicking it toggles the layer.
+    pub(super) fn paint_layer_chip(
+        &self,
+        hdc: &HDC,
+        rect: RECT,
+        left: i32,
+        paint: bool,
+    ) -> anyhow::Result<i32> {
+        let Some(workspace) = self.workspaces.focused() else {
+            return Ok(0);
+        };
+        if !self.config.layer_indicator {
+            return Ok(0);
+        }
+
+        let (label, color) = match workspace.layer {
+            WorkspaceLayer::Tiling => (self.strings().tiling, self.settings.colors.empty),
+            WorkspaceLayer::Floating => (self.strings().floating, self.settings.colors.nonempty),
+        };
+        let width = self.paint_chip(hdc, rect, left, label, color, paint)?;
+        if paint {
+            self.layer_chip_span.set(Some((left, left + width)));
+        }
+        Ok(width)
+    }
+
+    /// Draws the focused workspace's layout name as a chip starting at `left`
+    /// if enabled, and returns the width it takes. Clicking it cycles layouts.
+    pub(super) fn paint_layout_chip(
+        &self,
+        hdc: &HDC,
+        rect: RECT,
+        left: i32,
+        paint: bool,
+    ) -> anyhow::Result<i32> {
+        let Some(workspace) = self.workspaces.focused() else {
+            return Ok(0);
+        };
+        if !self.config.layout_indicator {
+            return Ok(0);
+        }
+
+        let label = crate::komo::layout_name(&workspace.layout);
+        let width = self.paint_chip(hdc, rect, left, label, self.settings.colors.empty, paint)?;
+        if paint {
+            self.layout_chip_span.set(Some((left, left + width)));
+        }
+        Ok(width)
+    }
+
+    /// Draws counters of the focused workspace's floating and minimized windows
+    /// starting at `left` if enabled, and returns the width they take. Neither
+    /// kind shows up in the tiled layout.
+    pub(super) fn paint_hidden_window_counts(
+        &self,
+        hdc: &HDC,
+        rect: RECT,
+        left: i32,
+        paint: bool,
+    ) -> anyhow::Result<i32> {
+        let Some(workspace) = self.workspaces.focused() else {
+            return Ok(0);
+        };
+        if !self.config.hidden_window_counts {
+            return Ok(0);
+        }
+
+        let floating = workspace.floating_windows().len();
+        // komorebi keeps minimized windows in place, only Windows knows about them
+        let minimized = crate::komo::workspace_windows(workspace)
+            .into_iter()
+            .filter(|window| unsafe { HWND::from_ptr(window.hwnd as _) }.IsIconic())
+            .count();
+
+        let mut width = 0;
+        let strings = self.strings();
+        for (label, count) in [(strings.floating, floating), (strings.minimized, minimized)] {
+            if count == 0 {
+                continue;
+            }
+            if width > 0 {
+                width += self.px(4);
+            }
+            let text = format!("{label} {count}");
+            let color = self.settings.colors.empty;
+            width += self.paint_chip(hdc, rect, left + width, &text, color, paint)?;
+        }
+        Ok(width)
+    }
+
+    /// Draws the focused container's position in its stack, like "2/4", as a
+    /// chip starting at `left` when it holds more than one window, and returns
+    /// the width it takes. Scrolling over it cycles the stack.
+    pub(super) fn paint_stack_chip(
+        &self,
+        hdc: &HDC,
+        rect: RECT,
+        left: i32,
+        paint: bool,
+    ) -> anyhow::Result<i32> {
+        let Some(workspace) = self.workspaces.focused() else {
+            return Ok(0);
+        };
+        let container = match workspace.monocle_container() {
+            Some(container) => container,
+            None => match workspace.focused_container() {
+                Some(container) => container,
+                None => return Ok(0),
+            },
+        };
+        let total = container.windows().len();
+        if total <= 1 {
+            return Ok(0);
+        }
+
+        let label = format!("{}/{total}", container.focused_window_idx() + 1);
+        let width = self.paint_chip(hdc, rect, left, &label, self.settings.colors.empty, paint)?;
+        if paint {
+            self.stack_chip_span.set(Some((left, left + width)));
+        }
+        Ok(width)
+    }
+
+    /// Draws `label` on a rounded chip of `color` starting at `left`, and
+    /// returns the width it takes.
+    fn paint_chip(
+        &self,
+        hdc: &HDC,
+        rect: RECT,
+        left: i32,
+        label: &str,
+        color: COLORREF,
+        paint: bool,
+    ) -> anyhow::Result<i32> {
+        let width = hdc.GetTextExtentPoint32(label)?.cx + self.padding() * 2;
+        if paint {
+            let chip_rect = RECT {
+                left,
+                right: left + width,
+                top: rect.top + self.px(12),
+                bottom: rect.bottom - self.px(12),
+            };
+            let brush = HBRUSH::CreateSolidBrush(color)?;
+            let _old_brush = hdc.SelectObject(&*brush);
+            hdc.RoundRect(chip_rect, self.border_radius())?;
+            hdc.DrawText(
+                label,
+                chip_rect,
+                co::DT::CENTER | co::DT::VCENTER | co::DT::SINGLELINE,
+            )?;
+        }
+        Ok(width)
+    }
+
+    /// Draws the focused window's title starting at `left` if enabled, and
+    /// returns the width it takes.
+    pub(super) fn paint_window_title(
+        &self,
+        hdc: &HDC,
+        rect: RECT,
+        left: i32,
+        paint: bool,
+    ) -> anyhow::Result<i32> {
+        if !self.config.window_title || self.focused_title.is_empty() {
+            return Ok(0);
+        }
+
+        let mut title: String = self
+            .focused_title
+            .chars()
+            .take(self.config.max_title_chars)
+            .collect();
+        if title.len() < self.focused_title.len() {
+            title.pop();
+            title.push('…');
+        }
+
+        let font = &self.settings.font;
+        let width = self.text_width(hdc, font, &title)? + self.padding() * 2;
+        if paint {
+            let title_rect = RECT {
+                left: left + self.padding(),
+                right: left + width,
+                top: rect.top,
+                bottom: rect.bottom,
+            };
+            self.draw_text(hdc, font, &title, title_rect, false)?;
+        }
+        Ok(width)
+    }
+
+    /// The state indicator of `button` in `style`, larger when the workspace
+    /// is focused. Only ever drawn within the button, so hit-testing is the
+    /// same for every style.
+    pub(super) fn indicator_rect(
+        &self,
+        rect: RECT,
+        button: &WorkspaceButton,
+        style: IndicatorStyle,
+        focused: bool,
+    ) -> RECT {
+        // vertical center of the indicator when it is not beside the label
+        let center = if button.has_content() {
+            rect.bottom - self.px(15)
+        } else {
+            rect.bottom / 2
+        };
+
+        match style {
+            IndicatorStyle::Underline => {
+                let h_padding = self.px(if focused { 5 } else { 10 });
+                RECT {
+                    left: button.left + h_padding,
+                    right: button.right() - h_padding,
+                    top: center - self.px(5),
+                    bottom: center + self.px(5),
+                }
+            }
+            IndicatorStyle::Pill => RECT {
+                left: button.left + self.px(2),
+                right: button.right() - self.px(2),
+                top: rect.top + self.px(4),
+                bottom: rect.bottom - self.px(4),
+            },
+            IndicatorStyle::Dot => {
+                let size = self.px(if focused { 8 } else { 6 });
+                let left = (button.left + button.right() - size) / 2;
+                RECT {
+                    left,
+                    right: left + size,
+                    top: center - size / 2,
+                    bottom: center - size / 2 + size,
+                }
+            }
+            IndicatorStyle::EdgeBar => {
+                let left = button.left + self.px(2);
+                RECT {
+                    left,
+                    right: left + self.px(if focused { 4 } else { 3 }),
+                    top: rect.top + self.px(10),
+                    bottom: rect.bottom - self.px(10),
+                }
+            }
+        }
+    }
+
+    /// Text of the box for container `idx` in the scrolling layout indicator.
+    pub(super) fn container_label(&self, workspace: &Workspace, idx: usize) -> String {
+        let number = || (idx + 1).to_string();
+        if self.config.container_labels == ContainerLabels::Index {
+            return number();
+        }
+
+        let Some(window) = workspace
+            .containers()
+            .get(idx)
+            .and_then(|container| container.focused_window())
+        else {
+            return number();
+        };
+
+        if !self.config.container_glyphs.is_empty() {
+            if let Ok(exe) = window.exe() {
+                let glyph = self
+                    .config
+                    .container_glyphs
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(&exe));
+                if let Some((_, glyph)) = glyph {
+                    return glyph.clone();
+                }
+            }
+        }
+
+        window
+            .title()
+            .ok()
+            .and_then(|title| title.chars().find(|c| c.is_alphanumeric()))
+            .map_or_else(number, |initial| initial.to_uppercase().collect())
+    }
+}
@@ -0,0 +1,354 @@
+use komorebi_client::{Axis, CycleDirection, SocketMessage};
+use winsafe::{prelude::*, *};
+
+use super::{
+    Window,
+    dialog::{
+        self, BN_CLICKED, EN_CHANGE, ES_AUTOHSCROLL, ID_CANCEL, ID_OK, LB_ADDSTRING, LB_GETCURSEL,
+        LB_RESETCONTENT, LB_SETCURSEL, LBN_DBLCLK, LBS_NOINTEGRALHEIGHT, LBS_NOTIFY,
+    },
+    locale::Strings,
+};
+
+pub(super) const CLASS_NAME: &str = "komoswitch.palette";
+
+const WA_INACTIVE: usize = 0;
+
+const ID_FILTER: u16 = 100;
+const ID_LIST: u16 = 101;
+
+// Palette metrics at 96 DPI
+const MARGIN: i32 = 8;
+const WIDTH: i32 = 320;
+const FILTER_HEIGHT: i32 = 23;
+const LIST_HEIGHT: i32 = 200;
+
+/// The commands on offer by the name they are filtered on, before the layouts
+/// to change to and the custom commands
+const COMMANDS: &[(fn(&Strings) -> &'static str, fn() -> SocketMessage)] = &[
+    (|strings| strings.retile, || SocketMessage::Retile),
+    (|strings| strings.toggle_float, || SocketMessage::ToggleFloat),
+    (|strings| strings.toggle_monocle, || SocketMessage::ToggleMonocle),
+    (|strings| strings.toggle_maximize, || SocketMessage::ToggleMaximize),
+    (|strings| strings.promote, || SocketMessage::Promote),
+    (|strings| strings.toggle_pause, || SocketMessage::TogglePause),
+    (|strings| strings.toggle_tiling, || SocketMessage::ToggleTiling),
+    (|strings| strings.flip_horizontally, || SocketMessage::FlipLayout(Axis::Horizontal)),
+    (|strings| strings.flip_vertically, || SocketMessage::FlipLayout(Axis::Vertical)),
+    (|strings| strings.next_layout, || SocketMessage::CycleLayout(CycleDirection::Next)),
+    (|strings| strings.previous_layout, || SocketMessage::CycleLayout(CycleDirection::Previous)),
+    (|strings| strings.minimize_window, || SocketMessage::Minimize),
+    (|strings| strings.close_window, || SocketMessage::Close),
+];
+
+/// How well `query` matches `text` as a case-insensitive subsequence, higher
+/// being better, or `None` when it does not match at all. Runs of adjacent
+/// characters and matches at word starts score extra.
+fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut pos = 0;
+    let mut previous: Option<usize> = None;
+    for wanted in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = pos + text[pos..].iter().position(|c| *c == wanted)?;
+        score += 1;
+        if previous.is_some_and(|previous| previous + 1 == found) {
+            score += 5;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        previous = Some(found);
+        pos = found + 1;
+    }
+    Some(score)
+}
+
+/// What picking an entry of the palette does
+enum PaletteAction {
+    Command(SocketMessage),
+    /// Runs the custom command of that name from the config
+    Custom(String),
+    /// Focuses a window, after switching to its monitor and workspace
+    Window {
+        monitor_idx: usize,
+        workspace_idx: usize,
+        hwnd: isize,
+    },
+}
+
+struct PaletteEntry {
+    label: String,
+    action: PaletteAction,
+}
+
+/// The open palette, everything it offers and the entries its list shows,
+/// best match first
+pub(super) struct CommandPalette {
+    hwnd: HWND,
+    entries: Vec<PaletteEntry>,
+    matches: Vec<usize>,
+}
+
+impl Window {
+    /// Opens a filterable list of komorebi commands.
+    pub(super) fn show_command_palette(&mut self) -> anyhow::Result<isize> {
+        let strings = self.strings();
+        let builtin = COMMANDS.iter().map(|(name, message)| PaletteEntry {
+            label: name(strings).to_string(),
+            action: PaletteAction::Command(message()),
+        });
+        let layouts = crate::komo::DEFAULT_LAYOUTS.iter().map(|(layout, name)| PaletteEntry {
+            label: format!("{}: {name}", strings.layout),
+            action: PaletteAction::Command(SocketMessage::ChangeLayout(*layout)),
+        });
+        let custom = self.config.custom_commands.iter().map(|command| PaletteEntry {
+            label: command.name.clone(),
+            action: PaletteAction::Custom(command.name.clone()),
+        });
+        let entries = builtin.chain(layouts).chain(custom).collect();
+        self.show_palette(strings.palette_title, entries)
+    }
+
+    /// Opens a filterable list of the windows on every workspace of every
+    /// monitor, like Alt+Tab with a search box.
+    pub(super) fn show_window_search(&mut self) -> anyhow::Result<isize> {
+        let state = crate::komo::read_state()?;
+        let mut entries = Vec::new();
+        for (monitor_idx, monitor) in state.monitors.elements().iter().enumerate() {
+            for (workspace_idx, workspace) in monitor.workspaces.elements().iter().enumerate() {
+                let workspace_name = workspace
+                    .name
+                    .clone()
+                    .unwrap_or((workspace_idx + 1).to_string());
+                for window in crate::komo::workspace_windows(workspace) {
+                    let Ok(title) = window.title() else {
+                        continue;
+                    };
+                    entries.push(PaletteEntry {
+                        label: (self.strings().window_search_entry)(
+                            &title,
+                            &workspace_name,
+                            monitor_idx + 1,
+                        ),
+                        action: PaletteAction::Window {
+                            monitor_idx,
+                            workspace_idx,
+                            hwnd: window.hwnd,
+                        },
+                    });
+                }
+            }
+        }
+        self.show_palette(self.strings().windows, entries)
+    }
+
+    fn show_palette(&mut self, title: &str, entries: Vec<PaletteEntry>) -> anyhow::Result<isize> {
+        // one palette at a time, the latest asked for wins
+        self.close_command_palette()?;
+
+        let hinstance = HINSTANCE::GetModuleHandle(None)?;
+        dialog::register_class(&hinstance, CLASS_NAME, Self::command_palette_proc)?;
+        let client = SIZE {
+            cx: self.px(MARGIN * 2 + WIDTH),
+            cy: self.px(MARGIN * 3 + FILTER_HEIGHT + LIST_HEIGHT),
+        };
+        let palette = dialog::create_window(
+            self,
+            &hinstance,
+            CLASS_NAME,
+            title,
+            co::WS_EX::TOPMOST,
+            client,
+            |size| {
+                Ok(POINT {
+                    x: (GetSystemMetrics(co::SM::CXSCREEN) - size.cx) / 2,
+                    y: (GetSystemMetrics(co::SM::CYSCREEN) - size.cy) / 3,
+                })
+            },
+        )?;
+        self.create_palette_controls(&palette, &hinstance)?;
+        self.command_palette = Some(CommandPalette {
+            hwnd: palette,
+            entries,
+            matches: Vec::new(),
+        });
+        self.filter_entries()?;
+
+        if let Some(palette) = &self.command_palette {
+            palette.hwnd.ShowWindow(co::SW::SHOW);
+            palette.hwnd.SetForegroundWindow();
+            if let Ok(filter) = palette.hwnd.GetDlgItem(ID_FILTER) {
+                filter.SetFocus();
+            }
+        }
+        Ok(0)
+    }
+
+    fn create_palette_controls(&self, palette: &HWND, hinst: &HINSTANCE) -> anyhow::Result<()> {
+        let controls = [
+            (
+                "EDIT",
+                ID_FILTER,
+                co::WS::TABSTOP | ES_AUTOHSCROLL,
+                self.px(MARGIN),
+                FILTER_HEIGHT,
+            ),
+            (
+                "LISTBOX",
+                ID_LIST,
+                co::WS::TABSTOP | co::WS::VSCROLL | LBS_NOTIFY | LBS_NOINTEGRALHEIGHT,
+                self.px(MARGIN * 2 + FILTER_HEIGHT),
+                LIST_HEIGHT,
+            ),
+        ];
+
+        for (class, id, style, top, height) in controls {
+            dialog::create_control(
+                palette,
+                hinst,
+                class,
+                None,
+                id,
+                style,
+                co::WS_EX::CLIENTEDGE,
+                POINT {
+                    x: self.px(MARGIN),
+                    y: top,
+                },
+                SIZE {
+                    cx: self.px(WIDTH),
+                    cy: self.px(height),
+                },
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Refills the list with the entries matching the filter, best first,
+    /// and selects the top one.
+    fn filter_entries(&mut self) -> anyhow::Result<()> {
+        let Some(palette) = &mut self.command_palette else {
+            return Ok(());
+        };
+        let query = palette
+            .hwnd
+            .GetDlgItem(ID_FILTER)
+            .and_then(|filter| filter.GetWindowText())
+            .unwrap_or_default();
+
+        let mut scored: Vec<(i32, usize)> = palette
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, entry)| Some((fuzzy_score(&query, &entry.label)?, idx)))
+            .collect();
+        // stable, so equal scores keep the order of the entries
+        scored.sort_by_key(|(score, _)| -score);
+        palette.matches = scored.into_iter().map(|(_, idx)| idx).collect();
+
+        let list = palette.hwnd.GetDlgItem(ID_LIST)?;
+        unsafe {
+            list.SendMessage(msg::WndMsg::new(LB_RESETCONTENT, 0, 0));
+            for idx in &palette.matches {
+                let name = WString::from_str(&palette.entries[*idx].label);
+                list.SendMessage(msg::WndMsg::new(LB_ADDSTRING, 0, name.as_ptr() as isize));
+            }
+            list.SendMessage(msg::WndMsg::new(LB_SETCURSEL, 0, 0));
+        }
+        Ok(())
+    }
+
+    /// Closes the palette and acts on the selected entry, if any.
+    fn run_selected_entry(&mut self) -> anyhow::Result<isize> {
+        let Some(mut palette) = self.command_palette.take() else {
+            return Ok(0);
+        };
+        let selected = palette.hwnd.GetDlgItem(ID_LIST).ok().and_then(|list| {
+            let pos = unsafe { list.SendMessage(msg::WndMsg::new(LB_GETCURSEL, 0, 0)) };
+            usize::try_from(pos).ok()
+        });
+        let entry = selected
+            .and_then(|pos| palette.matches.get(pos).copied())
+            .map(|idx| palette.entries.swap_remove(idx));
+
+        // closing first gives the focus back to the window a command is for
+        if let Err(err) = palette.hwnd.DestroyWindow() {
+            log::error!("Failed to close command palette: {err}");
+        }
+        let Some(entry) = entry else {
+            return Ok(0);
+        };
+        log::info!("Picked from palette: {}", entry.label);
+        match entry.action {
+            PaletteAction::Command(message) => {
+                komorebi_client::send_query(&message)?;
+            }
+            PaletteAction::Custom(name) => {
+                self.run_custom_command(&name)?;
+            }
+            PaletteAction::Window {
+                monitor_idx,
+                workspace_idx,
+                hwnd,
+            } => {
+                komorebi_client::send_query(&SocketMessage::FocusMonitorWorkspaceNumber(
+                    monitor_idx,
+                    workspace_idx,
+                ))?;
+                // komorebi picks the window up as focused once it is in the foreground
+                komorebi_client::WindowsApi::raise_and_focus_window(hwnd)?;
+            }
+        }
+        Ok(0)
+    }
+
+    fn close_command_palette(&mut self) -> anyhow::Result<isize> {
+        if let Some(palette) = self.command_palette.take() {
+            if let Err(err) = palette.hwnd.DestroyWindow() {
+                log::error!("Failed to close command palette: {err}");
+            }
+        }
+        Ok(0)
+    }
+
+    fn handle_command_palette_message(
+        &mut self,
+        wm_any: msg::WndMsg,
+    ) -> Option<anyhow::Result<isize>> {
+        Some(match wm_any.msg_id {
+            co::WM::COMMAND => match dialog::command(wm_any) {
+                (ID_OK, BN_CLICKED) | (ID_LIST, LBN_DBLCLK) => self.run_selected_entry(),
+                (ID_CANCEL, BN_CLICKED) => self.close_command_palette(),
+                (ID_FILTER, EN_CHANGE) => self.filter_entries().map(|_| 0),
+                _ => Ok(0),
+            },
+            // like a menu, clicking elsewhere dismisses it
+            co::WM::ACTIVATE if wm_any.wparam & 0xffff == WA_INACTIVE => {
+                self.close_command_palette()
+            }
+            co::WM::CLOSE => self.close_command_palette(),
+            _ => return None,
+        })
+    }
+
+    extern "system" fn command_palette_proc(
+        hwnd: HWND,
+        msg: co::WM,
+        wparam: usize,
+        lparam: isize,
+    ) -> isize {
+        dialog::dialog_proc(
+            hwnd,
+            msg::WndMsg::new(msg, wparam, lparam),
+            "Command palette",
+            |window, hwnd| {
+                window
+                    .command_palette
+                    .as_ref()
+                    .is_some_and(|palette| palette.hwnd == *hwnd)
+            },
+            Self::handle_command_palette_message,
+        )
+    }
+}
@@ -0,0 +1,138 @@
+use windows::Win32::{
+    Foundation::HANDLE,
+    System::{
+        Power::{
+            GetSystemPowerStatus, POWERBROADCAST_SETTING, RegisterPowerSettingNotification,
+            RegisterSuspendResumeNotification, SYSTEM_POWER_STATUS,
+            UnregisterPowerSettingNotification, UnregisterSuspendResumeNotification,
+        },
+        SystemServices::{
+            GUID_ACDC_POWER_SOURCE, GUID_CONSOLE_DISPLAY_STATE, GUID_POWER_SAVING_STATUS,
+        },
+    },
+    UI::WindowsAndMessaging::{
+        DEVICE_NOTIFY_WINDOW_HANDLE, PBT_APMRESUMEAUTOMATIC, PBT_POWERSETTINGCHANGE,
+    },
+};
+use winsafe::prelude::*;
+
+use super::Window;
+use crate::config::PowerSaving;
+
+/// `GUID_ACDC_POWER_SOURCE` while plugged in, rather than on battery or a UPS
+const AC_POWER: u8 = 0;
+
+/// Workspace updates arriving closer together than this are painted at once
+/// while saving power, e.g. the burst komorebi sends while retiling
+pub(super) const SAVER_REFRESH_INTERVAL_MS: u32 = 200;
+
+/// The power source and battery saver as of now, to start with until the
+/// notifications come in.
+fn power_status() -> (bool, bool) {
+    let mut status = SYSTEM_POWER_STATUS::default();
+    match unsafe { GetSystemPowerStatus(&mut status) } {
+        Ok(()) => (status.ACLineStatus == 0, status.SystemStatusFlag == 1),
+        Err(err) => {
+            log::warn!("Failed to read the power status: {err}");
+            (false, false)
+        }
+    }
+}
+
+impl Window {
+    /// Asks for `WM_POWERBROADCAST` on suspend and resume, which the bar would
+    /// otherwise miss once embedded in the taskbar as a child window.
+    pub(super) fn register_resume_notification(&mut self) {
+        if self.power_notify.is_some() {
+            return;
+        }
+        let recipient = HANDLE(self.hwnd.ptr());
+        let notify =
+            unsafe { RegisterSuspendResumeNotification(recipient, DEVICE_NOTIFY_WINDOW_HANDLE) };
+        match notify {
+            Ok(notify) => self.power_notify = Some(notify),
+            Err(err) => log::error!("Failed to register for resume notifications: {err}"),
+        }
+    }
+
+    pub(super) fn unregister_resume_notification(&mut self) {
+        if let Some(notify) = self.power_notify.take() {
+            unsafe { UnregisterSuspendResumeNotification(notify) }.ok();
+        }
+    }
+
+    /// Asks to be told when the device goes on battery or the battery saver
+    /// turns on, see `saving_power`.
+    pub(super) fn register_power_source_notifications(&mut self) {
+        if !self.power_source_notify.is_empty() {
+            return;
+        }
+        (self.on_battery, self.battery_saver) = power_status();
+
+        let recipient = HANDLE(self.hwnd.ptr());
+        for setting in [GUID_ACDC_POWER_SOURCE, GUID_POWER_SAVING_STATUS] {
+            let notify = unsafe {
+                RegisterPowerSettingNotification(recipient, &setting, DEVICE_NOTIFY_WINDOW_HANDLE)
+            };
+            match notify {
+                Ok(notify) => self.power_source_notify.push(notify),
+                Err(err) => log::error!("Failed to register for power source changes: {err}"),
+            }
+        }
+    }
+
+    pub(super) fn unregister_power_source_notifications(&mut self) {
+        for notify in self.power_source_notify.drain(..) {
+            unsafe { UnregisterPowerSettingNotification(notify) }.ok();
+        }
+    }
+
+    /// Whether the bar does without animations, paints bursts of workspace
+    /// updates at once and leaves the screen alone when nothing it shows
+    /// changed, as `power_saving` asks for.
+    pub(super) fn saving_power(&self) -> bool {
+        match self.config.power_saving {
+            PowerSaving::BatterySaver => self.battery_saver,
+            PowerSaving::OnBattery => self.on_battery || self.battery_saver,
+            PowerSaving::Never => false,
+        }
+    }
+
+    /// The `PBT_POWERSETTINGCHANGE` broadcast, with the changed setting in
+    /// `setting`.
+    fn handle_power_setting_change(&mut self, setting: isize) -> anyhow::Result<isize> {
+        let setting = unsafe { &*(setting as *const POWERBROADCAST_SETTING) };
+        let value = setting.Data[0];
+        let was_saving = self.saving_power();
+        if setting.PowerSetting == GUID_CONSOLE_DISPLAY_STATE {
+            self.handle_display_state(value)?;
+        } else if setting.PowerSetting == GUID_ACDC_POWER_SOURCE {
+            self.on_battery = value != AC_POWER;
+        } else if setting.PowerSetting == GUID_POWER_SAVING_STATUS {
+            self.battery_saver = value != 0;
+        }
+
+        if self.saving_power() != was_saving {
+            log::info!("Power saving {}", if was_saving { "off" } else { "on" });
+        }
+        Ok(1)
+    }
+
+    /// Re-reads komorebi's state after the system wakes up, as the bar may
+    /// have missed notifications while komorebi kept running.
+    pub(super) fn handle_power_broadcast(
+        &mut self,
+        event: usize,
+        data: isize,
+    ) -> anyhow::Result<isize> {
+        if event as u32 == PBT_POWERSETTINGCHANGE {
+            return self.handle_power_setting_change(data);
+        }
+        if event as u32 != PBT_APMRESUMEAUTOMATIC {
+            return Ok(1);
+        }
+        log::info!("Resumed from sleep, resyncing with komorebi");
+        self.resync()?;
+        Ok(1)
+    }
+}
@@ -0,0 +1,227 @@
+use komorebi_client::SocketMessage;
+use winsafe::{prelude::*, *};
+
+use super::{
+    Window,
+    dialog::{self, BN_CLICKED, BS_DEFPUSHBUTTON, EM_SETSEL, ES_AUTOHSCROLL, ID_CANCEL, ID_OK},
+};
+
+pub(super) const CLASS_NAME: &str = "komoswitch.rename";
+
+const ID_NAME: u16 = 100;
+
+// Dialog metrics at 96 DPI
+const MARGIN: i32 = 12;
+const CONTROL_HEIGHT: i32 = 23;
+const EDIT_WIDTH: i32 = 240;
+const BUTTON_WIDTH: i32 = 80;
+
+/// A workspace being renamed and the dialog asking for its new name
+pub(super) struct RenameDialog {
+    hwnd: HWND,
+    workspace_idx: usize,
+}
+
+impl Window {
+    /// Asks for a new name for workspace `idx` and sends it to komorebi.
+    pub(super) fn show_rename_dialog(&mut self, idx: usize) -> anyhow::Result<isize> {
+        if let Some(dialog) = &self.rename_dialog {
+            dialog.hwnd.SetForegroundWindow();
+            return Ok(0);
+        }
+        let Some(workspace) = self.workspaces.elements().get(idx) else {
+            return Ok(0);
+        };
+        let name = workspace.name.clone().unwrap_or_default();
+
+        let hinstance = HINSTANCE::GetModuleHandle(None)?;
+        dialog::register_class(&hinstance, CLASS_NAME, Self::rename_dialog_proc)?;
+        let client = SIZE {
+            cx: self.px(MARGIN * 2 + EDIT_WIDTH),
+            cy: self.px(MARGIN * 3 + CONTROL_HEIGHT * 2),
+        };
+        let dialog = dialog::create_window(
+            self,
+            &hinstance,
+            CLASS_NAME,
+            &(self.strings().rename_title)(idx + 1),
+            co::WS_EX::default(),
+            client,
+            |size| self.rename_dialog_position(size),
+        )?;
+        self.create_rename_controls(&dialog, &hinstance, &name)?;
+        dialog.ShowWindow(co::SW::SHOW);
+        dialog.SetForegroundWindow();
+        if let Ok(edit) = dialog.GetDlgItem(ID_NAME) {
+            edit.SetFocus();
+            unsafe {
+                edit.SendMessage(msg::WndMsg::new(EM_SETSEL, 0, -1));
+            }
+        }
+        self.rename_dialog = Some(RenameDialog {
+            hwnd: dialog,
+            workspace_idx: idx,
+        });
+        Ok(0)
+    }
+
+    /// Just above the bar under the cursor, or below it when the bar is at
+    /// the top of the screen, so the popup sits next to the renamed button.
+    fn rename_dialog_position(&self, size: SIZE) -> anyhow::Result<POINT> {
+        let cursor = GetCursorPos()?;
+        let bar = self.hwnd.GetWindowRect()?;
+        let screen_width = GetSystemMetrics(co::SM::CXSCREEN);
+        let y = if bar.top - size.cy >= 0 {
+            bar.top - size.cy
+        } else {
+            bar.bottom
+        };
+        Ok(POINT {
+            x: (cursor.x - size.cx / 2).clamp(0, (screen_width - size.cx).max(0)),
+            y,
+        })
+    }
+
+    fn create_rename_controls(
+        &self,
+        dialog: &HWND,
+        hinst: &HINSTANCE,
+        name: &str,
+    ) -> anyhow::Result<()> {
+        let strings = self.strings();
+        let cancel_left = self.px(MARGIN + EDIT_WIDTH - BUTTON_WIDTH);
+        let buttons_top = self.px(MARGIN * 2 + CONTROL_HEIGHT);
+        let controls = [
+            (
+                "EDIT",
+                name,
+                ID_NAME,
+                co::WS::TABSTOP | ES_AUTOHSCROLL,
+                co::WS_EX::CLIENTEDGE,
+                POINT {
+                    x: self.px(MARGIN),
+                    y: self.px(MARGIN),
+                },
+                EDIT_WIDTH,
+            ),
+            (
+                "BUTTON",
+                strings.ok,
+                ID_OK,
+                co::WS::TABSTOP | BS_DEFPUSHBUTTON,
+                co::WS_EX::default(),
+                POINT {
+                    x: cancel_left - self.px(MARGIN + BUTTON_WIDTH),
+                    y: buttons_top,
+                },
+                BUTTON_WIDTH,
+            ),
+            (
+                "BUTTON",
+                strings.cancel,
+                ID_CANCEL,
+                co::WS::TABSTOP,
+                co::WS_EX::default(),
+                POINT {
+                    x: cancel_left,
+                    y: buttons_top,
+                },
+                BUTTON_WIDTH,
+            ),
+        ];
+
+        for (class, text, id, style, ex_style, pos, width) in controls {
+            let size = SIZE {
+                cx: self.px(width),
+                cy: self.px(CONTROL_HEIGHT),
+            };
+            dialog::create_control(
+                dialog,
+                hinst,
+                class,
+                Some(text),
+                id,
+                style,
+                ex_style,
+                pos,
+                size,
+            )?;
+        }
+        Ok(())
+    }
+
+    fn rename_workspace(&mut self) -> anyhow::Result<isize> {
+        let Some(dialog) = self.rename_dialog.take() else {
+            return Ok(0);
+        };
+        let name = dialog
+            .hwnd
+            .GetDlgItem(ID_NAME)
+            .and_then(|edit| edit.GetWindowText())
+            .map(|text| text.trim().to_string())
+            .unwrap_or_default();
+        if let Err(err) = dialog.hwnd.DestroyWindow() {
+            log::error!("Failed to close rename dialog: {err}");
+        }
+        if name.is_empty() {
+            return Ok(0);
+        }
+
+        let (monitor_idx, workspace_idx) = self.workspace_target(dialog.workspace_idx);
+        let monitor_idx = match monitor_idx {
+            Some(monitor_idx) => monitor_idx,
+            None => crate::komo::read_state()?.monitors.focused_idx(),
+        };
+        log::info!("Renaming workspace {} to {name:?}", dialog.workspace_idx);
+        komorebi_client::send_query(&SocketMessage::WorkspaceName(
+            monitor_idx,
+            workspace_idx,
+            name,
+        ))?;
+        self.resync()
+    }
+
+    fn close_rename_dialog(&mut self) -> anyhow::Result<isize> {
+        if let Some(dialog) = self.rename_dialog.take() {
+            if let Err(err) = dialog.hwnd.DestroyWindow() {
+                log::error!("Failed to close rename dialog: {err}");
+            }
+        }
+        Ok(0)
+    }
+
+    fn handle_rename_dialog_message(
+        &mut self,
+        wm_any: msg::WndMsg,
+    ) -> Option<anyhow::Result<isize>> {
+        Some(match wm_any.msg_id {
+            co::WM::COMMAND => match dialog::command(wm_any) {
+                (ID_OK, BN_CLICKED) => self.rename_workspace(),
+                (ID_CANCEL, BN_CLICKED) => self.close_rename_dialog(),
+                _ => Ok(0),
+            },
+            co::WM::CLOSE => self.close_rename_dialog(),
+            _ => return None,
+        })
+    }
+
+    extern "system" fn rename_dialog_proc(
+        hwnd: HWND,
+        msg: co::WM,
+        wparam: usize,
+        lparam: isize,
+    ) -> isize {
+        dialog::dialog_proc(
+            hwnd,
+            msg::WndMsg::new(msg, wparam, lparam),
+            "Rename",
+            |window, hwnd| {
+                window
+                    .rename_dialog
+                    .as_ref()
+                    .is_some_and(|dialog| dialog.hwnd == *hwnd)
+            },
+            Self::handle_rename_dialog_message,
+        )
+    }
+}
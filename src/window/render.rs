@@ -0,0 +1,325 @@
+use std::ffi::c_void;
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use windows::Win32::{
+    Foundation::{
+        COLORREF as WIN32_COLORREF, HWND as WIN32_HWND, POINT as WIN32_POINT, RECT as WIN32_RECT,
+        SIZE as WIN32_SIZE,
+    },
+    Graphics::Gdi::{
+        AC_SRC_ALPHA, AC_SRC_OVER, BI_RGB, BITMAPINFO, BITMAPINFOHEADER, BLENDFUNCTION,
+        CreateCompatibleDC, CreateDIBSection, DIB_RGB_COLORS, DeleteDC, DeleteObject, GdiFlush,
+        HBITMAP, HDC as WIN32_HDC, HGDIOBJ, LAYOUT_BITMAPORIENTATIONPRESERVED, LAYOUT_RTL,
+        SelectObject, SetLayout,
+    },
+    UI::WindowsAndMessaging::{ULW_ALPHA, UPDATELAYEREDWINDOWINFO, UpdateLayeredWindowIndirect},
+};
+use winsafe::{prelude::*, *};
+
+use super::Window;
+
+/// Opacity of the whole bar while komorebi is offline, out of 255
+const OFFLINE_OPACITY: u8 = 110;
+
+/// A 32-bit top-down bitmap selected into a memory DC.
+struct Surface {
+    hdc: WIN32_HDC,
+    bitmap: HBITMAP,
+    old_bitmap: HGDIOBJ,
+    bits: *mut u32,
+    len: usize,
+}
+
+impl Surface {
+    fn new(size: SIZE) -> anyhow::Result<Self> {
+        let info = BITMAPINFO {
+            bmiHeader: BITMAPINFOHEADER {
+                biSize: size_of::<BITMAPINFOHEADER>() as u32,
+                biWidth: size.cx,
+                biHeight: -size.cy,
+                biPlanes: 1,
+                biBitCount: 32,
+                biCompression: BI_RGB.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        unsafe {
+            let hdc = CreateCompatibleDC(None);
+            let mut bits: *mut c_void = std::ptr::null_mut();
+            let bitmap = match CreateDIBSection(Some(hdc), &info, DIB_RGB_COLORS, &mut bits, None, 0)
+            {
+                Ok(bitmap) => bitmap,
+                Err(err) => {
+                    let _ = DeleteDC(hdc);
+                    return Err(err.into());
+                }
+            };
+            let old_bitmap = SelectObject(hdc, HGDIOBJ(bitmap.0));
+
+            Ok(Self {
+                hdc,
+                bitmap,
+                old_bitmap,
+                bits: bits as *mut u32,
+                len: (size.cx * size.cy) as usize,
+            })
+        }
+    }
+
+    /// Flips the x axis of everything drawn afterwards, except text and icons.
+    fn mirror(&self) {
+        unsafe {
+            SetLayout(self.hdc, LAYOUT_RTL | LAYOUT_BITMAPORIENTATIONPRESERVED);
+        }
+    }
+
+    fn hdc(&self) -> HDC {
+        unsafe { HDC::from_ptr(self.hdc.0) }
+    }
+
+    /// Pixels as `0xAARRGGBB`; GDI must be flushed before reading them.
+    fn pixels(&mut self) -> &mut [u32] {
+        unsafe { std::slice::from_raw_parts_mut(self.bits, self.len) }
+    }
+}
+
+impl Drop for Surface {
+    fn drop(&mut self) {
+        unsafe {
+            SelectObject(self.hdc, self.old_bitmap);
+            let _ = DeleteObject(HGDIOBJ(self.bitmap.0));
+            let _ = DeleteDC(self.hdc);
+        }
+    }
+}
+
+/// Tells apart frames differing in any pixel, in size or in opacity, and
+/// frames for another window.
+fn frame_hash(hwnd: &HWND, surface: &mut Surface, size: SIZE, opacity: u8) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    (hwnd.ptr() as usize, size.cx, size.cy, opacity).hash(&mut hasher);
+    surface.pixels().hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Turns `surface`, a bar lying down, a quarter turn clockwise so it stands in
+/// a vertical taskbar, its text reading from top to bottom.
+fn rotate(surface: &mut Surface, size: SIZE) -> anyhow::Result<Surface> {
+    let mut rotated = Surface::new(SIZE {
+        cx: size.cy,
+        cy: size.cx,
+    })?;
+    let source = surface.pixels();
+    let target = rotated.pixels();
+    for y in 0..size.cy {
+        for x in 0..size.cx {
+            target[(x * size.cy + size.cy - 1 - y) as usize] = source[(y * size.cx + x) as usize];
+        }
+    }
+    Ok(rotated)
+}
+
+/// Recovers the premultiplied color and coverage of a pixel drawn once over
+/// black and once over white: coverage is what the white background no longer
+/// shows through, and the pixel over black is already premultiplied.
+fn unblend(over_black: u32, over_white: u32) -> u32 {
+    let channel = |pixel: u32, shift: u32| (pixel >> shift) & 0xff;
+    let shown_through: u32 = [0, 8, 16]
+        .iter()
+        .map(|&shift| channel(over_white, shift).saturating_sub(channel(over_black, shift)))
+        .sum::<u32>()
+        / 3;
+    let alpha = 255 - shown_through;
+
+    let premultiplied = [0, 8, 16]
+        .iter()
+        .map(|&shift| channel(over_black, shift).min(alpha) << shift)
+        .fold(0, |pixel, channel| pixel | channel);
+    (alpha << 24) | premultiplied
+}
+
+/// Coverage between 0 and 1 of the pixel at `x`, `y` by a rectangle with
+/// corners rounded by `radius`, for antialiased edges.
+fn rounded_rect_coverage(x: i32, y: i32, rect: RECT, radius: f32) -> f32 {
+    let half_width = (rect.right - rect.left) as f32 / 2.0;
+    let half_height = (rect.bottom - rect.top) as f32 / 2.0;
+    let radius = radius.min(half_width).min(half_height);
+    // distance from the pixel center to the rounded outline, negative inside
+    let qx = (x as f32 + 0.5 - rect.left as f32 - half_width).abs() - (half_width - radius);
+    let qy = (y as f32 + 0.5 - rect.top as f32 - half_height).abs() - (half_height - radius);
+    let outside = qx.max(0.0).hypot(qy.max(0.0));
+    let distance = outside + qx.max(qy).min(0.0) - radius;
+    (0.5 - distance).clamp(0.0, 1.0)
+}
+
+/// Composites a rounded rectangle of `color` at `opacity` under the
+/// premultiplied `pixels`.
+fn composite_capsule(
+    pixels: &mut [u32],
+    size: SIZE,
+    capsule_rect: RECT,
+    radius: f32,
+    color: COLORREF,
+    opacity: f32,
+) {
+    let color = [color.GetBValue(), color.GetGValue(), color.GetRValue()];
+    for y in capsule_rect.top.max(0)..capsule_rect.bottom.min(size.cy) {
+        for x in capsule_rect.left.max(0)..capsule_rect.right.min(size.cx) {
+            let alpha = opacity * rounded_rect_coverage(x, y, capsule_rect, radius);
+            if alpha <= 0.0 {
+                continue;
+            }
+
+            let pixel = &mut pixels[(y * size.cx + x) as usize];
+            let shown_through = 1.0 - (*pixel >> 24) as f32 / 255.0;
+            let over = |value: u32, under: f32| {
+                (value as f32 + under * shown_through).round().min(255.0) as u32
+            };
+            let mut composited = over(*pixel >> 24, alpha * 255.0) << 24;
+            for (idx, channel) in color.iter().enumerate() {
+                let shift = idx as u32 * 8;
+                let value = (*pixel >> shift) & 0xff;
+                composited |= over(value, *channel as f32 * alpha) << shift;
+            }
+            *pixel = composited;
+        }
+    }
+}
+
+impl Window {
+    /// Draws the bar with per-pixel alpha through `UpdateLayeredWindowIndirect`,
+    /// so antialiased edges blend with whatever is behind the bar.
+    pub(super) fn render(&self) -> anyhow::Result<()> {
+        self.render_dirty(None)
+    }
+
+    /// Draws the bar like `render`, but only has the screen update the button
+    /// of workspace `changed` if given.
+    pub(super) fn render_dirty(&self, changed: Option<usize>) -> anyhow::Result<()> {
+        let rect = self.bar_rect()?;
+        let size = SIZE {
+            cx: rect.right - rect.left,
+            cy: rect.bottom - rect.top,
+        };
+        if size.cx <= 0 || size.cy <= 0 {
+            return Ok(());
+        }
+
+        let mut over_black = Surface::new(size)?;
+        let mut over_white = Surface::new(size)?;
+        for (surface, background) in [(&mut over_black, 0x000000), (&mut over_white, 0xffffff)] {
+            surface.pixels().fill(background);
+            if self.settings.mirrored {
+                surface.mirror();
+            }
+            self.paint_and_get_width(&surface.hdc(), true)?;
+        }
+        unsafe {
+            let _ = GdiFlush();
+        }
+
+        let white_pixels = over_white.pixels();
+        for (black, white) in over_black.pixels().iter_mut().zip(white_pixels.iter()) {
+            *black = unblend(*black, *white);
+        }
+        if let Some(capsule) = self.config.capsule {
+            let capsule_rect = RECT {
+                left: 0,
+                right: size.cx,
+                top: self.px(4),
+                bottom: size.cy - self.px(4),
+            };
+            let color = capsule.color.map_or(self.settings.colors.text_effect, |color| {
+                COLORREF::from_rgb(color.r, color.g, color.b)
+            });
+            composite_capsule(
+                over_black.pixels(),
+                size,
+                capsule_rect,
+                self.px(capsule.corner_radius.max(0)) as f32,
+                color,
+                capsule.opacity.clamp(0.0, 1.0),
+            );
+        }
+
+        let dirty = match changed {
+            Some(idx) => self
+                .layout_workspaces(&over_black.hdc())?
+                .into_iter()
+                .find(|button| button.idx == idx)
+                .map(|button| {
+                    let (left, right) = if self.settings.mirrored {
+                        (size.cx - button.right(), size.cx - button.left)
+                    } else {
+                        (button.left, button.right())
+                    };
+                    if self.vertical {
+                        WIN32_RECT {
+                            left: 0,
+                            top: left,
+                            right: size.cy,
+                            bottom: right,
+                        }
+                    } else {
+                        WIN32_RECT {
+                            left,
+                            top: 0,
+                            right,
+                            bottom: size.cy,
+                        }
+                    }
+                }),
+            None => None,
+        };
+
+        let (mut surface, size) = if self.vertical {
+            let rotated = rotate(&mut over_black, size)?;
+            let size = SIZE {
+                cx: size.cy,
+                cy: size.cx,
+            };
+            (rotated, size)
+        } else {
+            (over_black, size)
+        };
+
+        let opacity = if self.offline { OFFLINE_OPACITY } else { 255 };
+        // handing the screen the same frame again only costs compositing, but
+        // so does hashing every frame when not saving power
+        if self.saving_power() {
+            let frame = frame_hash(&self.hwnd, &mut surface, size, opacity);
+            if self.last_frame.replace(Some(frame)) == Some(frame) {
+                return Ok(());
+            }
+        } else {
+            self.last_frame.set(None);
+        }
+
+        let blend = BLENDFUNCTION {
+            BlendOp: AC_SRC_OVER as u8,
+            BlendFlags: 0,
+            SourceConstantAlpha: opacity,
+            AlphaFormat: AC_SRC_ALPHA as u8,
+        };
+        let size = WIN32_SIZE {
+            cx: size.cx,
+            cy: size.cy,
+        };
+        let origin = WIN32_POINT::default();
+        let info = UPDATELAYEREDWINDOWINFO {
+            cbSize: size_of::<UPDATELAYEREDWINDOWINFO>() as u32,
+            psize: &size,
+            hdcSrc: surface.hdc,
+            pptSrc: &origin,
+            crKey: WIN32_COLORREF(0),
+            pblend: &blend,
+            dwFlags: ULW_ALPHA,
+            prcDirty: dirty.as_ref().map_or(std::ptr::null(), |dirty| dirty as *const _),
+            ..Default::default()
+        };
+        unsafe { UpdateLayeredWindowIndirect(WIN32_HWND(self.hwnd.ptr()), &info) }.ok()?;
+        Ok(())
+    }
+}
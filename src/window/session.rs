@@ -0,0 +1,100 @@
+use windows::Win32::{
+    Foundation::{HANDLE, HWND as WIN32_HWND},
+    System::{
+        Power::{RegisterPowerSettingNotification, UnregisterPowerSettingNotification},
+        RemoteDesktop::{
+            NOTIFY_FOR_THIS_SESSION, WTSRegisterSessionNotification,
+            WTSUnRegisterSessionNotification,
+        },
+        SystemServices::GUID_CONSOLE_DISPLAY_STATE,
+    },
+    UI::WindowsAndMessaging::{DEVICE_NOTIFY_WINDOW_HANDLE, WTS_SESSION_LOCK, WTS_SESSION_UNLOCK},
+};
+use winsafe::prelude::*;
+
+use super::Window;
+
+/// `GUID_CONSOLE_DISPLAY_STATE` of a display that is off, rather than on or
+/// dimmed
+const DISPLAY_OFF: u8 = 0;
+
+impl Window {
+    /// Asks for `WM_WTSSESSION_CHANGE` on lock and unlock, and to be told when
+    /// the display turns off and on. Windows sends the current display state
+    /// right away.
+    pub(super) fn register_session_notifications(&mut self) {
+        if !self.session_notify {
+            let hwnd = WIN32_HWND(self.hwnd.ptr());
+            match unsafe { WTSRegisterSessionNotification(hwnd, NOTIFY_FOR_THIS_SESSION) } {
+                Ok(()) => self.session_notify = true,
+                Err(err) => log::error!("Failed to register for session notifications: {err}"),
+            }
+        }
+
+        if self.display_notify.is_none() {
+            let recipient = HANDLE(self.hwnd.ptr());
+            let notify = unsafe {
+                RegisterPowerSettingNotification(
+                    recipient,
+                    &GUID_CONSOLE_DISPLAY_STATE,
+                    DEVICE_NOTIFY_WINDOW_HANDLE,
+                )
+            };
+            match notify {
+                Ok(notify) => self.display_notify = Some(notify),
+                Err(err) => log::error!("Failed to register for display state changes: {err}"),
+            }
+        }
+    }
+
+    pub(super) fn unregister_session_notifications(&mut self) {
+        if std::mem::take(&mut self.session_notify) {
+            unsafe { WTSUnRegisterSessionNotification(WIN32_HWND(self.hwnd.ptr())) }.ok();
+        }
+        if let Some(notify) = self.display_notify.take() {
+            unsafe { UnregisterPowerSettingNotification(notify) }.ok();
+        }
+    }
+
+    pub(super) fn handle_session_change(&mut self, event: usize) -> anyhow::Result<isize> {
+        match event as u32 {
+            WTS_SESSION_LOCK => self.set_unseen(true, self.display_off),
+            WTS_SESSION_UNLOCK => self.set_unseen(false, self.display_off),
+            _ => Ok(0),
+        }
+    }
+
+    /// `GUID_CONSOLE_DISPLAY_STATE` changed to `state`.
+    pub(super) fn handle_display_state(&mut self, state: u8) -> anyhow::Result<isize> {
+        self.set_unseen(self.locked, state == DISPLAY_OFF)
+    }
+
+    /// Whether nobody can see the bar, as the session is locked or the display
+    /// is off. Workspace updates are then only recorded and the background
+    /// threads stop polling.
+    pub(super) fn unseen(&self) -> bool {
+        self.locked || self.display_off
+    }
+
+    fn set_unseen(&mut self, locked: bool, display_off: bool) -> anyhow::Result<isize> {
+        let was_unseen = self.unseen();
+        self.locked = locked;
+        self.display_off = display_off;
+        let unseen = self.unseen();
+        if unseen == was_unseen {
+            return Ok(0);
+        }
+
+        crate::komo::pause_polling(unseen);
+        if unseen {
+            log::info!("Session locked or display off, pausing");
+            return Ok(0);
+        }
+
+        log::info!("Session unlocked and display on, resyncing with komorebi");
+        self.resync().or_else(|err| {
+            log::warn!("Failed to resync: {err}");
+            self.refresh()
+        })
+    }
+}
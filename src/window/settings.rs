@@ -1,37 +1,154 @@
+use std::{cell::RefCell, collections::HashMap};
+
 use windows::{
     UI::ViewManagement::{UIColorType, UISettings},
-    Win32::Graphics::Gdi::{DeleteObject, HGDIOBJ},
+    Win32::{
+        Graphics::Gdi::{DeleteObject, HGDIOBJ},
+        UI::{
+            Accessibility::{HCF_HIGHCONTRASTON, HIGHCONTRASTW},
+            WindowsAndMessaging::{
+                SPI_GETHIGHCONTRAST, SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS, SystemParametersInfoW,
+            },
+        },
+    },
 };
 use winsafe::*;
 
-pub const TRANSPARENCY_KEY_DARK: COLORREF = COLORREF::from_rgb(0, 0, 0);
-pub const TRANSPARENCY_KEY_LIGHT: COLORREF = COLORREF::from_rgb(255, 255, 255);
+use crate::config::{ColorOverrides, Config, FontStyle, FontWeight, HexColor};
+
+use super::text::{FALLBACK_FACES, is_rtl_locale};
 
+#[derive(Clone, PartialEq)]
 pub struct ColorSettings {
+    light_mode: bool,
     pub nonempty: COLORREF,
     pub focused: COLORREF,
+    /// Where the gradient of the focused indicator ends
+    pub focused_end: COLORREF,
     pub empty: COLORREF,
     pub monocle: COLORREF,
     pub maximized: COLORREF,
+    pub warning: COLORREF,
+    pub hover: COLORREF,
     pub foreground: COLORREF,
+    /// Outline or shadow behind text, contrasting with `foreground`
+    pub text_effect: COLORREF,
+    /// Opaque background of the whole bar, only used in high contrast mode
+    pub background: Option<COLORREF>,
+}
+
+/// Whether a Windows high contrast theme is active.
+fn is_high_contrast() -> bool {
+    let mut high_contrast = HIGHCONTRASTW {
+        cbSize: size_of::<HIGHCONTRASTW>() as u32,
+        ..Default::default()
+    };
+    let queried = unsafe {
+        SystemParametersInfoW(
+            SPI_GETHIGHCONTRAST,
+            high_contrast.cbSize,
+            Some(&mut high_contrast as *mut _ as _),
+            SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+        )
+    };
+    queried.is_ok() && high_contrast.dwFlags.contains(HCF_HIGHCONTRASTON)
 }
 
 impl ColorSettings {
-    pub fn new() -> anyhow::Result<Self> {
-        Self::get_colors_from_system()
+    pub fn new(overrides: &ColorOverrides) -> anyhow::Result<Self> {
+        let mut colors = if is_high_contrast() {
+            Self::get_high_contrast_colors()
+        } else {
+            Self::get_colors_from_system()?
+        };
+        colors.apply_overrides(overrides);
+        Ok(colors)
+    }
+
+    fn apply_overrides(&mut self, overrides: &ColorOverrides) {
+        let to_colorref = |color: HexColor| COLORREF::from_rgb(color.r, color.g, color.b);
+        let fields = [
+            (&mut self.nonempty, overrides.nonempty),
+            (&mut self.focused, overrides.focused),
+            (&mut self.focused_end, overrides.focused_end),
+            (&mut self.empty, overrides.empty),
+            (&mut self.monocle, overrides.monocle),
+            (&mut self.maximized, overrides.maximized),
+            (&mut self.warning, overrides.warning),
+            (&mut self.hover, overrides.hover),
+            (&mut self.foreground, overrides.foreground),
+            (&mut self.text_effect, overrides.text_effect),
+        ];
+        for (field, color) in fields {
+            if let Some(color) = color {
+                *field = to_colorref(color);
+            }
+        }
+    }
+
+    /// Colors `t` of the way from `self` to `other`, `t` being between 0 and 1
+    pub fn blend(&self, other: &Self, t: f32) -> Self {
+        let mix = |from: COLORREF, to: COLORREF| {
+            let channel =
+                |from: u8, to: u8| (from as f32 + (to as f32 - from as f32) * t).round() as u8;
+            COLORREF::from_rgb(
+                channel(from.GetRValue(), to.GetRValue()),
+                channel(from.GetGValue(), to.GetGValue()),
+                channel(from.GetBValue(), to.GetBValue()),
+            )
+        };
+
+        Self {
+            light_mode: other.light_mode,
+            nonempty: mix(self.nonempty, other.nonempty),
+            focused: mix(self.focused, other.focused),
+            focused_end: mix(self.focused_end, other.focused_end),
+            empty: mix(self.empty, other.empty),
+            monocle: mix(self.monocle, other.monocle),
+            maximized: mix(self.maximized, other.maximized),
+            warning: mix(self.warning, other.warning),
+            hover: mix(self.hover, other.hover),
+            foreground: mix(self.foreground, other.foreground),
+            text_effect: mix(self.text_effect, other.text_effect),
+            background: match (self.background, other.background) {
+                (Some(from), Some(to)) => Some(mix(from, to)),
+                (_, background) => background,
+            },
+        }
     }
 
     pub fn is_light_mode(&self) -> bool {
-        self.foreground.GetRValue() == 0
-            && self.foreground.GetBValue() == 0
-            && self.foreground.GetBValue() == 0
+        self.light_mode
     }
 
-    pub fn get_color_key(&self) -> COLORREF {
-        if self.is_light_mode() {
-            TRANSPARENCY_KEY_LIGHT
-        } else {
-            TRANSPARENCY_KEY_DARK
+    /// Colors of the active high contrast theme, drawn over an opaque
+    /// background since the theme's colors are only legible on its own
+    /// background.
+    fn get_high_contrast_colors() -> Self {
+        let background = GetSysColor(co::COLOR::BTNFACE);
+        let foreground = GetSysColor(co::COLOR::BTNTEXT);
+        let highlight = GetSysColor(co::COLOR::HIGHLIGHT);
+        let gray = GetSysColor(co::COLOR::GRAYTEXT);
+        let hot = GetSysColor(co::COLOR::HOTLIGHT);
+        let luminance = |color: COLORREF| {
+            299 * color.GetRValue() as u32
+                + 587 * color.GetGValue() as u32
+                + 114 * color.GetBValue() as u32
+        };
+
+        Self {
+            light_mode: luminance(background) > luminance(foreground),
+            nonempty: foreground,
+            focused: highlight,
+            focused_end: highlight,
+            empty: gray,
+            monocle: hot,
+            maximized: highlight,
+            warning: hot,
+            hover: gray,
+            foreground,
+            text_effect: background,
+            background: Some(background),
         }
     }
 
@@ -43,11 +160,20 @@ impl ColorSettings {
             true => COLORREF::from_rgb(0, 0, 0), 
             false => COLORREF::from_rgb(255, 255, 255), 
         };
+        let text_effect = match is_light_mode {
+            true => COLORREF::from_rgb(255, 255, 255),
+            false => COLORREF::from_rgb(0, 0, 0),
+        };
         let focused = match is_light_mode {
             true => ui_settings.GetColorValue(UIColorType::AccentDark1)?,
             false => ui_settings.GetColorValue(UIColorType::AccentLight2)?,
         };
         let focused = COLORREF::from_rgb(focused.R, focused.G, focused.B);
+        let focused_end = match is_light_mode {
+            true => ui_settings.GetColorValue(UIColorType::Accent)?,
+            false => ui_settings.GetColorValue(UIColorType::AccentLight3)?,
+        };
+        let focused_end = COLORREF::from_rgb(focused_end.R, focused_end.G, focused_end.B);
         let nonempty = match is_light_mode {
             true => COLORREF::from_rgb(150, 150, 150),
             false => COLORREF::from_rgb(100, 100, 100),
@@ -68,53 +194,159 @@ impl ColorSettings {
             false => COLORREF::from_rgb(10, 102, 194),
         };
 
+        let warning = match is_light_mode {
+            true => COLORREF::from_rgb(196, 120, 0),
+            false => COLORREF::from_rgb(252, 200, 0),
+        };
+
+        let hover = match is_light_mode {
+            true => COLORREF::from_rgb(225, 225, 225),
+            false => COLORREF::from_rgb(45, 45, 45),
+        };
+
         Ok(Self {
+            light_mode: is_light_mode,
             nonempty,
             focused,
+            focused_end,
             empty,
             monocle,
             maximized,
+            warning,
+            hover,
             foreground,
+            text_effect,
+            background: None,
         })
     }
 }
 
+/// Height of the label font at 96 DPI
+const FONT_HEIGHT: i32 = 24;
+
 pub struct Settings {
     pub colors: ColorSettings,
     pub font: HFONT,
-    pub transparent_brush: HBRUSH,
-    pub transparent_pen: HPEN,
+    /// Smaller font for the window-count badges
+    pub badge_font: HFONT,
+    /// `FALLBACK_FACES` at the size of `font`
+    fallbacks: Vec<HFONT>,
+    /// `FALLBACK_FACES` at the size of `badge_font`
+    badge_fallbacks: Vec<HFONT>,
+    /// Font of the focused workspace's label, if configured
+    focused_font: Option<HFONT>,
+    /// `FALLBACK_FACES` at the size of `focused_font`
+    focused_fallbacks: Vec<HFONT>,
+    /// Whether the bar is drawn mirrored, starting from the right
+    pub mirrored: bool,
+    /// Widths of workspace labels by text and whether they are in the focused
+    /// font, so a rename only measures the new label
+    pub label_widths: RefCell<HashMap<(String, bool), i32>>,
 }
 
 impl Settings {
-    pub fn new() -> anyhow::Result<Settings> {
-        let colors = ColorSettings::new()?;
+    pub fn new(config: &Config, dpi: u32) -> anyhow::Result<Settings> {
+        let colors = ColorSettings::new(&config.colors)?;
         let mut lf = LOGFONT::default();
-        lf.lfHeight = 24;
-        if colors.is_light_mode() {
-            lf.set_lfFaceName("Segoe UI Variable Text Semibold");
+        lf.lfHeight = FONT_HEIGHT * dpi as i32 / 96;
+        // ClearType's colored fringes can't be blended with per-pixel alpha
+        lf.lfQuality = co::QUALITY::ANTIALIASED;
+        let face = if colors.is_light_mode() {
+            "Segoe UI Variable Text Semibold"
         } else {
-            lf.set_lfFaceName("Segoe UI Variable Text");
-        }
+            "Segoe UI Variable Text"
+        };
+        lf.set_lfFaceName(face);
         let font = HFONT::CreateFontIndirect(&lf)?.leak();
-        let transparent_brush = HBRUSH::CreateSolidBrush(colors.get_color_key())?.leak();
-        let transparent_pen = HPEN::CreatePen(co::PS::SOLID, 1, colors.get_color_key())?.leak();
+        let fallbacks = Self::create_fallbacks(&mut lf)?;
+        lf.lfHeight = (14 * dpi / 96) as i32;
+        lf.set_lfFaceName(face);
+        let badge_font = HFONT::CreateFontIndirect(&lf)?.leak();
+        let badge_fallbacks = Self::create_fallbacks(&mut lf)?;
+
+        let (focused_font, focused_fallbacks) = if config.focused_font == FontStyle::default() {
+            (None, Vec::new())
+        } else {
+            let style = config.focused_font;
+            lf.lfHeight = style.size.unwrap_or(FONT_HEIGHT).max(1) * dpi as i32 / 96;
+            match style.weight {
+                Some(weight) => {
+                    lf.lfWeight = match weight {
+                        FontWeight::Light => co::FW::LIGHT,
+                        FontWeight::Normal => co::FW::NORMAL,
+                        FontWeight::Medium => co::FW::MEDIUM,
+                        FontWeight::Semibold => co::FW::SEMIBOLD,
+                        FontWeight::Bold => co::FW::BOLD,
+                    };
+                    // the weight replaces the one implied by the face name
+                    lf.set_lfFaceName("Segoe UI Variable Text");
+                }
+                None => lf.set_lfFaceName(face),
+            }
+            let focused_font = HFONT::CreateFontIndirect(&lf)?.leak();
+            (Some(focused_font), Self::create_fallbacks(&mut lf)?)
+        };
 
         Ok(Self {
             colors,
             font,
-            transparent_brush,
-            transparent_pen,
+            badge_font,
+            fallbacks,
+            badge_fallbacks,
+            focused_font,
+            focused_fallbacks,
+            mirrored: config.mirror_rtl && is_rtl_locale(),
+            label_widths: RefCell::new(HashMap::new()),
         })
     }
+
+    fn create_fallbacks(lf: &mut LOGFONT) -> anyhow::Result<Vec<HFONT>> {
+        let mut fonts = Vec::with_capacity(FALLBACK_FACES.len());
+        for face in FALLBACK_FACES {
+            lf.set_lfFaceName(face);
+            fonts.push(HFONT::CreateFontIndirect(lf)?.leak());
+        }
+        Ok(fonts)
+    }
+
+    /// Font of a workspace label
+    pub fn label_font(&self, focused: bool) -> &HFONT {
+        match &self.focused_font {
+            Some(focused_font) if focused => focused_font,
+            _ => &self.font,
+        }
+    }
+
+    /// Fallback fonts matching `font`, which must be one of ours
+    pub fn fallbacks_for(&self, font: &HFONT) -> &[HFONT] {
+        if font.ptr() == self.badge_font.ptr() {
+            &self.badge_fallbacks
+        } else if self
+            .focused_font
+            .as_ref()
+            .is_some_and(|focused_font| font.ptr() == focused_font.ptr())
+        {
+            &self.focused_fallbacks
+        } else {
+            &self.fallbacks
+        }
+    }
 }
 
 impl Drop for Settings {
     fn drop(&mut self) {
         unsafe {
             assert!(DeleteObject(HGDIOBJ(self.font.ptr())) != false);
-            assert!(DeleteObject(HGDIOBJ(self.transparent_brush.ptr())) != false);
-            assert!(DeleteObject(HGDIOBJ(self.transparent_pen.ptr())) != false);
+            assert!(DeleteObject(HGDIOBJ(self.badge_font.ptr())) != false);
+            let fonts = self
+                .fallbacks
+                .iter()
+                .chain(&self.badge_fallbacks)
+                .chain(&self.focused_font)
+                .chain(&self.focused_fallbacks);
+            for font in fonts {
+                assert!(DeleteObject(HGDIOBJ(font.ptr())) != false);
+            }
         }
     }
 }
@@ -0,0 +1,256 @@
+use winsafe::{prelude::*, *};
+
+use crate::config::{ColorOverrides, Config, HexColor};
+
+use super::{
+    Window,
+    dialog::{self, BN_CLICKED, EN_CHANGE, ID_CANCEL, ID_OK},
+    locale::Strings,
+};
+
+pub(super) const CLASS_NAME: &str = "komoswitch.settings";
+
+const ID_PADDING: u16 = 100;
+/// Id of the first color edit box, the others follow in `COLOR_FIELDS` order
+const ID_FIRST_COLOR: u16 = 101;
+
+type ColorField = fn(&mut ColorOverrides) -> &mut Option<HexColor>;
+
+/// Colors editable in the dialog, by their label; an empty box means the
+/// theme color
+const COLOR_FIELDS: &[(fn(&Strings) -> &'static str, ColorField)] = &[
+    (|strings| strings.color_focused, |colors| &mut colors.focused),
+    (|strings| strings.color_focused_end, |colors| &mut colors.focused_end),
+    (|strings| strings.color_nonempty, |colors| &mut colors.nonempty),
+    (|strings| strings.color_empty, |colors| &mut colors.empty),
+    (|strings| strings.monocle, |colors| &mut colors.monocle),
+    (|strings| strings.maximized, |colors| &mut colors.maximized),
+    (|strings| strings.color_warning, |colors| &mut colors.warning),
+    (|strings| strings.color_hover, |colors| &mut colors.hover),
+    (|strings| strings.color_foreground, |colors| &mut colors.foreground),
+    (|strings| strings.color_text_outline, |colors| &mut colors.text_effect),
+];
+
+// Dialog metrics at 96 DPI
+const MARGIN: i32 = 12;
+const ROW_HEIGHT: i32 = 30;
+const CONTROL_HEIGHT: i32 = 23;
+const LABEL_WIDTH: i32 = 90;
+const EDIT_WIDTH: i32 = 110;
+const BUTTON_WIDTH: i32 = 80;
+
+impl Window {
+    /// Opens the settings dialog, whose edits are previewed on the bar until
+    /// they are saved with OK or rolled back with Cancel.
+    pub(super) fn show_settings_dialog(&mut self) -> anyhow::Result<isize> {
+        if let Some(dialog) = &self.settings_dialog {
+            dialog.SetForegroundWindow();
+            return Ok(0);
+        }
+
+        let hinstance = HINSTANCE::GetModuleHandle(None)?;
+        dialog::register_class(&hinstance, CLASS_NAME, Self::settings_dialog_proc)?;
+
+        let rows = 1 + COLOR_FIELDS.len() as i32;
+        let client = SIZE {
+            cx: self.px(MARGIN * 3 + LABEL_WIDTH + EDIT_WIDTH),
+            cy: self.px(MARGIN * 2 + ROW_HEIGHT * (rows + 1)),
+        };
+        let dialog = dialog::create_window(
+            self,
+            &hinstance,
+            CLASS_NAME,
+            self.strings().settings_title,
+            co::WS_EX::default(),
+            client,
+            |size| {
+                Ok(POINT {
+                    x: (GetSystemMetrics(co::SM::CXSCREEN) - size.cx) / 2,
+                    y: (GetSystemMetrics(co::SM::CYSCREEN) - size.cy) / 2,
+                })
+            },
+        )?;
+        self.create_settings_controls(&dialog, &hinstance)?;
+        dialog.ShowWindow(co::SW::SHOW);
+        dialog.SetForegroundWindow();
+        self.settings_dialog = Some(dialog);
+        Ok(0)
+    }
+
+    fn create_settings_controls(&self, dialog: &HWND, hinst: &HINSTANCE) -> anyhow::Result<()> {
+        let control = |class: &str,
+                       text: &str,
+                       id: u16,
+                       style: co::WS,
+                       ex_style: co::WS_EX,
+                       pos: POINT,
+                       width: i32|
+         -> anyhow::Result<()> {
+            let size = SIZE {
+                cx: self.px(width),
+                cy: self.px(CONTROL_HEIGHT),
+            };
+            dialog::create_control(
+                dialog,
+                hinst,
+                class,
+                Some(text),
+                id,
+                style,
+                ex_style,
+                pos,
+                size,
+            )?;
+            Ok(())
+        };
+
+        let strings = self.strings();
+        let mut rows = vec![(strings.padding, ID_PADDING, self.config.padding.to_string())];
+        let mut colors = self.config.colors.clone();
+        for (idx, (label, field)) in COLOR_FIELDS.iter().enumerate() {
+            let text = field(&mut colors).map(|color| color.to_string());
+            rows.push((label(strings), ID_FIRST_COLOR + idx as u16, text.unwrap_or_default()));
+        }
+
+        let label_left = self.px(MARGIN);
+        let edit_left = self.px(MARGIN * 2 + LABEL_WIDTH);
+        for (row, (label, id, text)) in rows.iter().enumerate() {
+            let top = self.px(MARGIN + ROW_HEIGHT * row as i32);
+            control(
+                "STATIC",
+                label,
+                0,
+                co::WS::default(),
+                co::WS_EX::default(),
+                POINT {
+                    x: label_left,
+                    y: top + self.px(4),
+                },
+                LABEL_WIDTH,
+            )?;
+            control(
+                "EDIT",
+                text,
+                *id,
+                co::WS::TABSTOP,
+                co::WS_EX::CLIENTEDGE,
+                POINT { x: edit_left, y: top },
+                EDIT_WIDTH,
+            )?;
+        }
+
+        let buttons_top = self.px(MARGIN + ROW_HEIGHT * rows.len() as i32);
+        let cancel_left = self.px(MARGIN * 2 + LABEL_WIDTH + EDIT_WIDTH - BUTTON_WIDTH);
+        for (id, text, left) in [
+            (ID_OK, strings.ok, cancel_left - self.px(MARGIN + BUTTON_WIDTH)),
+            (ID_CANCEL, strings.cancel, cancel_left),
+        ] {
+            control(
+                "BUTTON",
+                text,
+                id,
+                co::WS::TABSTOP,
+                co::WS_EX::default(),
+                POINT {
+                    x: left,
+                    y: buttons_top,
+                },
+                BUTTON_WIDTH,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// The options currently typed into the dialog; invalid entries keep their
+    /// saved value.
+    fn read_settings_dialog(&self, dialog: &HWND) -> Config {
+        let mut config = self.config_file.effective();
+        let text = |id: u16| {
+            dialog
+                .GetDlgItem(id)
+                .and_then(|edit| edit.GetWindowText())
+                .map(|text| text.trim().to_string())
+                .unwrap_or_default()
+        };
+
+        if let Ok(padding) = text(ID_PADDING).parse() {
+            config.padding = padding;
+        }
+        for (idx, (_, field)) in COLOR_FIELDS.iter().enumerate() {
+            let value = text(ID_FIRST_COLOR + idx as u16);
+            if value.is_empty() {
+                *field(&mut config.colors) = None;
+            } else if let Ok(color) = value.parse() {
+                *field(&mut config.colors) = Some(color);
+            }
+        }
+        config
+    }
+
+    fn handle_settings_dialog_command(&mut self, id: u16, code: u16) -> anyhow::Result<isize> {
+        let Some(dialog) = self.settings_dialog.as_ref().map(|dialog| unsafe { dialog.raw_copy() })
+        else {
+            return Ok(0);
+        };
+
+        match (id, code) {
+            (ID_OK, BN_CLICKED) => {
+                let config = self.read_settings_dialog(&dialog);
+                self.config_file.set("padding", config.padding)?;
+                self.config_file.set("colors", config.colors)?;
+                self.close_settings_dialog();
+                self.apply_config()
+            }
+            (ID_CANCEL, BN_CLICKED) => self.cancel_settings_dialog(),
+            (_, EN_CHANGE) => {
+                self.config = self.read_settings_dialog(&dialog);
+                self.reload_settings()
+            }
+            _ => Ok(0),
+        }
+    }
+
+    /// Drops the previewed options and closes the dialog.
+    pub(super) fn cancel_settings_dialog(&mut self) -> anyhow::Result<isize> {
+        self.close_settings_dialog();
+        self.config = self.config_file.effective();
+        self.reload_settings()
+    }
+
+    fn close_settings_dialog(&mut self) {
+        if let Some(dialog) = self.settings_dialog.take() {
+            if let Err(err) = dialog.DestroyWindow() {
+                log::error!("Failed to close settings dialog: {err}");
+            }
+        }
+    }
+
+    fn handle_settings_dialog_message(
+        &mut self,
+        wm_any: msg::WndMsg,
+    ) -> Option<anyhow::Result<isize>> {
+        Some(match wm_any.msg_id {
+            co::WM::COMMAND => {
+                let (id, code) = dialog::command(wm_any);
+                self.handle_settings_dialog_command(id, code)
+            }
+            co::WM::CLOSE => self.cancel_settings_dialog(),
+            _ => return None,
+        })
+    }
+
+    extern "system" fn settings_dialog_proc(
+        hwnd: HWND,
+        msg: co::WM,
+        wparam: usize,
+        lparam: isize,
+    ) -> isize {
+        dialog::dialog_proc(
+            hwnd,
+            msg::WndMsg::new(msg, wparam, lparam),
+            "Settings",
+            |window, hwnd| window.settings_dialog.as_ref() == Some(hwnd),
+            Self::handle_settings_dialog_message,
+        )
+    }
+}
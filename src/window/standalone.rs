@@ -0,0 +1,188 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use windows::Win32::UI::Input::KeyboardAndMouse::ReleaseCapture;
+use winsafe::{prelude::*, *};
+
+use crate::config::ConfigFile;
+
+use super::Window;
+
+/// Kept next to the config rather than in it, as it changes with every move
+const POSITIONS_FILE: &str = "positions.json";
+
+/// Where the user moved a standalone bar to: its top-left corner relative to
+/// the monitor it was dropped on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SavedPosition {
+    /// Device name like `\\.\DISPLAY2`, which unlike the monitor handle
+    /// outlives komoswitch
+    monitor: String,
+    x: i32,
+    y: i32,
+}
+
+fn positions_path() -> Option<PathBuf> {
+    Some(ConfigFile::path()?.with_file_name(POSITIONS_FILE))
+}
+
+/// The saved positions, by the device name of the monitor each bar belongs to.
+fn load_positions() -> HashMap<String, SavedPosition> {
+    let Some(path) = positions_path() else {
+        return HashMap::new();
+    };
+    // missing until a bar is first moved
+    let Ok(contents) = std::fs::read_to_string(&path) else {
+        return HashMap::new();
+    };
+    serde_json::from_str(&contents).unwrap_or_else(|err| {
+        log::warn!("Ignoring the invalid {}: {err}", path.display());
+        HashMap::new()
+    })
+}
+
+fn save_positions(positions: &HashMap<String, SavedPosition>) -> anyhow::Result<()> {
+    let path = positions_path().context("Could not determine config path")?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, serde_json::to_string_pretty(positions)?)?;
+    Ok(())
+}
+
+/// Device name and screen rectangle of `monitor`.
+fn monitor_info(monitor: &HMONITOR) -> anyhow::Result<(String, RECT)> {
+    let info = monitor.GetMonitorInfo()?;
+    Ok((info.szDevice(), info.rcMonitor))
+}
+
+/// Screen rectangle of the monitor called `name`, if it is still connected.
+fn monitor_named(name: &str) -> Option<RECT> {
+    let mut found = None;
+    let result = HDC::NULL.EnumDisplayMonitors(None, |monitor, _, _| {
+        if let Ok((device, rect)) = monitor_info(&monitor) {
+            if device == name {
+                found = Some(rect);
+            }
+        }
+        true
+    });
+    if let Err(err) = result {
+        log::warn!("Failed to list the monitors: {err}");
+    }
+    found
+}
+
+/// `origin` moved just enough for a bar `size` large to lie within `area`.
+fn clamp(origin: POINT, size: SIZE, area: RECT) -> POINT {
+    POINT {
+        x: origin.x.min(area.right - size.cx).max(area.left),
+        y: origin.y.min(area.bottom - size.cy).max(area.top),
+    }
+}
+
+impl Window {
+    /// The monitor whose position the bar keeps: its own with
+    /// `per_monitor_bars`, otherwise the primary monitor it starts on.
+    fn home_monitor(&self) -> HMONITOR {
+        match &self.monitor {
+            Some(monitor) => unsafe { HMONITOR::from_ptr(monitor.handle as _) },
+            None => HMONITOR::MonitorFromPoint(POINT::default(), co::MONITOR::DEFAULTTOPRIMARY),
+        }
+    }
+
+    /// Where the bar was last moved to, on the monitor it was dropped on or,
+    /// if that one is gone, at the same offset into the bar's own monitor.
+    /// `standalone_origin` keeps it on screen either way.
+    fn saved_position(&self) -> anyhow::Result<Option<POINT>> {
+        let (home, home_rect) = monitor_info(&self.home_monitor())?;
+        let Some(saved) = load_positions().remove(&home) else {
+            return Ok(None);
+        };
+        let rect = monitor_named(&saved.monitor).unwrap_or_else(|| {
+            log::info!(
+                "Monitor {} is gone, restoring the bar on {home}",
+                saved.monitor
+            );
+            home_rect
+        });
+        Ok(Some(POINT {
+            x: rect.left + saved.x,
+            y: rect.top + saved.y,
+        }))
+    }
+
+    /// Picks up the position the user left the standalone bar at, unless
+    /// already known.
+    pub(super) fn restore_standalone_position(&mut self) {
+        if self.standalone_position.is_some() {
+            return;
+        }
+        self.standalone_position = self.saved_position().unwrap_or_else(|err| {
+            log::warn!("Failed to restore the bar's position: {err}");
+            None
+        });
+    }
+
+    /// Top-left corner of the standalone bar `size` large: where the user
+    /// moved it, kept on screen, or at the bottom of its monitor following
+    /// the configured anchor.
+    pub(super) fn standalone_origin(&self, size: SIZE) -> anyhow::Result<POINT> {
+        let Some(origin) = self.standalone_position else {
+            return Ok(POINT {
+                x: self.standalone_x(size.cx)?,
+                y: self.monitor_rect()?.bottom - size.cy,
+            });
+        };
+        let monitor = HMONITOR::MonitorFromPoint(origin, co::MONITOR::DEFAULTTONEAREST);
+        Ok(clamp(origin, size, monitor.GetMonitorInfo()?.rcMonitor))
+    }
+
+    /// Lets the user drag the standalone bar around by the space between its
+    /// buttons, as if by a title bar.
+    pub(super) fn start_standalone_move(&self) -> anyhow::Result<isize> {
+        // the button press mustn't keep the mouse from the move loop
+        unsafe { ReleaseCapture() }.ok();
+        let caption = co::HT::CAPTION.raw() as usize;
+        unsafe {
+            self.hwnd
+                .SendMessage(msg::WndMsg::new(co::WM::NCLBUTTONDOWN, caption, 0));
+        }
+        Ok(0)
+    }
+
+    /// Remembers where the user dropped the standalone bar, for this and the
+    /// next launch.
+    pub(super) fn handle_exit_size_move(&mut self) -> anyhow::Result<isize> {
+        if self.embedded {
+            return Ok(0);
+        }
+        let rect = self.hwnd.GetWindowRect()?;
+        self.standalone_position = Some(POINT {
+            x: rect.left,
+            y: rect.top,
+        });
+
+        let (home, _) = monitor_info(&self.home_monitor())?;
+        let dropped_on = self.hwnd.MonitorFromWindow(co::MONITOR::DEFAULTTONEAREST);
+        let (monitor, monitor_rect) = monitor_info(&dropped_on)?;
+        log::info!("Bar moved to ({}, {}) on {monitor}", rect.left, rect.top);
+        let mut positions = load_positions();
+        positions.insert(
+            home,
+            SavedPosition {
+                monitor,
+                x: rect.left - monitor_rect.left,
+                y: rect.top - monitor_rect.top,
+            },
+        );
+        if let Err(err) = save_positions(&positions) {
+            crate::errors::report(
+                &self.hwnd,
+                format!("Failed to save the bar's position: {err}"),
+            );
+        }
+        Ok(0)
+    }
+}
@@ -0,0 +1,515 @@
+use std::time::{Duration, Instant};
+
+use windows::Win32::Foundation::HWND as WIN32_HWND;
+use windows::Win32::Graphics::Gdi::{
+    GetMonitorInfoW, HMONITOR, MONITOR_DEFAULTTONEAREST, MONITORINFO, MonitorFromWindow,
+};
+use winsafe::{prelude::*, *};
+
+use crate::config::Anchor;
+
+use super::{
+    Window,
+    taskbar_icons::{self, TaskbarButtons},
+};
+
+/// Height of the bar while no taskbar is available to take it from, at 96 DPI
+pub(super) const STANDALONE_HEIGHT: i32 = 48;
+
+pub(super) const EMBED_RETRY_TIMER: usize = 3;
+const EMBED_RETRY_INTERVAL_MS: u32 = 5000;
+
+/// Waits for Explorer to finish sliding the taskbar's buttons to their new
+/// place after the alignment changed
+pub(super) const ALIGNMENT_TIMER: usize = 9;
+const ALIGNMENT_SETTLE_MS: u32 = 1000;
+
+/// How long a measurement of the taskbar's content is reused, as finding its
+/// buttons through UI Automation takes a while
+const CONTENT_MAX_AGE: Duration = Duration::from_secs(1);
+const SAVER_CONTENT_MAX_AGE: Duration = Duration::from_secs(5);
+
+pub(super) fn find_taskbar() -> anyhow::Result<HWND> {
+    let taskbar_atom = AtomStr::from_str("Shell_TrayWnd");
+    HWND::FindWindow(Some(taskbar_atom), None)?.ok_or(anyhow::anyhow!("Taskbar not found"))
+}
+
+/// Whether `taskbar` is docked to the left or right edge of its monitor.
+fn is_vertical(taskbar: &HWND) -> anyhow::Result<bool> {
+    let rect = taskbar.GetWindowRect()?;
+    Ok(rect.bottom - rect.top > rect.right - rect.left)
+}
+
+/// Size of `taskbar` across, its height or the width of a vertical one.
+fn thickness(taskbar: &HWND) -> anyhow::Result<i32> {
+    let rect = taskbar.GetClientRect()?;
+    Ok(if is_vertical(taskbar)? {
+        rect.right - rect.left
+    } else {
+        rect.bottom - rect.top
+    })
+}
+
+/// What the bar is placed relative to within the taskbar, measured along it
+/// in its client coordinates
+#[derive(Default)]
+pub struct TaskbarContent {
+    clock_end: Option<i32>,
+    icons_start: Option<i32>,
+    /// Start and end of the Start button, the widgets button and the
+    /// notification area up to the clock, which the bar must not cover
+    obstacles: Vec<(i32, i32)>,
+    measured: Option<Instant>,
+}
+
+impl TaskbarContent {
+    /// Whether the bar goes in the same place around `other`.
+    fn same_place(&self, other: &TaskbarContent) -> bool {
+        self.clock_end == other.clock_end
+            && self.icons_start == other.icons_start
+            && self.obstacles == other.obstacles
+    }
+}
+
+/// Start and end of `rect` along the taskbar at `taskbar_rect`, both in screen
+/// coordinates.
+fn span_along(rect: RECT, taskbar_rect: RECT, vertical: bool) -> (i32, i32) {
+    if vertical {
+        (rect.top - taskbar_rect.top, rect.bottom - taskbar_rect.top)
+    } else {
+        (rect.left - taskbar_rect.left, rect.right - taskbar_rect.left)
+    }
+}
+
+/// Screen rectangle of the first child of `parent` of window class `class`.
+fn child_rect(parent: &HWND, class: &str) -> Option<RECT> {
+    parent
+        .FindWindowEx(None, AtomStr::from_str(class), None)
+        .ok()??
+        .GetWindowRect()
+        .ok()
+}
+
+/// Measures `taskbar` around `buttons`, the ones UI Automation found in it.
+pub(super) fn measure_content(
+    taskbar: &HWND,
+    vertical: bool,
+    buttons: TaskbarButtons,
+) -> anyhow::Result<TaskbarContent> {
+    let taskbar_rect = taskbar.GetWindowRect()?;
+    let along = |rect| span_along(rect, taskbar_rect, vertical);
+
+    // only the primary taskbar has a notification area and clock
+    let tray = taskbar.FindWindowEx(None, AtomStr::from_str("TrayNotifyWnd"), None)?;
+    let clock_end = tray
+        .as_ref()
+        .and_then(|tray| child_rect(tray, "TrayClockWClass"))
+        .map(|clock| along(clock).1);
+    let tray = tray
+        .and_then(|tray| tray.GetWindowRect().ok())
+        .map(|tray| (along(tray).0, clock_end.unwrap_or(along(tray).1)));
+
+    // the Windows 10 Start button is a window of its own
+    let start = buttons.start.or_else(|| child_rect(taskbar, "Start"));
+    let obstacles = [start, buttons.widgets]
+        .into_iter()
+        .flatten()
+        .map(along)
+        .chain(tray)
+        .collect();
+
+    Ok(TaskbarContent {
+        clock_end,
+        icons_start: buttons.icons.map(|icons| along(icons).0),
+        obstacles,
+        measured: Some(Instant::now()),
+    })
+}
+
+/// Moves a bar `length` long starting at `x` into the closest stretch of
+/// `0..end` free of `obstacles` that holds it, and tells whether one did.
+fn avoid_obstacles(x: i32, length: i32, end: i32, obstacles: &[(i32, i32)]) -> (i32, bool) {
+    let mut obstacles = obstacles.to_vec();
+    obstacles.sort();
+    let mut gaps = Vec::new();
+    let mut gap_start = 0;
+    for (start, stop) in obstacles {
+        if start > gap_start {
+            gaps.push((gap_start, start));
+        }
+        gap_start = gap_start.max(stop);
+    }
+    if end > gap_start {
+        gaps.push((gap_start, end));
+    }
+
+    let distance = |(start, stop): (i32, i32)| (start - x).max(x + length - stop).max(0);
+    let fitting = gaps
+        .iter()
+        .copied()
+        .filter(|(start, stop)| stop - start >= length)
+        .min_by_key(|gap| distance(*gap));
+    match fitting {
+        Some((start, stop)) => (x.clamp(start, stop - length), true),
+        None => {
+            let closest = gaps.into_iter().min_by_key(|gap| distance(*gap));
+            (closest.map_or(x, |(start, _)| start), false)
+        }
+    }
+}
+
+/// `HMONITOR` of the monitor `hwnd` is on, comparable to komorebi's monitor ids
+fn monitor_of(hwnd: &HWND) -> isize {
+    unsafe { MonitorFromWindow(WIN32_HWND(hwnd.ptr()), MONITOR_DEFAULTTONEAREST) }.0 as isize
+}
+
+/// The taskbar on monitor `handle`: the primary taskbar, or one of the
+/// secondary taskbars Windows shows on the other monitors.
+fn find_taskbar_on(handle: isize) -> anyhow::Result<HWND> {
+    if let Ok(taskbar) = find_taskbar() {
+        if monitor_of(&taskbar) == handle {
+            return Ok(taskbar);
+        }
+    }
+
+    let mut after = None;
+    while let Some(taskbar) = HWND::NULL.FindWindowEx(
+        after.as_ref(),
+        AtomStr::from_str("Shell_SecondaryTrayWnd"),
+        None,
+    )? {
+        if monitor_of(&taskbar) == handle {
+            return Ok(taskbar);
+        }
+        after = Some(taskbar);
+    }
+
+    anyhow::bail!("No taskbar on monitor {handle:#x}")
+}
+
+impl Window {
+    /// The taskbar the bar belongs in, see `find_taskbar_on` for a bar pinned
+    /// to a monitor.
+    pub(super) fn find_taskbar(&self) -> anyhow::Result<HWND> {
+        match &self.monitor {
+            Some(monitor) => find_taskbar_on(monitor.handle),
+            None => find_taskbar(),
+        }
+    }
+
+    /// Screen area of the bar's monitor, the primary one unless pinned.
+    fn monitor_rect(&self) -> anyhow::Result<RECT> {
+        let Some(monitor) = &self.monitor else {
+            return Ok(RECT {
+                left: 0,
+                top: 0,
+                right: GetSystemMetrics(co::SM::CXSCREEN),
+                bottom: GetSystemMetrics(co::SM::CYSCREEN),
+            });
+        };
+
+        let mut info = MONITORINFO {
+            cbSize: size_of::<MONITORINFO>() as u32,
+            ..Default::default()
+        };
+        unsafe { GetMonitorInfoW(HMONITOR(monitor.handle as _), &mut info) }.ok()?;
+        Ok(RECT {
+            left: info.rcMonitor.left,
+            top: info.rcMonitor.top,
+            right: info.rcMonitor.right,
+            bottom: info.rcMonitor.bottom,
+        })
+    }
+
+    /// Start of a bar `length` long within `start..end` following the
+    /// configured anchor.
+    fn anchored(&self, start: i32, end: i32, length: i32, content: &TaskbarContent) -> i32 {
+        let offset = self.px(self.config.placement.x);
+        match self.config.placement.anchor {
+            Anchor::Left => start + offset,
+            Anchor::Center => (start + end - length) / 2 + offset,
+            Anchor::RightOfClock => match content.clock_end {
+                Some(clock) => clock + offset,
+                None => end - length - offset,
+            },
+            Anchor::Auto => match content.icons_start {
+                Some(icons) => (icons - offset - length).max(start),
+                None => start + offset,
+            },
+        }
+    }
+
+    /// Has the taskbar's content the bar is placed around measured, unless it
+    /// was measured just now or is being measured. Finding the buttons without
+    /// a window of their own is too slow to do on every move of the bar and is
+    /// done off the UI thread, see `handle_taskbar_content`, and only for the
+    /// `Auto` anchor or `check_overlap`.
+    pub(super) fn measure_taskbar_content(&self) {
+        if !self.embedded {
+            *self.taskbar_content.borrow_mut() = TaskbarContent::default();
+            self.overflow.set(false);
+            return;
+        }
+        let max_age = if self.saving_power() {
+            SAVER_CONTENT_MAX_AGE
+        } else {
+            CONTENT_MAX_AGE
+        };
+        let fresh = self
+            .taskbar_content
+            .borrow()
+            .measured
+            .is_some_and(|measured| measured.elapsed() < max_age);
+        if fresh || self.content_requested.get() {
+            return;
+        }
+
+        let taskbar = match self.find_taskbar() {
+            Ok(taskbar) => taskbar,
+            Err(err) => {
+                log::debug!("Failed to find the taskbar to measure: {err}");
+                return;
+            }
+        };
+        let placement = &self.config.placement;
+        if placement.anchor == Anchor::Auto || placement.check_overlap {
+            self.content_requested.set(true);
+            taskbar_icons::request_content(&taskbar, self.vertical, self.handle());
+            return;
+        }
+        match measure_content(&taskbar, self.vertical, TaskbarButtons::default()) {
+            Ok(content) => *self.taskbar_content.borrow_mut() = content,
+            Err(err) => log::debug!("Failed to measure the taskbar: {err}"),
+        }
+    }
+
+    /// Takes the taskbar's content measured for `measure_taskbar_content`,
+    /// moving the bar if it changed.
+    pub(super) fn handle_taskbar_content(
+        &self,
+        content: Option<TaskbarContent>,
+    ) -> anyhow::Result<isize> {
+        self.content_requested.set(false);
+        let Some(content) = content.filter(|_| self.embedded) else {
+            return Ok(0);
+        };
+        let moved = !content.same_place(&self.taskbar_content.borrow());
+        *self.taskbar_content.borrow_mut() = content;
+        if moved {
+            self.refresh()?;
+        }
+        Ok(0)
+    }
+
+    /// Reports the bar overlapping the taskbar's buttons once it starts to.
+    fn set_overflow(&self, overflow: bool) {
+        if overflow == self.overflow.replace(overflow) {
+            return;
+        }
+        if overflow {
+            crate::errors::report(&self.hwnd, self.strings().overflow.to_string());
+        } else {
+            log::info!("The bar fits in the taskbar again");
+        }
+    }
+
+    /// Where a bar `length` long goes in the taskbar, running down a vertical
+    /// one.
+    pub(super) fn bar_position(&self, length: i32) -> anyhow::Result<POINT> {
+        let taskbar = self.find_taskbar()?;
+        let rect = taskbar.GetClientRect()?;
+        let end = if self.vertical { rect.bottom } else { rect.right };
+        let content = self.taskbar_content.borrow();
+        let along = self.anchored(0, end, length, &content);
+        let (along, fits) = avoid_obstacles(along, length, end, &content.obstacles);
+        self.set_overflow(!fits);
+        let across = self.px(self.config.placement.y);
+        Ok(if self.vertical {
+            POINT {
+                x: across,
+                y: along,
+            }
+        } else {
+            POINT {
+                x: along,
+                y: across,
+            }
+        })
+    }
+
+    /// Size of the window for a bar `length` long and `thickness` across,
+    /// standing upright in a vertical taskbar.
+    pub(super) fn bar_size(&self, length: i32, thickness: i32) -> SIZE {
+        if self.vertical {
+            SIZE {
+                cx: thickness,
+                cy: length,
+            }
+        } else {
+            SIZE {
+                cx: length,
+                cy: thickness,
+            }
+        }
+    }
+
+    /// Client area of the bar as laid out, before it is rotated to stand in a
+    /// vertical taskbar.
+    pub(super) fn bar_rect(&self) -> anyhow::Result<RECT> {
+        let rect = self.hwnd.GetClientRect()?;
+        Ok(if self.vertical {
+            RECT {
+                left: rect.top,
+                top: rect.left,
+                right: rect.bottom,
+                bottom: rect.right,
+            }
+        } else {
+            rect
+        })
+    }
+
+    /// Prepares the bar for the orientation of `taskbar`, and returns its
+    /// thickness.
+    pub(super) fn fit_to_taskbar(&mut self, taskbar: &HWND) -> anyhow::Result<i32> {
+        let vertical = is_vertical(taskbar)?;
+        if vertical != self.vertical {
+            log::info!("Taskbar is {}", if vertical { "vertical" } else { "horizontal" });
+            self.vertical = vertical;
+        }
+        thickness(taskbar)
+    }
+
+    /// Left edge of the standalone bar on the screen, measured from the right
+    /// while the bar is mirrored.
+    pub(super) fn standalone_x(&self, width: i32) -> anyhow::Result<i32> {
+        let monitor = self.monitor_rect()?;
+        let x = self.anchored(monitor.left, monitor.right, width, &TaskbarContent::default());
+        Ok(if self.settings.mirrored {
+            monitor.left + monitor.right - width - x
+        } else {
+            x
+        })
+    }
+
+    /// Parents the bar to the taskbar, or falls back to a standalone topmost
+    /// window that keeps retrying in the background.
+    pub(super) fn embed_or_standalone(&mut self) -> anyhow::Result<()> {
+        match self.try_embed() {
+            Ok(()) => Ok(()),
+            Err(err) => {
+                log::warn!("Failed to embed into the taskbar, running standalone: {err}");
+                crate::eventlog::warning(&format!(
+                    "Failed to embed into the taskbar, running standalone: {err:#}"
+                ));
+                self.enter_standalone()
+            }
+        }
+    }
+
+    fn try_embed(&mut self) -> anyhow::Result<()> {
+        let taskbar = self.find_taskbar()?;
+        let thickness = self.fit_to_taskbar(&taskbar)?;
+        let width = self.get_window_width()?;
+
+        self.hwnd.SetParent(&taskbar)?;
+        self.hwnd.SetWindowPos(
+            HwndPlace::Place(co::HWND_PLACE::default()),
+            self.bar_position(width)?,
+            self.bar_size(width, thickness),
+            co::SWP::NOACTIVATE | co::SWP::NOZORDER,
+        )?;
+
+        self.embedded = true;
+        log::info!("Embedded into the taskbar");
+        self.apply_capture_exclusion();
+        Ok(())
+    }
+
+    /// Matches the height of the embedded bar to the taskbar, which changes
+    /// with the small taskbar setting or the DPI, and stands it upright when
+    /// the taskbar moves to a side of the screen. Painting derives all vertical
+    /// metrics from the client area, so a repaint takes care of the rest.
+    pub(super) fn sync_taskbar_height(&mut self) -> anyhow::Result<()> {
+        if !self.embedded {
+            return Ok(());
+        }
+
+        let rect = self.bar_rect()?;
+        let was_vertical = self.vertical;
+        let taskbar = self.find_taskbar()?;
+        let height = self.fit_to_taskbar(&taskbar)?;
+        if rect.bottom - rect.top == height && self.vertical == was_vertical {
+            return Ok(());
+        }
+
+        log::info!("Taskbar height changed to {height}");
+        self.hwnd.SetWindowPos(
+            HwndPlace::Place(co::HWND_PLACE::default()),
+            self.bar_position(rect.right - rect.left)?,
+            self.bar_size(rect.right - rect.left, height),
+            co::SWP::NOACTIVATE | co::SWP::NOZORDER,
+        )?;
+        Ok(())
+    }
+
+    fn enter_standalone(&mut self) -> anyhow::Result<()> {
+        let rect = self.bar_rect()?;
+        self.embedded = false;
+        self.vertical = false;
+
+        let size = SIZE {
+            cx: rect.right - rect.left,
+            cy: self.px(STANDALONE_HEIGHT),
+        };
+        self.restore_standalone_position();
+        self.hwnd.SetWindowPos(
+            HwndPlace::Place(co::HWND_PLACE::TOPMOST),
+            self.standalone_origin(size)?,
+            size,
+            co::SWP::NOACTIVATE,
+        )?;
+
+        self.apply_capture_exclusion();
+        self.hwnd
+            .SetTimer(EMBED_RETRY_TIMER, EMBED_RETRY_INTERVAL_MS, None)?;
+        Ok(())
+    }
+
+    /// The taskbar's buttons move to the center or the left, taking the free
+    /// space next to the Start button with them.
+    pub(super) fn handle_taskbar_alignment(&mut self, centered: bool) -> anyhow::Result<isize> {
+        if !self.embedded {
+            return Ok(0);
+        }
+        let alignment = if centered { "centered" } else { "left-aligned" };
+        log::info!("Repositioning the bar for the {alignment} taskbar");
+        self.hwnd.SetTimer(ALIGNMENT_TIMER, ALIGNMENT_SETTLE_MS, None)?;
+        Ok(0)
+    }
+
+    /// Places the bar around the taskbar's buttons where they ended up.
+    pub(super) fn handle_alignment_settled(&mut self) -> anyhow::Result<isize> {
+        self.hwnd.KillTimer(ALIGNMENT_TIMER)?;
+        self.taskbar_content.borrow_mut().measured = None;
+        self.refresh()
+    }
+
+    pub(super) fn handle_embed_retry(&mut self) -> anyhow::Result<isize> {
+        if self.embedded {
+            self.hwnd.KillTimer(EMBED_RETRY_TIMER)?;
+            return Ok(0);
+        }
+
+        match self.try_embed() {
+            Ok(()) => {
+                self.hwnd.KillTimer(EMBED_RETRY_TIMER)?;
+                self.refresh()
+            }
+            Err(err) => {
+                log::debug!("Taskbar still unavailable: {err}");
+                Ok(0)
+            }
+        }
+    }
+}
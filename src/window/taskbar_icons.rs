@@ -0,0 +1,169 @@
+use std::sync::{
+    OnceLock,
+    mpsc::{self, Sender},
+};
+
+use windows::Win32::{
+    Foundation::{HWND as WIN32_HWND, RECT as WIN32_RECT},
+    System::{
+        Com::{CLSCTX_INPROC_SERVER, COINIT_MULTITHREADED, CoCreateInstance, CoInitializeEx},
+        Variant::VARIANT,
+    },
+    UI::Accessibility::{
+        CUIAutomation, IUIAutomation, TreeScope_Descendants, UIA_AutomationIdPropertyId,
+        UIA_ClassNamePropertyId,
+    },
+};
+use winsafe::{prelude::*, *};
+
+use crate::msgs::BarUpdate;
+
+use super::{BarHandle, taskbar};
+
+/// UI Automation class of the app buttons in the Windows 11 taskbar
+const TASK_BUTTON_CLASS: &str = "Taskbar.TaskListButtonAutomationPeer";
+
+/// UI Automation id of the Start button, centered along with the app buttons
+const START_BUTTON_ID: &str = "StartButton";
+
+/// UI Automation id of the widgets button at the far end of the taskbar
+const WIDGETS_BUTTON_ID: &str = "WidgetsButton";
+
+/// Screen rectangles of the buttons Windows 11 draws in the taskbar, which
+/// have no window of their own
+#[derive(Default)]
+pub(super) struct TaskbarButtons {
+    /// Around the Start button and the app buttons, centered by default
+    pub icons: Option<RECT>,
+    pub start: Option<RECT>,
+    pub widgets: Option<RECT>,
+}
+
+/// A bar asking for the content of its taskbar to be measured
+struct Request {
+    taskbar: isize,
+    vertical: bool,
+    bar: BarHandle,
+}
+
+/// Measurements waiting for the UI Automation thread, see `request_content`
+static REQUESTS: OnceLock<Sender<Request>> = OnceLock::new();
+
+fn create_automation() -> Option<IUIAutomation> {
+    unsafe {
+        if let Err(err) = CoInitializeEx(None, COINIT_MULTITHREADED).ok() {
+            log::error!("Failed to initialize COM for UI Automation: {err}");
+            return None;
+        }
+        match CoCreateInstance(&CUIAutomation, None, CLSCTX_INPROC_SERVER) {
+            Ok(automation) => Some(automation),
+            Err(err) => {
+                log::error!("Failed to create the UI Automation client: {err}");
+                None
+            }
+        }
+    }
+}
+
+/// Answers the bars' requests one after the other. UI Automation must not
+/// run on the thread of a window it walks, which the bar's window is among,
+/// as it sends that window messages and waits for the answers.
+fn start_measuring() -> Sender<Request> {
+    let (sender, requests) = mpsc::channel::<Request>();
+    std::thread::spawn(move || {
+        let automation = create_automation();
+        for request in requests {
+            let taskbar = unsafe { HWND::from_ptr(request.taskbar as _) };
+            let buttons = match &automation {
+                Some(automation) => {
+                    unsafe { find_buttons(automation, &taskbar) }.unwrap_or_else(|err| {
+                        log::debug!("Failed to find the taskbar buttons: {err}");
+                        TaskbarButtons::default()
+                    })
+                }
+                None => TaskbarButtons::default(),
+            };
+            let content = taskbar::measure_content(&taskbar, request.vertical, buttons)
+                .inspect_err(|err| log::debug!("Failed to measure the taskbar: {err}"))
+                .ok();
+            request.bar.send(BarUpdate::TaskbarContent(content));
+        }
+    });
+    sender
+}
+
+/// Measures the content of `taskbar`, buttons without a window of their own
+/// included, off the UI thread. `bar` gets the result as a `BarUpdate`.
+pub(super) fn request_content(taskbar: &HWND, vertical: bool, bar: BarHandle) {
+    let request = Request {
+        taskbar: taskbar.ptr() as isize,
+        vertical,
+        bar,
+    };
+    if REQUESTS.get_or_init(start_measuring).send(request).is_err() {
+        log::error!("The taskbar measuring thread is gone");
+    }
+}
+
+fn union(rect: Option<RECT>, other: RECT) -> RECT {
+    match rect {
+        Some(rect) => RECT {
+            left: rect.left.min(other.left),
+            top: rect.top.min(other.top),
+            right: rect.right.max(other.right),
+            bottom: rect.bottom.max(other.bottom),
+        },
+        None => other,
+    }
+}
+
+unsafe fn find_buttons(
+    automation: &IUIAutomation,
+    taskbar: &HWND,
+) -> windows::core::Result<TaskbarButtons> {
+    unsafe {
+        let root = automation.ElementFromHandle(WIN32_HWND(taskbar.ptr()))?;
+        let by_id = |id: &str| {
+            automation.CreatePropertyCondition(UIA_AutomationIdPropertyId, &VARIANT::from(id))
+        };
+        let app_buttons = automation
+            .CreatePropertyCondition(UIA_ClassNamePropertyId, &VARIANT::from(TASK_BUTTON_CLASS))?;
+        let condition = automation.CreateOrCondition(
+            &automation.CreateOrCondition(&app_buttons, &by_id(START_BUTTON_ID)?)?,
+            &by_id(WIDGETS_BUTTON_ID)?,
+        )?;
+        let found = root.FindAll(TreeScope_Descendants, &condition)?;
+
+        let mut buttons = TaskbarButtons::default();
+        for idx in 0..found.Length()? {
+            let element = found.GetElement(idx)?;
+            let WIN32_RECT {
+                left,
+                top,
+                right,
+                bottom,
+            } = element.CurrentBoundingRectangle()?;
+            // buttons scrolled out of an overflowing taskbar have no extent
+            if right <= left || bottom <= top {
+                continue;
+            }
+            let rect = RECT {
+                left,
+                top,
+                right,
+                bottom,
+            };
+
+            let id = element.CurrentAutomationId()?;
+            if WIDGETS_BUTTON_ID == id {
+                buttons.widgets = Some(rect);
+                continue;
+            }
+            if START_BUTTON_ID == id {
+                buttons.start = Some(rect);
+            }
+            buttons.icons = Some(union(buttons.icons, rect));
+        }
+        Ok(buttons)
+    }
+}
@@ -0,0 +1,222 @@
+use windows::{
+    Win32::{
+        Globalization::{GetLocaleInfoEx, LOCALE_IREADINGLAYOUT, LOCALE_RETURN_NUMBER},
+        Graphics::Gdi::{GGI_MARK_NONEXISTING_GLYPHS, GetGlyphIndicesW, HDC as WIN32_HDC},
+    },
+    core::PCWSTR,
+};
+use winsafe::{prelude::*, *};
+
+use crate::config::TextEffect;
+
+use super::Window;
+
+/// Fonts tried, in order, for characters the bar's font has no glyph for
+pub(super) const FALLBACK_FACES: &[&str] = &[
+    "Segoe UI Emoji",
+    "Segoe UI Symbol",
+    "Microsoft YaHei UI",
+    "Yu Gothic UI",
+    "Malgun Gothic",
+];
+
+/// Glyph index `GetGlyphIndicesW` reports for missing characters
+const MISSING_GLYPH: u16 = 0xffff;
+
+/// Characters that modify the previous one and must stay in its run
+fn is_joiner(ch: char) -> bool {
+    matches!(ch, '\u{200d}' | '\u{fe00}'..='\u{fe0f}' | '\u{1f3fb}'..='\u{1f3ff}')
+}
+
+/// Whether `text` contains Hebrew, Arabic or another right-to-left script and
+/// should be read right to left.
+fn is_rtl(text: &str) -> bool {
+    text.chars().any(|ch| {
+        matches!(ch,
+            '\u{0590}'..='\u{08ff}'
+            | '\u{fb1d}'..='\u{fdff}'
+            | '\u{fe70}'..='\u{feff}'
+            | '\u{10800}'..='\u{10fff}'
+            | '\u{1e800}'..='\u{1efff}')
+    })
+}
+
+/// Whether the user's locale lays text out right to left.
+pub(super) fn is_rtl_locale() -> bool {
+    // with LOCALE_RETURN_NUMBER the value is written as a u32 over two units
+    let mut layout = [0u16; 2];
+    let written = unsafe {
+        GetLocaleInfoEx(
+            PCWSTR::null(), // the user's default locale
+            LOCALE_IREADINGLAYOUT | LOCALE_RETURN_NUMBER,
+            Some(&mut layout),
+        )
+    };
+    written != 0 && layout[0] == 1
+}
+
+fn has_glyph(hdc: &HDC, font: &HFONT, ch: char) -> bool {
+    let mut units = [0u16; 2];
+    let units = ch.encode_utf16(&mut units);
+    // glyph lookup only works on the basic multilingual plane
+    if units.len() > 1 {
+        return false;
+    }
+
+    let Ok(_old_font) = hdc.SelectObject(font) else {
+        return false;
+    };
+    let mut glyph = 0u16;
+    let converted = unsafe {
+        GetGlyphIndicesW(
+            WIN32_HDC(hdc.ptr()),
+            PCWSTR(units.as_ptr()),
+            1,
+            &mut glyph,
+            GGI_MARK_NONEXISTING_GLYPHS,
+        )
+    };
+    converted == 1 && glyph != MISSING_GLYPH
+}
+
+impl Window {
+    /// Splits `text` into runs that `font` or one of its fallbacks can draw.
+    fn text_runs<'a>(
+        &'a self,
+        hdc: &HDC,
+        font: &'a HFONT,
+        text: &str,
+    ) -> Vec<(String, &'a HFONT)> {
+        let fallbacks = self.settings.fallbacks_for(font);
+        if text.is_ascii() || fallbacks.is_empty() {
+            return vec![(text.to_string(), font)];
+        }
+
+        let mut runs: Vec<(String, &HFONT)> = Vec::new();
+        for ch in text.chars() {
+            if is_joiner(ch) {
+                if let Some((run, _)) = runs.last_mut() {
+                    run.push(ch);
+                    continue;
+                }
+            }
+
+            let chosen = if ch.len_utf16() > 1 {
+                // outside the BMP is almost always emoji, the first fallback
+                &fallbacks[0]
+            } else {
+                std::iter::once(font)
+                    .chain(fallbacks)
+                    .find(|candidate| has_glyph(hdc, candidate, ch))
+                    .unwrap_or(font)
+            };
+
+            match runs.last_mut() {
+                Some((run, run_font)) if run_font.ptr() == chosen.ptr() => run.push(ch),
+                _ => runs.push((ch.to_string(), chosen)),
+            }
+        }
+        runs
+    }
+
+    /// Draws the configured outline or shadow of `text`, to be covered by the
+    /// text itself.
+    fn draw_text_effect(
+        &self,
+        hdc: &HDC,
+        text: &str,
+        rect: RECT,
+        flags: co::DT,
+    ) -> anyhow::Result<()> {
+        let d = self.px(1).max(1);
+        let offsets: &[(i32, i32)] = match self.config.text_effect {
+            TextEffect::None => return Ok(()),
+            TextEffect::Outline => &[
+                (-d, -d),
+                (0, -d),
+                (d, -d),
+                (-d, 0),
+                (d, 0),
+                (-d, d),
+                (0, d),
+                (d, d),
+            ],
+            TextEffect::Shadow => &[(d, d)],
+        };
+
+        let text_color = hdc.SetTextColor(self.settings.colors.text_effect)?;
+        for (dx, dy) in offsets {
+            let offset_rect = RECT {
+                left: rect.left + dx,
+                right: rect.right + dx,
+                top: rect.top + dy,
+                bottom: rect.bottom + dy,
+            };
+            hdc.DrawText(text, offset_rect, flags)?;
+        }
+        hdc.SetTextColor(text_color)?;
+        Ok(())
+    }
+
+    /// Width of `text` drawn with `font` and its fallbacks.
+    pub(super) fn text_width(&self, hdc: &HDC, font: &HFONT, text: &str) -> anyhow::Result<i32> {
+        let mut width = 0;
+        for (run, run_font) in self.text_runs(hdc, font, text) {
+            let _old_font = hdc.SelectObject(run_font)?;
+            width += hdc.GetTextExtentPoint32(&run)?.cx;
+        }
+        Ok(width)
+    }
+
+    /// Draws a single line of `text` vertically centered in `rect`, switching
+    /// to fallback fonts for characters `font` can't display. Right-to-left
+    /// text is shaped by GDI and its runs are laid out from the right.
+    pub(super) fn draw_text(
+        &self,
+        hdc: &HDC,
+        font: &HFONT,
+        text: &str,
+        rect: RECT,
+        centered: bool,
+    ) -> anyhow::Result<()> {
+        let rtl = is_rtl(text);
+        let mut flags = co::DT::VCENTER | co::DT::SINGLELINE | co::DT::NOPREFIX | co::DT::NOCLIP;
+        if rtl {
+            flags |= co::DT::RTLREADING;
+        }
+        let runs = self.text_runs(hdc, font, text);
+
+        let mut widths = Vec::with_capacity(runs.len());
+        for (run, run_font) in &runs {
+            let _old_font = hdc.SelectObject(*run_font)?;
+            widths.push(hdc.GetTextExtentPoint32(run)?.cx);
+        }
+        let total: i32 = widths.iter().sum();
+
+        let mut left = if centered {
+            rect.left + (rect.right - rect.left - total) / 2
+        } else {
+            rect.left
+        };
+        if rtl {
+            left += total;
+        }
+        for ((run, run_font), width) in runs.iter().zip(widths) {
+            if rtl {
+                left -= width;
+            }
+            let _old_font = hdc.SelectObject(*run_font)?;
+            let run_rect = RECT {
+                left,
+                right: left + width,
+                ..rect
+            };
+            self.draw_text_effect(hdc, run, run_rect, co::DT::LEFT | flags)?;
+            hdc.DrawText(run, run_rect, co::DT::LEFT | flags)?;
+            if !rtl {
+                left += width;
+            }
+        }
+        Ok(())
+    }
+}
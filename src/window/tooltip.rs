@@ -0,0 +1,136 @@
+use windows::{
+    Win32::{
+        Foundation::HWND as WIN32_HWND,
+        UI::{
+            Controls::{
+                TTF_ABSOLUTE, TTF_TRACK, TTM_ADDTOOLW, TTM_GETBUBBLESIZE, TTM_SETMAXTIPWIDTH,
+                TTM_TRACKACTIVATE, TTM_TRACKPOSITION, TTM_UPDATETIPTEXTW, TTS_ALWAYSTIP,
+                TTS_NOPREFIX, TTTOOLINFOW,
+            },
+            WindowsAndMessaging::CW_USEDEFAULT,
+        },
+    },
+    core::PWSTR,
+};
+use winsafe::{prelude::*, *};
+
+use super::Window;
+
+/// The bar only ever has the one tool, the hovered workspace button
+const TOOL_ID: usize = 1;
+
+/// Widest the tooltip gets before wrapping, at 96 DPI
+const MAX_TOOLTIP_WIDTH: i32 = 400;
+
+fn send(tooltip: &HWND, msg: u32, wparam: usize, lparam: isize) -> isize {
+    unsafe { tooltip.SendMessage(msg::WndMsg::new(co::WM::from_raw(msg), wparam, lparam)) }
+}
+
+impl Window {
+    /// `text` must stay alive while the returned info is in use.
+    fn tool_info(&self, text: &mut [u16]) -> TTTOOLINFOW {
+        TTTOOLINFOW {
+            cbSize: size_of::<TTTOOLINFOW>() as u32,
+            uFlags: TTF_TRACK | TTF_ABSOLUTE,
+            hwnd: WIN32_HWND(self.hwnd.ptr()),
+            uId: TOOL_ID,
+            lpszText: PWSTR(text.as_mut_ptr()),
+            ..Default::default()
+        }
+    }
+
+    fn create_tooltip(&self) -> anyhow::Result<HWND> {
+        let tooltip = unsafe {
+            HWND::CreateWindowEx(
+                co::WS_EX::TOPMOST,
+                AtomStr::from_str("tooltips_class32"),
+                None,
+                co::WS::POPUP | co::WS::from_raw(TTS_ALWAYSTIP | TTS_NOPREFIX),
+                POINT {
+                    x: CW_USEDEFAULT,
+                    y: CW_USEDEFAULT,
+                },
+                SIZE {
+                    cx: CW_USEDEFAULT,
+                    cy: CW_USEDEFAULT,
+                },
+                Some(&self.hwnd),
+                IdMenu::None,
+                &HINSTANCE::GetModuleHandle(None)?,
+                None,
+            )?
+        };
+
+        let mut text = [0u16];
+        let info = self.tool_info(&mut text);
+        send(&tooltip, TTM_ADDTOOLW, 0, &info as *const _ as isize);
+        // a maximum width is what makes the tooltip honor line breaks
+        send(
+            &tooltip,
+            TTM_SETMAXTIPWIDTH,
+            0,
+            self.px(MAX_TOOLTIP_WIDTH) as isize,
+        );
+        Ok(tooltip)
+    }
+
+    /// Apps the static komorebi config routes to workspace `idx`.
+    fn workspace_rules(&self, idx: usize) -> &[String] {
+        let (monitor_idx, workspace_idx) = self.workspace_target(idx);
+        self.workspace_rules
+            .get(monitor_idx.unwrap_or(0))
+            .and_then(|workspaces| workspaces.get(workspace_idx))
+            .map_or(&[][..], Vec::as_slice)
+    }
+
+    /// Shows the titles of the windows on the hovered workspace above the bar,
+    /// followed by the apps komorebi's rules send there, or hides the tooltip
+    /// when the hovered workspace has neither.
+    pub(super) fn update_workspace_tooltip(&mut self) -> anyhow::Result<()> {
+        let hovered = self.hovered_workspace.filter(|_| self.config.window_tooltips);
+        let mut lines = hovered
+            .and_then(|idx| self.workspaces.elements().get(idx))
+            .map(crate::komo::workspace_window_titles)
+            .unwrap_or_default();
+        let rules = hovered.map_or(&[][..], |idx| self.workspace_rules(idx));
+        if !rules.is_empty() {
+            lines.push(format!("{} {}", rules.join(", "), self.strings().rules_here));
+        }
+
+        if lines.is_empty() {
+            if let Some(tooltip) = &self.tooltip {
+                let mut text = [0u16];
+                let info = self.tool_info(&mut text);
+                send(tooltip, TTM_TRACKACTIVATE, 0, &info as *const _ as isize);
+            }
+            return Ok(());
+        }
+
+        if self.tooltip.is_none() {
+            self.tooltip = Some(self.create_tooltip()?);
+        }
+        let Some(tooltip) = &self.tooltip else {
+            return Ok(());
+        };
+
+        let mut text: Vec<u16> = lines.join("\r\n").encode_utf16().chain(Some(0)).collect();
+        let info = self.tool_info(&mut text);
+        let info_ptr = &info as *const _ as isize;
+        send(tooltip, TTM_UPDATETIPTEXTW, 0, info_ptr);
+
+        // above the bar, or below it if the bar is at the top of the screen
+        let size = send(tooltip, TTM_GETBUBBLESIZE, 0, info_ptr);
+        let height = ((size >> 16) & 0xffff) as i32;
+        let cursor = GetCursorPos()?;
+        let bar = self.hwnd.GetWindowRect()?;
+        let y = if bar.top - height - self.px(4) >= 0 {
+            bar.top - height - self.px(4)
+        } else {
+            bar.bottom + self.px(4)
+        };
+        let position = ((y as u16 as isize) << 16) | cursor.x as u16 as isize;
+        send(tooltip, TTM_TRACKPOSITION, 0, position);
+        send(tooltip, TTM_TRACKACTIVATE, 1, info_ptr);
+        Ok(())
+    }
+}
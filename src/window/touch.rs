@@ -0,0 +1,77 @@
+use komorebi_client::{CycleDirection, SocketMessage};
+use windows::Win32::UI::{
+    Input::Pointer::GetPointerType,
+    WindowsAndMessaging::{POINTER_INPUT_TYPE, PT_TOUCH, WM_POINTERDOWN},
+};
+use winsafe::{prelude::*, *};
+
+use super::{Visibility, Window};
+
+// Gesture thresholds at 96 DPI
+
+/// Farthest a finger may move for the contact to still count as a tap
+const TAP_SLOP: i32 = 10;
+/// Shortest horizontal movement that counts as a swipe
+const SWIPE_DISTANCE: i32 = 48;
+/// How far outside a button a tap still hits it
+const TOUCH_HIT_SLOP: i32 = 12;
+
+impl Window {
+    /// Turns touch contacts into taps and swipes. Pen and mouse pointers are
+    /// left to the default handling, which turns them into mouse messages.
+    pub(super) fn handle_pointer(&mut self, p: msg::WndMsg) -> anyhow::Result<isize> {
+        let pointer_id = (p.wparam & 0xffff) as u32;
+        let mut kind = POINTER_INPUT_TYPE::default();
+        unsafe { GetPointerType(pointer_id, &mut kind)? };
+        if kind != PT_TOUCH {
+            return Ok(unsafe { self.hwnd.DefWindowProc(p) });
+        }
+
+        // in screen coordinates
+        let point = POINT {
+            x: (p.lparam & 0xffff) as u16 as i16 as i32,
+            y: ((p.lparam >> 16) & 0xffff) as u16 as i16 as i32,
+        };
+        if p.msg_id.raw() == WM_POINTERDOWN {
+            self.touch_start = Some(point);
+            return Ok(0);
+        }
+        let Some(start) = self.touch_start.take() else {
+            return Ok(0);
+        };
+
+        let dx = point.x - start.x;
+        let dy = point.y - start.y;
+        if dx.abs() >= self.px(SWIPE_DISTANCE) && dx.abs() > dy.abs() {
+            // the workspaces follow the finger, like pages on a phone
+            let direction = if dx < 0 {
+                CycleDirection::Next
+            } else {
+                CycleDirection::Previous
+            };
+            log::info!("Swiped {dx}px, cycling workspaces: {direction:?}");
+            komorebi_client::send_query(&SocketMessage::CycleFocusWorkspace(direction))?;
+        } else if dx.abs() <= self.px(TAP_SLOP) && dy.abs() <= self.px(TAP_SLOP) {
+            self.handle_tap(point)?;
+        }
+        Ok(0)
+    }
+
+    fn handle_tap(&mut self, point: POINT) -> anyhow::Result<isize> {
+        if self.visibility == Visibility::Collapsed {
+            return self.set_visibility(Visibility::Shown);
+        }
+
+        let rect = self.hwnd.GetWindowRect()?;
+        let client = POINT {
+            x: point.x - rect.left,
+            y: point.y - rect.top,
+        };
+        let Some(button) = self.workspace_near(client, self.px(TOUCH_HIT_SLOP))? else {
+            return Ok(0);
+        };
+        log::info!("Tapped workspace {}: {}", button.idx, button.label);
+        komorebi_client::send_query(&self.focus_workspace_message(button.idx))?;
+        Ok(0)
+    }
+}
@@ -0,0 +1,132 @@
+use windows::Win32::{
+    Foundation::HWND as WIN32_HWND,
+    UI::{
+        Shell::{
+            NIF_ICON, NIF_INFO, NIF_MESSAGE, NIF_TIP, NIIF_INFO, NIIF_WARNING, NIM_ADD, NIM_DELETE,
+            NIM_MODIFY, NIN_BALLOONHIDE, NIN_BALLOONTIMEOUT, NIN_BALLOONUSERCLICK, NOTIFYICONDATAW,
+            Shell_NotifyIconW,
+        },
+        WindowsAndMessaging::{IDI_APPLICATION, LoadIconW},
+    },
+};
+use winsafe::{prelude::*, *};
+
+use crate::msgs::TrayIcon;
+
+use super::Window;
+
+const TRAY_ICON_ID: u32 = 1;
+const TRAY_TOOLTIP: &str = "komoswitch";
+
+/// Copies `text` into a fixed size field of `NOTIFYICONDATAW`, cut off to
+/// leave room for the terminating null.
+fn copy_text(field: &mut [u16], text: &str) {
+    let text: Vec<u16> = text.encode_utf16().take(field.len() - 1).collect();
+    field[..text.len()].copy_from_slice(&text);
+}
+
+impl Window {
+    fn tray_icon_data(&self) -> NOTIFYICONDATAW {
+        NOTIFYICONDATAW {
+            cbSize: std::mem::size_of::<NOTIFYICONDATAW>() as u32,
+            hWnd: WIN32_HWND(self.hwnd.ptr()),
+            uID: TRAY_ICON_ID,
+            ..Default::default()
+        }
+    }
+
+    /// Fills in the tooltip, which carries the version warning if there is one.
+    fn set_tray_tooltip(&self, data: &mut NOTIFYICONDATAW) {
+        let text = match &self.version_mismatch {
+            Some(mismatch) => format!("{TRAY_TOOLTIP}\n{mismatch}"),
+            None => TRAY_TOOLTIP.to_string(),
+        };
+        copy_text(&mut data.szTip, &text);
+    }
+
+    pub(super) fn add_tray_icon(&mut self) -> anyhow::Result<()> {
+        let mut data = self.tray_icon_data();
+        data.uFlags = NIF_ICON | NIF_MESSAGE | NIF_TIP;
+        data.uCallbackMessage = TrayIcon::ID.raw();
+        data.hIcon = unsafe { LoadIconW(None, IDI_APPLICATION)? };
+        self.set_tray_tooltip(&mut data);
+
+        if !unsafe { Shell_NotifyIconW(NIM_ADD, &data) }.as_bool() {
+            anyhow::bail!("Failed to add tray icon");
+        }
+        self.tray_icon = true;
+        Ok(())
+    }
+
+    pub(super) fn update_tray_tooltip(&self) {
+        if !self.tray_icon {
+            return;
+        }
+        let mut data = self.tray_icon_data();
+        data.uFlags = NIF_TIP;
+        self.set_tray_tooltip(&mut data);
+        if !unsafe { Shell_NotifyIconW(NIM_MODIFY, &data) }.as_bool() {
+            log::error!("Failed to update the tray icon tooltip");
+        }
+    }
+
+    pub(super) fn remove_tray_icon(&mut self) {
+        if self.tray_icon {
+            unsafe { Shell_NotifyIconW(NIM_DELETE, &self.tray_icon_data()) };
+            self.tray_icon = false;
+            self.balloon_tray_icon = false;
+        }
+    }
+
+    /// Pops up a balloon from the notification area icon, shown as a toast by
+    /// Windows 10 and later. Without a tray icon one is added just for it and
+    /// removed once the balloon is gone.
+    pub(super) fn show_balloon(&mut self, title: &str, text: &str, warning: bool) {
+        if !self.tray_icon {
+            if let Err(err) = self.add_tray_icon() {
+                log::error!("Failed to add a tray icon for a notification: {err}");
+                return;
+            }
+            self.balloon_tray_icon = true;
+        }
+
+        let mut data = self.tray_icon_data();
+        data.uFlags = NIF_INFO;
+        data.dwInfoFlags = if warning { NIIF_WARNING } else { NIIF_INFO };
+        copy_text(&mut data.szInfoTitle, title);
+        copy_text(&mut data.szInfo, text);
+        if !unsafe { Shell_NotifyIconW(NIM_MODIFY, &data) }.as_bool() {
+            log::error!("Failed to show a notification");
+        }
+    }
+
+    pub(super) fn handle_tray_icon(&mut self, p: msg::WndMsg) -> anyhow::Result<isize> {
+        let event = p.lparam as u32 & 0xffff;
+        if matches!(
+            event,
+            NIN_BALLOONHIDE | NIN_BALLOONTIMEOUT | NIN_BALLOONUSERCLICK
+        ) {
+            if self.balloon_tray_icon {
+                self.remove_tray_icon();
+            }
+            return Ok(0);
+        }
+
+        match unsafe { co::WM::from_raw(event) } {
+            co::WM::LBUTTONUP => self.toggle_visibility(),
+            co::WM::RBUTTONUP => {
+                // required for the menu to close when clicking elsewhere
+                self.hwnd.SetForegroundWindow();
+
+                let cursor = GetCursorPos()?;
+                let rect = self.hwnd.GetWindowRect()?;
+                let coords = POINT {
+                    x: cursor.x - rect.left,
+                    y: cursor.y - rect.top,
+                };
+                self.show_context_menu(coords, None)
+            }
+            _ => Ok(0),
+        }
+    }
+}
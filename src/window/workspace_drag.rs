@@ -0,0 +1,187 @@
+use komorebi_client::SocketMessage;
+use windows::Win32::{
+    Foundation::HWND as WIN32_HWND,
+    UI::Input::KeyboardAndMouse::{ReleaseCapture, SetCapture},
+};
+use winsafe::{prelude::*, *};
+
+use super::{Window, click::CLICK_TIMER, layout::WorkspaceButton};
+
+/// A workspace button held down in the all-monitors mode, which can be
+/// dragged onto another monitor's workspaces to move it there.
+#[derive(Clone, Copy)]
+pub(super) struct WorkspaceDrag {
+    /// Button the drag started on
+    idx: usize,
+    /// Where the button was pressed, in client coordinates
+    start: POINT,
+    /// Cursor position along the bar, once it moved far enough to count as
+    /// a drag rather than a click
+    cursor: Option<i32>,
+    /// Button of another monitor the workspace would be dropped on
+    target: Option<usize>,
+}
+
+impl Window {
+    /// Index into `monitor_groups` of the monitor showing workspace `idx`.
+    fn group_of(&self, idx: usize) -> Option<usize> {
+        self.monitor_groups
+            .iter()
+            .position(|group| group.contains(idx))
+    }
+
+    /// Starts following the cursor after `button` was pressed, if the bar
+    /// shows more than one monitor to drag it between.
+    pub(super) fn arm_workspace_drag(&mut self, button: &WorkspaceButton, point: POINT) {
+        if self.monitor_groups.len() < 2 || button.new_workspace {
+            return;
+        }
+        self.workspace_drag = Some(WorkspaceDrag {
+            idx: button.idx,
+            start: point,
+            cursor: None,
+            target: None,
+        });
+        // keeps the drag going when the cursor leaves the bar
+        unsafe { SetCapture(WIN32_HWND(self.hwnd.ptr())) };
+    }
+
+    /// Button the dragged workspace would be dropped on, for highlighting it.
+    pub(super) fn workspace_drop_target(&self) -> Option<usize> {
+        self.workspace_drag.and_then(|drag| drag.target)
+    }
+
+    /// Follows the cursor while a button is held, returning whether it is
+    /// being dragged.
+    pub(super) fn handle_workspace_drag_move(&mut self, point: POINT) -> anyhow::Result<bool> {
+        let Some(mut drag) = self.workspace_drag else {
+            return Ok(false);
+        };
+        if drag.cursor.is_none() {
+            let moved_x = (point.x - drag.start.x).abs();
+            let moved_y = (point.y - drag.start.y).abs();
+            if moved_x < GetSystemMetrics(co::SM::CXDRAG)
+                && moved_y < GetSystemMetrics(co::SM::CYDRAG)
+            {
+                return Ok(false);
+            }
+            log::info!("Dragging workspace {}", drag.idx);
+            // a single click waiting for a double-click is now the drag
+            self.hwnd.KillTimer(CLICK_TIMER).ok();
+            self.pending_click = None;
+            self.hovered_workspace = None;
+        }
+
+        drag.cursor = Some(self.layout_x(point)?);
+        // komorebi can't reorder workspaces on the same monitor
+        drag.target = self
+            .workspace_at(point)?
+            .filter(|button| !button.new_workspace)
+            .map(|button| button.idx)
+            .filter(|idx| self.group_of(*idx) != self.group_of(drag.idx));
+        self.workspace_drag = Some(drag);
+        self.render()?;
+        Ok(true)
+    }
+
+    /// Moves the dragged workspace to the monitor it was dropped on, or with
+    /// `swap` (Ctrl held) trades it for the workspace it was dropped on.
+    pub(super) fn handle_workspace_drop(&mut self, swap: bool) -> anyhow::Result<isize> {
+        let Some(drag) = self.workspace_drag.take() else {
+            return Ok(0);
+        };
+        unsafe { ReleaseCapture() }.ok();
+        if drag.cursor.is_none() {
+            return Ok(0);
+        }
+        self.render()?;
+        let Some(target) = drag.target else {
+            return Ok(0);
+        };
+        let (Some(monitor_idx), workspace_idx) = self.workspace_target(target) else {
+            return Ok(0);
+        };
+
+        if swap {
+            // komorebi swaps with the workspace focused on the other monitor
+            komorebi_client::send_query(&SocketMessage::FocusMonitorWorkspaceNumber(
+                monitor_idx,
+                workspace_idx,
+            ))?;
+        }
+        komorebi_client::send_query(&self.focus_workspace_message(drag.idx))?;
+        if swap {
+            log::info!("Swapping workspace {} with workspace {target}", drag.idx);
+            komorebi_client::send_query(&SocketMessage::SwapWorkspacesToMonitorNumber(
+                monitor_idx,
+            ))?;
+        } else {
+            log::info!("Moving workspace {} to monitor {monitor_idx}", drag.idx);
+            komorebi_client::send_query(&SocketMessage::MoveWorkspaceToMonitorNumber(monitor_idx))?;
+        }
+        self.resync()
+    }
+
+    /// The bar lost the mouse to another window, e.g. a menu, mid-drag.
+    pub(super) fn handle_capture_changed(&mut self) -> anyhow::Result<isize> {
+        if let Some(drag) = self.workspace_drag.take() {
+            log::info!("Cancelled dragging workspace {}", drag.idx);
+            self.render()?;
+        }
+        Ok(0)
+    }
+
+    /// Draws a line under the button the workspace would be dropped on and the
+    /// dragged button's label following the cursor.
+    pub(super) fn paint_workspace_drag(
+        &self,
+        hdc: &HDC,
+        rect: RECT,
+        buttons: &[WorkspaceButton],
+    ) -> anyhow::Result<()> {
+        let Some(WorkspaceDrag {
+            idx,
+            cursor: Some(cursor),
+            target,
+            ..
+        }) = self.workspace_drag
+        else {
+            return Ok(());
+        };
+        let find = |idx: usize| buttons.iter().find(|button| button.idx == idx);
+
+        if let Some(target) = target.and_then(find) {
+            let line_rect = RECT {
+                left: target.left + self.px(4),
+                right: target.right() - self.px(4),
+                top: rect.bottom - self.px(6),
+                bottom: rect.bottom - self.px(4),
+            };
+            hdc.FillRect(
+                line_rect,
+                &*HBRUSH::CreateSolidBrush(self.settings.colors.focused)?,
+            )?;
+        }
+
+        let Some(dragged) = find(idx) else {
+            return Ok(());
+        };
+        let width = dragged.label_width + self.padding() * 2;
+        let ghost_rect = RECT {
+            left: cursor - width / 2,
+            right: cursor - width / 2 + width,
+            top: rect.top + self.px(4),
+            bottom: rect.bottom - self.px(4),
+        };
+        let brush = HBRUSH::CreateSolidBrush(self.settings.colors.hover)?;
+        let _old_brush = hdc.SelectObject(&*brush);
+        hdc.RoundRect(ghost_rect, self.border_radius())?;
+        let text_rect = RECT {
+            top: 0,
+            bottom: rect.bottom - self.px(10),
+            ..ghost_rect
+        };
+        self.draw_text(hdc, &self.settings.font, &dragged.label, text_rect, true)?;
+        Ok(())
+    }
+}